@@ -0,0 +1,57 @@
+//! Compare a full parse of a big deck with an incremental one, after the last
+//! slide is edited.
+//!
+//! Run it with `cargo bench --bench incremental`.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use slidy::parser::{parse_text, IncrementalParser};
+
+const SLIDES: usize = 2000;
+const ROUNDS: u32 = 20;
+
+/// A deck of `SLIDES` slides, whose last line is `last`.
+fn deck(last: &str) -> String {
+    let mut deck = String::from(":ge :bc white :fc black :sz 0.05 0.1\n");
+    for idx in 0..SLIDES {
+        deck.push_str(&format!(
+            ":sl\n:tb :fc red :sz 0.1 0.2\nSlide {idx}\n\
+             :tb :ps 0.1 0.3\nSome text\n  and some more\n"
+        ));
+    }
+    deck.push_str(last);
+    deck
+}
+
+/// How long `f` takes on average.
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        f();
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    let base = Path::new("");
+    let decks = [deck("One"), deck("Two")];
+
+    let full = time(|| {
+        for deck in &decks {
+            parse_text(deck, base).unwrap();
+        }
+    });
+
+    let mut parser = IncrementalParser::default();
+    parser.parse_text(&decks[0], base).unwrap();
+    let incremental = time(|| {
+        for deck in decks.iter().rev() {
+            parser.parse_text(deck, base).unwrap();
+        }
+    });
+
+    println!("{SLIDES} slides, the last one edited:");
+    println!("  full parse:        {:?}", full / 2);
+    println!("  incremental parse: {:?}", incremental / 2);
+}
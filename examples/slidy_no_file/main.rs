@@ -2,15 +2,15 @@ use std::thread::sleep;
 use std::time::Duration;
 
 use slidy::backends::sdl;
-use slidy::backends::SlidyBackend;
+use slidy::backends::{InputOutcome, SlidyBackend};
 
 mod slides;
 
 #[doc(hidden)]
 fn main() {
     // Init stuffs
-    let mut backend = sdl::Backend::new();
-    let mut context = backend.get_context();
+    let mut backend = sdl::Backend::new().unwrap();
+    let mut context = backend.get_context().unwrap();
 
     // Selected slide
     let mut rotations = 0.0;
@@ -25,7 +25,7 @@ fn main() {
         let slides = slides::prepare_slide(rotations, display_text, c1, c2);
         context.set_slides(slides);
 
-        if context.manage_inputs() {
+        if context.manage_inputs() == InputOutcome::Quit {
             break;
         };
         context.render();
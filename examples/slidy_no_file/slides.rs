@@ -1,6 +1,6 @@
 use slidy::slideshow::{
     Position, Section, SectionFigure, SectionMain, SectionText, Size, Slide,
-    Slideshow,
+    Slideshow, Visibility,
 };
 
 pub fn prepare_slide(rot: f32, text: String, c1: u8, c2: u8) -> Slideshow {
@@ -12,29 +12,60 @@ pub fn prepare_slide(rot: f32, text: String, c1: u8, c2: u8) -> Slideshow {
                     Section {
                         size: Some(Size { w: 0.04, h: 0.08 }),
                         position: Some(Position { x: 0.1, y: 0.1 }),
+                        free_axis: None,
                         sec_main: Some(SectionMain::Text(SectionText {
                             text,
                             color: Some((c1, 255 - c2, 100, 255).into()),
                             font: None,
+                            box_width: None,
+                            line_height: None,
+                            vcenter: false,
+                            is_code: false,
+                            lang: None,
+                            fit: None,
+                            columns: None,
+                            rotation: 0.0,
                         })),
+                        visibility: Visibility::Both,
+                        bg_color: None,
+                        tags: vec![],
                     },
                     Section {
                         size: Some(Size { w: 0.3, h: 0.3 }),
                         position: Some(Position { x: 0.2, y: 0.3 }),
+                        free_axis: None,
                         sec_main: Some(SectionMain::Figure(SectionFigure {
                             path: String::from("resources/star.jpg"),
                             rotation: rot,
+                            opacity: 1.0,
+                            ..SectionFigure::default()
                         })),
+                        visibility: Visibility::Both,
+                        bg_color: None,
+                        tags: vec![],
                     },
                     Section {
                         size: Some(Size { w: 0.2, h: 0.2 }),
                         position: Some(Position { x: 0.6, y: 0.6 }),
+                        free_axis: None,
                         sec_main: Some(SectionMain::Figure(SectionFigure {
                             path: String::from("resources/star.jpg"),
                             rotation: -rot + 369.3,
+                            opacity: 1.0,
+                            ..SectionFigure::default()
                         })),
+                        visibility: Visibility::Both,
+                        bg_color: None,
+                        tags: vec![],
                     },
                 ],
+                order: 0,
+                group: None,
+                counted: true,
+                grid: None,
+                tags: vec![],
+                gradient: None,
+                layout: None,
             }
         }],
         ..Default::default()
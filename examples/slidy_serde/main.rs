@@ -2,13 +2,13 @@ use std::thread::sleep;
 use std::time::Duration;
 
 use slidy::backends::sdl;
-use slidy::backends::SlidyBackend;
+use slidy::backends::{InputOutcome, SlidyBackend};
 
 #[doc(hidden)]
 fn main() {
     // Init stuffs
-    let mut backend = sdl::Backend::new();
-    let mut context = backend.get_context();
+    let mut backend = sdl::Backend::new().unwrap();
+    let mut context = backend.get_context().unwrap();
 
     // Set the slides.
     let file_content = include_str!("./resources/input_file.json");
@@ -17,7 +17,7 @@ fn main() {
 
     // Event loop
     loop {
-        if context.manage_inputs() {
+        if context.manage_inputs() == InputOutcome::Quit {
             break;
         };
         context.render();
@@ -1,9 +1,17 @@
 //! The provided Crossterm backend.
-use crate::slideshow::{Position, SectionMain, Slideshow};
+use super::{
+    keymap::{Action, Key, KeyMap},
+    History, RenderDefaults, Stopwatch,
+};
+use crate::slideshow::{
+    Gradient, Section, SectionMain, Slide, Slideshow, TextRun,
+};
 use crossterm::{
     cursor,
     event::{poll, read, Event, KeyCode, KeyEvent},
-    style::{Color, PrintStyledContent, Stylize},
+    style::{
+        Color, PrintStyledContent, ResetColor, SetBackgroundColor, Stylize,
+    },
     terminal, QueueableCommand,
 };
 
@@ -12,41 +20,65 @@ use std::{marker::PhantomData, time::Duration};
 use tracing::{debug, error, trace, warn};
 
 /// The backend.
-pub struct Backend {}
+pub struct Backend {
+    options: super::Options,
+}
 
 impl super::SlidyBackend for Backend {
-    fn get_context(&mut self) -> Box<dyn super::SlidyContext + '_> {
-        let ctx = self.internal_get_context();
-        Box::new(ctx)
+    fn get_context(
+        &mut self,
+    ) -> Result<Box<dyn super::SlidyContext + '_>, super::BackendError> {
+        let ctx = self.internal_get_context()?;
+        Ok(Box::new(ctx))
     }
 }
 
 impl Backend {
-    #[must_use]
     /// Create a new backend.
-    pub fn new() -> Self {
+    ///
+    /// # Errors
+    ///
+    /// If the terminal can't be switched to raw mode.
+    pub fn new() -> Result<Self, super::BackendError> {
+        Self::with_options(super::Options::default())
+    }
+
+    /// Create a new backend, tuned with the given options.
+    ///
+    /// # Errors
+    ///
+    /// If the terminal can't be switched to raw mode.
+    pub fn with_options(
+        options: super::Options,
+    ) -> Result<Self, super::BackendError> {
         debug!("Enable raw-mode.");
-        terminal::enable_raw_mode()
-            .expect("Raw mode is needed for input management.");
-        Self {}
+        terminal::enable_raw_mode().map_err(|e| {
+            format!("Raw mode is needed for input management: {e}")
+        })?;
+        Ok(Self { options })
     }
 
     /// Get the runnable context.
-    fn internal_get_context(&self) -> Context {
-        let _ = self;
+    fn internal_get_context(&self) -> Result<Context, super::BackendError> {
         let mut stdout = stdout();
         stdout
             .queue(cursor::Hide)
-            .expect("Unable to hide the cursor?");
-        stdout.flush().expect("Unable to flush?");
+            .and_then(std::io::Write::flush)
+            .map_err(|e| format!("Unable to hide the cursor: {e}"))?;
 
-        Context {
+        Ok(Context {
             slide_id: 0,
             slides: Slideshow::default(),
             _lifetime: PhantomData,
             stdout,
             slides_changed: true,
-        }
+            wrap: self.options.wrap,
+            linear: self.options.linear,
+            keymap: self.options.keymap.clone(),
+            render_defaults: self.options.render_defaults.clone(),
+            timer: Stopwatch::default(),
+            history: History::default(),
+        })
     }
 }
 
@@ -59,12 +91,6 @@ impl Drop for Backend {
     }
 }
 
-impl Default for Backend {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 /// The context, which contains the live data.
 /// This structure has to be used to update the slides in the event loop, or
 /// manage keypresses, and so on.
@@ -74,6 +100,42 @@ pub struct Context<'backend> {
     _lifetime: PhantomData<&'backend ()>,
     stdout: Stdout,
     slides_changed: bool,
+    /// If the navigation wraps around the first and the last slides.
+    wrap: bool,
+    /// If the chapters are ignored.
+    linear: bool,
+    /// The keys used to control the slideshow.
+    keymap: KeyMap,
+    /// Where the text goes, when the sections do not say it.
+    render_defaults: RenderDefaults,
+    /// The talk's timer: it is not shown, but it can be driven through the
+    /// context.
+    timer: Stopwatch,
+    /// The slides shown before the current one, to jump back to them.
+    history: History,
+}
+
+/// Translate the terminal's key, so that it can be looked up in the keymap.
+const fn get_key(code: KeyCode) -> Option<Key> {
+    let key = match code {
+        KeyCode::Char(' ') => Key::Space,
+        KeyCode::Char(c) => Key::Char(c.to_ascii_lowercase()),
+        KeyCode::Esc => Key::Escape,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::F(n) => Key::F(n),
+        _ => return None,
+    };
+    Some(key)
 }
 
 impl<'b> super::SlidyContext for Context<'b> {
@@ -83,48 +145,101 @@ impl<'b> super::SlidyContext for Context<'b> {
     }
 
     /// Manage the incoming events.
-    fn manage_inputs(&mut self) -> super::ShouldQuit {
+    fn manage_inputs(&mut self) -> super::InputOutcome {
+        let mut outcome = super::InputOutcome::Continue;
         while matches!(poll(Duration::ZERO), Ok(true)) {
             let evt = read().expect("Poll told us this should work.");
             trace!("{:#?}", evt);
-            match evt {
+            let action = match evt {
                 Event::Resize(..) => {
                     self.slides_changed = true;
+                    None
                 }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('q'),
-                    ..
-                }) => return true,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('n'),
-                    ..
-                }) => {
-                    self.slide_id =
-                        (self.slide_id + 1).min(self.slides.slides.len() - 1);
-                    self.slides_changed = true;
+                Event::Key(KeyEvent { code, .. }) => {
+                    get_key(code).and_then(|k| self.keymap.action(k))
                 }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('p'),
-                    ..
-                }) => {
-                    self.slide_id = if self.slide_id > 0 {
-                        self.slide_id - 1
-                    } else {
-                        0
-                    };
-                    self.slides_changed = true;
+                _ => None,
+            };
+            match action {
+                Some(Action::Quit) => return super::InputOutcome::Quit,
+                Some(Action::Reload) => outcome = super::InputOutcome::Reload,
+                Some(Action::Edit) => outcome = super::InputOutcome::Edit,
+                Some(Action::Next) if !self.linear => {
+                    self.go_to(super::next_chapter(
+                        &self.slides.slides,
+                        self.slide_id,
+                        self.wrap,
+                    ));
+                }
+                Some(Action::Prev) if !self.linear => {
+                    self.go_to(super::prev_chapter(
+                        &self.slides.slides,
+                        self.slide_id,
+                        self.wrap,
+                    ));
+                }
+                Some(Action::Next | Action::NextSub) => {
+                    self.go_to(super::next_index(
+                        self.slide_id,
+                        self.slides.slides.len(),
+                        self.wrap,
+                    ));
+                }
+                Some(Action::Prev | Action::PrevSub) => {
+                    self.go_to(super::prev_index(
+                        self.slide_id,
+                        self.slides.slides.len(),
+                        self.wrap,
+                    ));
+                }
+                Some(Action::Back) => {
+                    if let Some(idx) = self.history.pop() {
+                        self.show(idx);
+                    }
+                }
+                Some(Action::TimerStart) if self.timer.is_running() => {
+                    self.timer.stop();
                 }
+                Some(Action::TimerStart) => self.timer.start(),
+                Some(Action::TimerReset) => self.timer.reset(),
                 _ => {}
             }
         }
-        false
+        outcome
+    }
+
+    /// Give the terminal back as it was, with the cursor and without the raw
+    /// mode.
+    fn suspend(&mut self) {
+        if let Err(e) = terminal::disable_raw_mode() {
+            error!("Unable to disable the raw-mode: {:?}", e);
+        }
+        self.clear_all();
+        self.stdout
+            .queue(cursor::MoveTo(0, 0))
+            .and_then(|s| s.queue(cursor::Show))
+            .expect("Unable to show the cursor back?");
+        self.flush();
+    }
+
+    fn resume(&mut self) {
+        if let Err(e) = terminal::enable_raw_mode() {
+            error!("Unable to switch to raw-mode: {:?}", e);
+        }
+        if let Err(e) = self.stdout.queue(cursor::Hide) {
+            error!("Unable to hide the cursor: {:?}", e);
+        }
+        self.slides_changed = true;
+    }
+
+    fn owns_terminal(&self) -> bool {
+        true
     }
 
     /// Render the windows.
     fn render(&mut self) {
         if self.slides_changed {
             trace!("Rendering phase");
-            self.clear_all();
             let term_size = match terminal::size() {
                 Ok(v) => v,
                 Err(e) => {
@@ -134,54 +249,310 @@ impl<'b> super::SlidyContext for Context<'b> {
             };
             debug!("Considering slide {}", self.slide_id);
 
-            if let Some(slide) = self.slides.slides.get(self.slide_id) {
-                for sec in &slide.sections {
-                    // @TODO why is position 0. 0. if it is not there?
-                    let pos = sec
-                        .position
-                        .as_ref()
-                        .unwrap_or(&Position { x: 0.01, y: 0.01 });
-                    let x = (f32::from(term_size.0) * pos.x).ceil();
-                    assert!(0.0 <= x && x <= u16::MAX.into());
-
-                    #[allow(clippy::cast_possible_truncation)]
-                    #[allow(clippy::cast_sign_loss)]
-                    let x: u16 = x as u16;
-                    let y = (f32::from(term_size.1) * pos.y).ceil();
-                    assert!(0.0 <= y && y <= u16::MAX.into());
-
-                    #[allow(clippy::cast_possible_truncation)]
-                    #[allow(clippy::cast_sign_loss)]
-                    let mut y: u16 = y as u16;
-                    if let Some(SectionMain::Text(sec_text)) = &sec.sec_main {
-                        for chunk in sec_text.text.as_str().split('\n') {
-                            debug!("Writing {chunk} to [{x}, {y}]");
-                            self.stdout
-                                .queue(cursor::MoveTo(x, y))
-                                .expect("Unable to move the cursor?");
-                            // I should use the "style" defined in the slides instead of this one.
-                            let styled = chunk.with(Color::White);
-                            self.stdout
-                                .queue(PrintStyledContent(styled))
-                                .expect("Unable to write on the terminal?");
-                            y += 1;
-                        }
+            let placeholder = Slide::placeholder();
+            let slide =
+                self.slides.slide_at(self.slide_id).unwrap_or_else(|| {
+                    warn!("There are no slides to show!");
+                    &placeholder
+                });
+            let background = slide_background(&self.slides, slide);
+            clear_screen(&mut self.stdout, background);
+            // The text is white, unless the slide's color says otherwise.
+            let font_col = background.map_or(Color::White, |bg| {
+                term_color(crate::slideshow::Color::readable_on(bg))
+            });
+            for placed in layout(slide, term_size, &self.render_defaults) {
+                debug!(
+                    "Writing {} to [{}, {}]",
+                    placed.text, placed.x, placed.y
+                );
+                self.stdout
+                    .queue(cursor::MoveTo(placed.x, placed.y))
+                    .expect("Unable to move the cursor?");
+                // I should use the "style" defined in the slides instead of this one.
+                let styled = match (placed.bg, background) {
+                    (Some(bg), _) => {
+                        placed.text.with(Color::White).on(term_color(bg))
                     }
-                }
-            } else {
-                warn!("There are no slides to show!");
+                    (None, Some(bg)) => {
+                        placed.text.with(font_col).on(term_color(bg))
+                    }
+                    (None, None) => placed.text.with(Color::White),
+                };
+                self.stdout
+                    .queue(PrintStyledContent(styled))
+                    .expect("Unable to write on the terminal?");
             }
             self.flush();
         }
         self.slides_changed = false;
     }
+
+    fn current_index(&self) -> usize {
+        self.slide_id
+    }
+
+    fn set_slide_index(&mut self, idx: usize) {
+        let idx = idx.min(self.slides.slides.len().saturating_sub(1));
+        self.go_to(idx);
+    }
+
+    fn timer_start(&mut self) {
+        self.timer.start();
+    }
+
+    fn timer_stop(&mut self) {
+        self.timer.stop();
+    }
+
+    fn timer_reset(&mut self) {
+        self.timer.reset();
+    }
+
+    fn timer_elapsed(&self) -> Option<Duration> {
+        Some(self.timer.elapsed())
+    }
+}
+
+/// The color of the box behind the code, when the section does not set one.
+const CODE_BG: crate::slideshow::Color = crate::slideshow::Color {
+    r: 0x30,
+    g: 0x30,
+    b: 0x30,
+    a: 0xff,
+};
+
+/// A line of text, where the terminal shows it.
+#[derive(Debug)]
+struct Placed {
+    x: u16,
+    y: u16,
+    text: String,
+    /// The color behind the text, if any.
+    bg: Option<crate::slideshow::Color>,
+}
+
+/// Clear the screen, filling it with the `background` color, if any.
+fn clear_screen(
+    stdout: &mut Stdout,
+    background: Option<crate::slideshow::Color>,
+) {
+    if let Some(bg) = background {
+        stdout
+            .queue(SetBackgroundColor(term_color(bg)))
+            .expect("Unable to set the background?");
+    }
+    stdout
+        .queue(terminal::Clear(terminal::ClearType::All))
+        .expect("Unable to clear the screen?");
+    if background.is_some() {
+        stdout
+            .queue(ResetColor)
+            .expect("Unable to reset the colors?");
+    }
+}
+
+/// The color the `slide` is shown on, if the slides set one. A gradient is
+/// shown as the color in its middle, since each cell has a single color.
+fn slide_background(
+    slides: &Slideshow,
+    slide: &Slide,
+) -> Option<crate::slideshow::Color> {
+    let middle = |gradient: Gradient| gradient.color_at(0.5);
+    // The slide's own background wins, be it a color or a gradient, as in
+    // the other backends.
+    slide
+        .bg_color
+        .or_else(|| slide.gradient.map(middle))
+        .or_else(|| slides.gradient.map(middle))
+        .or(slides.bg_col)
+}
+
+/// The color of the terminal for `color`: the alpha is ignored.
+const fn term_color(color: crate::slideshow::Color) -> Color {
+    Color::Rgb {
+        r: color.r,
+        g: color.g,
+        b: color.b,
+    }
+}
+
+/// The cell at `pos` (from 0 to 1) along a side of `size` cells.
+fn to_cell(size: u16, pos: f32) -> u16 {
+    let cell = (f32::from(size) * pos).ceil().clamp(0.0, u16::MAX.into());
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    let cell = cell as u16;
+    cell
+}
+
+/// Where the lines of `slide` go in a terminal of `cols` x `rows` cells, as
+/// the audience sees it. What does not fit in the terminal is cut, so that
+/// nothing wraps around or scrolls it. The sections without a position or a
+/// size get the ones in `defaults`.
+fn layout(
+    slide: &Slide,
+    (cols, rows): (u16, u16),
+    defaults: &RenderDefaults,
+) -> Vec<Placed> {
+    let mut placed = vec![];
+    let slide = slide.place_on_grid();
+    // The terminal is what the audience sees.
+    let visible = slide.sections.iter().filter(|s| s.visibility.on_main());
+    for sec in visible {
+        let sec_text = match &sec.sec_main {
+            Some(SectionMain::Text(sec_text)) => sec_text,
+            // The images can't be shown, but their captions can.
+            Some(SectionMain::Figure(fig)) => {
+                let caption = fig.caption.as_deref().and_then(|caption| {
+                    place_caption(sec, caption, (cols, rows), defaults)
+                });
+                placed.extend(caption);
+                continue;
+            }
+            None => continue,
+        };
+        let pos = sec
+            .position_or(&defaults.text_position)
+            .unwrap_or_else(|| defaults.text_position.clone());
+        let (x, mut y) = (to_cell(cols, pos.x), to_cell(rows, pos.y));
+        // The rows can't be split: a taller line height just leaves some
+        // blank rows.
+        #[allow(clippy::cast_possible_truncation)]
+        #[allow(clippy::cast_sign_loss)]
+        let step = sec_text
+            .line_height
+            .map_or(1, |h| h.round().clamp(1.0, u16::MAX.into()) as u16);
+        // The lines are padded to the longest one, so that the background
+        // makes a box.
+        let text = sec_text.text.trim_end_matches('\n');
+        let width = text
+            .split('\n')
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or(0);
+        // The code always has a box behind it.
+        let bg = sec.bg_color.or_else(|| sec_text.is_code.then_some(CODE_BG));
+        let room = usize::from(cols.saturating_sub(x));
+        for chunk in text.split('\n') {
+            if y >= rows || room == 0 {
+                break;
+            }
+            // Images can't be shown, so we print their path.
+            let chunk: String = TextRun::split(chunk)
+                .into_iter()
+                .map(|run| match run {
+                    TextRun::Text(text) => text,
+                    TextRun::Image { path, .. } => path,
+                })
+                .collect();
+            let chunk = match bg {
+                Some(_) => format!("{chunk:width$}"),
+                None => chunk,
+            };
+            placed.push(Placed {
+                x,
+                y,
+                text: chunk.chars().take(room).collect(),
+                bg,
+            });
+            y = y.saturating_add(step);
+        }
+    }
+    placed
+}
+
+/// Where the `caption` of the figure in `sec` goes: centered below the place
+/// the image would take.
+fn place_caption(
+    sec: &Section,
+    caption: &str,
+    (cols, rows): (u16, u16),
+    defaults: &RenderDefaults,
+) -> Option<Placed> {
+    let pos = sec
+        .position_or(&defaults.figure_position)
+        .unwrap_or_else(|| defaults.figure_position.clone());
+    let size = sec.size.as_ref().unwrap_or(&defaults.figure_size);
+    let y = to_cell(rows, pos.y + size.h);
+    let x = to_cell(cols, pos.x);
+    if y >= rows || x >= cols {
+        return None;
+    }
+    let width = usize::from(to_cell(cols, size.w));
+    let len = caption.chars().count();
+    let x = x.saturating_add(
+        u16::try_from(width.saturating_sub(len) / 2).unwrap_or(u16::MAX),
+    );
+    let room = usize::from(cols.saturating_sub(x));
+    Some(Placed {
+        x,
+        y,
+        text: caption.chars().take(room).collect(),
+        bg: None,
+    })
+}
+
+/// Draw the slide at `idx` in a grid of `cols` x `rows` characters.
+///
+/// The grid is what the terminal would show, without the colors: each string
+/// is a row, from top to bottom. If there is no slide at `idx`, the grid is
+/// empty.
+#[must_use]
+pub fn render_to_grid(
+    slides: &Slideshow,
+    idx: usize,
+    cols: u16,
+    rows: u16,
+) -> Vec<String> {
+    render_to_grid_with(slides, idx, (cols, rows), &RenderDefaults::default())
+}
+
+/// Like [`render_to_grid`], with other `defaults` than the built-in ones.
+#[must_use]
+pub fn render_to_grid_with(
+    slides: &Slideshow,
+    idx: usize,
+    (cols, rows): (u16, u16),
+    defaults: &RenderDefaults,
+) -> Vec<String> {
+    let mut grid = vec![vec![' '; usize::from(cols)]; usize::from(rows)];
+    let placeholder = Slide::placeholder();
+    let slide = slides.slide_at(idx).unwrap_or(&placeholder);
+    for placed in layout(slide, (cols, rows), defaults) {
+        let row = grid[usize::from(placed.y)]
+            .iter_mut()
+            .skip(usize::from(placed.x));
+        for (cell, ch) in row.zip(placed.text.chars()) {
+            *cell = ch;
+        }
+    }
+    grid.into_iter()
+        .map(|row| row.into_iter().collect())
+        .collect()
 }
 
 impl Context<'_> {
+    /// Show the slide at `idx`, remembering the current one in the history.
+    fn go_to(&mut self, idx: usize) {
+        if idx != self.slide_id {
+            self.history.push(self.slide_id);
+            self.show(idx);
+        }
+    }
+
+    /// Show the slide at `idx`, or the last one if there are not so many
+    /// slides.
+    fn show(&mut self, idx: usize) {
+        let idx = idx.min(self.slides.slides.len().saturating_sub(1));
+        if idx != self.slide_id {
+            self.slide_id = idx;
+            self.slides_changed = true;
+        }
+    }
+
     fn clear_all(&mut self) {
-        self.stdout
-            .queue(terminal::Clear(terminal::ClearType::All))
-            .expect("Unable to clear the screen?");
+        clear_screen(&mut self.stdout, None);
     }
 
     fn flush(&mut self) {
@@ -197,3 +568,92 @@ impl Drop for Context<'_> {
         self.flush();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::Path;
+
+    fn grid(text: &str, cols: u16, rows: u16) -> Vec<String> {
+        let slides = crate::parser::parse_text(text, Path::new("")).unwrap();
+        render_to_grid(&slides, 0, cols, rows)
+    }
+
+    #[test]
+    fn test_render_to_grid() {
+        let rows = grid(":sl\n:tb :ps 0.5 0.5\nHi\nyou", 10, 4);
+        assert_eq!(
+            rows,
+            ["", "", "     Hi", "     you"].map(|r| format!("{r:10}"))
+        );
+        // Without a position, the text starts at the top left, one cell in.
+        let rows = grid(":sl\n:tb\nHi", 4, 2);
+        assert_eq!(rows, ["    ", " Hi "]);
+        // The taller lines leave some blank rows.
+        let rows = grid(":sl\n:tb :ps 0 0 :lh 2\na\nb", 1, 3);
+        assert_eq!(rows, ["a", " ", "b"]);
+        // The blank lines are kept.
+        let rows = grid(":sl\n:tb\na\n\nb", 2, 5);
+        assert_eq!(rows, ["  ", " a", "  ", " b", "  "]);
+        // The presenter's sections are not shown.
+        let rows = grid(":sl\n:tb :only presenter\nHi", 4, 2);
+        assert_eq!(rows, ["    ", "    "]);
+        // There is no such slide.
+        assert_eq!(render_to_grid(&Slideshow::default(), 3, 2, 1), ["  "]);
+    }
+
+    #[test]
+    fn test_render_to_grid_overflow() {
+        // The lines are cut at the right edge, instead of wrapping.
+        let rows = grid(":sl\n:tb :ps 0.5 0\nHello", 6, 2);
+        assert_eq!(rows, ["   Hel", "      "]);
+        // The lines below the bottom are not written.
+        let rows = grid(":sl\n:tb :ps 0 0.5\na\nb\nc", 1, 2);
+        assert_eq!(rows, [" ", "a"]);
+        // Out of the terminal, nothing is shown.
+        let rows = grid(":sl\n:tb :ps 1 1\nHi", 2, 2);
+        assert_eq!(rows, ["  ", "  "]);
+        assert_eq!(grid(":sl\n:tb\nHi", 0, 0), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_render_to_grid_defaults() {
+        let slides = crate::parser::parse_text(":sl\n:tb\nHi", Path::new(""));
+        let mut defaults = RenderDefaults::default();
+        defaults.set_str("text-position=0.5,0.5").unwrap();
+        let rows = render_to_grid_with(&slides.unwrap(), 0, (4, 2), &defaults);
+        assert_eq!(rows, ["    ", "  Hi"]);
+    }
+
+    #[test]
+    fn test_render_to_grid_caption() {
+        // The figure takes from 0 to 0.5: the caption is centered below it.
+        let text = ":sl\n:fg resources/star.jpg :ps 0 0 :sz 0.5 0.5 :cap Hi";
+        let rows = grid(text, 10, 4);
+        assert_eq!(rows, ["", "", " Hi", ""].map(|r| format!("{r:10}")));
+    }
+
+    #[test]
+    fn test_slide_background() {
+        let background = |text: &str| {
+            let slides = crate::parser::parse_text(text, Path::new(""));
+            let slides = slides.unwrap();
+            slide_background(&slides, &slides.slides[0]).map(|c| (c.r, c.g))
+        };
+        assert_eq!(background(":sl\n:tb\nHi"), None);
+        let gradient = ":bc gradient 0 0 0 255 255 100 0 255";
+        let deck = format!(":ge {gradient}\n:sl\n:tb\nHi");
+        assert_eq!(background(&deck), Some((128, 50)));
+        // The slide's own color comes first.
+        let text = format!(":ge {gradient}\n:sl :bc 10 11 12 255\n:tb\nHi");
+        assert_eq!(background(&text), Some((10, 11)));
+        let text = format!(":ge :bc 10 11 12 255\n:sl {gradient}\n");
+        assert_eq!(background(&text), Some((128, 50)));
+    }
+
+    #[test]
+    fn test_render_to_grid_no_slides() {
+        let rows = grid("# just a comment", 20, 3);
+        assert_eq!(rows[2], format!("{:20}", "        No slides"));
+    }
+}
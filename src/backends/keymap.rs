@@ -0,0 +1,279 @@
+/*!
+The key bindings, shared by all the backends.
+
+Each backend translates the keys it receives into a [`Key`], and then looks
+up the [`Action`] to perform in the [`KeyMap`]. The default bindings can be
+changed with [`KeyMap::bind`], or with a `key=action` string, like
+`space=next`.
+*/
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// A key, independent of the backend that received it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// A printable character, always lowercase.
+    Char(char),
+    /// The spacebar.
+    Space,
+    /// The escape key.
+    Escape,
+    /// Enter, or return.
+    Enter,
+    /// The backspace key.
+    Backspace,
+    /// The tab key.
+    Tab,
+    /// The left arrow.
+    Left,
+    /// The right arrow.
+    Right,
+    /// The up arrow.
+    Up,
+    /// The down arrow.
+    Down,
+    /// Page up.
+    PageUp,
+    /// Page down.
+    PageDown,
+    /// Home.
+    Home,
+    /// End.
+    End,
+    /// A function key, like `F5`.
+    F(u8),
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    /// Read the key's name, like `n`, `space`, `pagedown` or `f5`. Case does
+    /// not matter, so the names given by SDL are understood as well.
+    fn from_str(s: &str) -> Result<Self, String> {
+        let name = s.to_lowercase();
+        let key = match name.as_str() {
+            "space" | " " => Self::Space,
+            "escape" | "esc" => Self::Escape,
+            "enter" | "return" => Self::Enter,
+            "backspace" => Self::Backspace,
+            "tab" => Self::Tab,
+            "left" => Self::Left,
+            "right" => Self::Right,
+            "up" => Self::Up,
+            "down" => Self::Down,
+            "pageup" => Self::PageUp,
+            "pagedown" => Self::PageDown,
+            "home" => Self::Home,
+            "end" => Self::End,
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Self::Char(c),
+                    (Some('f'), Some(_)) => name[1..]
+                        .parse()
+                        .map(Self::F)
+                        .map_err(|_| format!("Unknown key: {s}"))?,
+                    _ => return Err(format!("Unknown key: {s}")),
+                }
+            }
+        };
+        Ok(key)
+    }
+}
+
+/// What the user can ask for with a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Show the next chapter, or the next slide if there are no chapters.
+    Next,
+    /// Show the previous chapter, or the previous slide if there are no
+    /// chapters.
+    Prev,
+    /// Show the next slide, even in the same chapter.
+    NextSub,
+    /// Show the previous slide, even in the same chapter.
+    PrevSub,
+    /// Quit.
+    Quit,
+    /// Parse the slides again.
+    Reload,
+    /// Show or hide the timer.
+    ToggleTimer,
+    /// Show or hide the side window, with the next slide.
+    ToggleSide,
+    /// Show or hide the slide counter.
+    ToggleCounter,
+    /// Show or hide the laser pointer, that follows the mouse.
+    TogglePointer,
+    /// Show or hide how fast the frames are drawn.
+    ToggleHud,
+    /// Draw the text with the next font, out of the loaded ones.
+    CycleFont,
+    /// Dim the whole slide but a section: the one under the mouse, or the
+    /// one picked with [`Action::NextFocus`].
+    ToggleSpotlight,
+    /// Put the spotlight on the next section of the slide.
+    NextFocus,
+    /// Go back to the slide shown before the last jump.
+    Back,
+    /// Start or stop the timer.
+    TimerStart,
+    /// Stop the timer, and set it back to 0.
+    TimerReset,
+    /// Open the slides' file in the user's editor.
+    Edit,
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "next" => Ok(Self::Next),
+            "prev" => Ok(Self::Prev),
+            "next-sub" => Ok(Self::NextSub),
+            "prev-sub" => Ok(Self::PrevSub),
+            "quit" => Ok(Self::Quit),
+            "reload" => Ok(Self::Reload),
+            "toggle-timer" => Ok(Self::ToggleTimer),
+            "toggle-side" => Ok(Self::ToggleSide),
+            "toggle-counter" => Ok(Self::ToggleCounter),
+            "toggle-pointer" => Ok(Self::TogglePointer),
+            "toggle-hud" => Ok(Self::ToggleHud),
+            "cycle-font" => Ok(Self::CycleFont),
+            "toggle-spotlight" => Ok(Self::ToggleSpotlight),
+            "next-focus" => Ok(Self::NextFocus),
+            "back" => Ok(Self::Back),
+            "timer-start" => Ok(Self::TimerStart),
+            "timer-reset" => Ok(Self::TimerReset),
+            "edit" => Ok(Self::Edit),
+            _ => Err(format!("Unknown action: {s}")),
+        }
+    }
+}
+
+/// The association between the keys and the actions.
+///
+/// Many keys can ask for the same action, but a key asks for a single
+/// action.
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: HashMap<Key, Action>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        use Action::{
+            Back, CycleFont, Edit, Next, NextFocus, NextSub, Prev, PrevSub,
+            Quit, Reload, TimerReset, TimerStart, ToggleCounter, ToggleHud,
+            TogglePointer, ToggleSide, ToggleSpotlight, ToggleTimer,
+        };
+
+        let bindings = [
+            (Key::Char('n'), Next),
+            (Key::Right, Next),
+            (Key::Down, NextSub),
+            (Key::PageDown, Next),
+            (Key::Char('p'), Prev),
+            (Key::Left, Prev),
+            (Key::Up, PrevSub),
+            (Key::PageUp, Prev),
+            (Key::Char('q'), Quit),
+            (Key::Escape, Quit),
+            (Key::F(5), Reload),
+            (Key::F(3), ToggleHud),
+            (Key::F(4), CycleFont),
+            (Key::Char('t'), ToggleTimer),
+            (Key::Char('s'), ToggleSide),
+            (Key::Char('c'), ToggleCounter),
+            (Key::Char('l'), TogglePointer),
+            (Key::Char('f'), ToggleSpotlight),
+            (Key::Tab, NextFocus),
+            (Key::Backspace, Back),
+            (Key::Space, TimerStart),
+            (Key::Char('r'), TimerReset),
+            (Key::Char('e'), Edit),
+        ];
+        Self {
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+impl KeyMap {
+    /// Get the action bound to `key`, if any.
+    #[must_use]
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bindings.get(&key).copied()
+    }
+
+    /// Bind `key` to `action`, replacing what the key did before.
+    pub fn bind(&mut self, key: Key, action: Action) {
+        self.bindings.insert(key, action);
+    }
+
+    /// Read a binding like `space=next`, and apply it.
+    ///
+    /// # Errors
+    ///
+    /// If the binding is not in the `key=action` form, or if either the key
+    /// or the action are unknown.
+    pub fn bind_str(&mut self, binding: &str) -> Result<(), String> {
+        let (key, action) = binding.split_once('=').ok_or_else(|| {
+            format!("A binding must be like `key=action`, found {binding}")
+        })?;
+        self.bind(key.trim().parse()?, action.trim().parse()?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_default_bindings() {
+        let keymap = KeyMap::default();
+        assert_eq!(keymap.action(Key::Char('n')), Some(Action::Next));
+        assert_eq!(keymap.action(Key::PageDown), Some(Action::Next));
+        assert_eq!(keymap.action(Key::Left), Some(Action::Prev));
+        assert_eq!(keymap.action(Key::Down), Some(Action::NextSub));
+        assert_eq!(keymap.action(Key::Escape), Some(Action::Quit));
+        assert_eq!(keymap.action(Key::Space), Some(Action::TimerStart));
+        assert_eq!(keymap.action(Key::F(4)), Some(Action::CycleFont));
+        assert_eq!(keymap.action(Key::Char('e')), Some(Action::Edit));
+        let spotlight = Some(Action::ToggleSpotlight);
+        assert_eq!(keymap.action(Key::Char('f')), spotlight);
+        assert_eq!(keymap.action(Key::Tab), Some(Action::NextFocus));
+        assert_eq!(keymap.action(Key::Backspace), Some(Action::Back));
+        assert_eq!(keymap.action(Key::Char('x')), None);
+    }
+
+    #[test]
+    fn test_key_names() {
+        assert_eq!("N".parse(), Ok(Key::Char('n')));
+        assert_eq!("Space".parse(), Ok(Key::Space));
+        assert_eq!("PageDown".parse(), Ok(Key::PageDown));
+        assert_eq!("Return".parse(), Ok(Key::Enter));
+        assert_eq!("F5".parse(), Ok(Key::F(5)));
+        assert_eq!("f".parse(), Ok(Key::Char('f')));
+        assert!("fancy".parse::<Key>().is_err());
+        assert!("".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn test_bind() {
+        let mut keymap = KeyMap::default();
+        keymap.bind_str("space=next").unwrap();
+        keymap.bind_str("x = quit").unwrap();
+        assert_eq!(keymap.action(Key::Space), Some(Action::Next));
+        assert_eq!(keymap.action(Key::Char('x')), Some(Action::Quit));
+        // The other keys are still there.
+        assert_eq!(keymap.action(Key::Char('n')), Some(Action::Next));
+
+        assert!(keymap.bind_str("space").is_err());
+        assert!(keymap.bind_str("space=jump").is_err());
+        assert!(keymap.bind_str("hyper=next").is_err());
+    }
+}
@@ -16,16 +16,43 @@ window, and the end user can interact by using `n`, `p`, and other facilities.
 Sometimes, we don't have the luxury of SDL, or simply we're only interested in
 showing some text in a terminal. Crossterm backend does not support all the
 features of SDL2 (such as images, colors, ...) but can be useful anyway.
+
+### SVG
+
+Not an interactive backend: it writes each slide as an SVG image, always
+available too.
+
+### Null
+
+Shows nothing at all, and it is always available: it records what it
+receives, and reads the inputs from a script. Useful to test the event loop
+without a display or a terminal.
 */
 
 #[cfg(feature = "cterm")]
 pub mod crossterm;
+pub mod keymap;
+pub mod null;
 #[cfg(feature = "sdl")]
 pub mod sdl;
+pub mod svg;
 
-use crate::slideshow::Slideshow;
+use std::time::{Duration, Instant};
 
-type ShouldQuit = bool;
+use crate::slideshow::{Position, Size, Slide, Slideshow};
+
+/// What the application should do after the inputs have been managed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputOutcome {
+    /// Keep on showing the slides.
+    Continue,
+    /// The user wants to quit.
+    Quit,
+    /// The user asked to parse the slides again.
+    Reload,
+    /// The user wants to change the slides' file in an editor.
+    Edit,
+}
 
 /// A (vague) backend definition.
 /// There are no strict requirements to become a backend - infact, we need to
@@ -35,7 +62,82 @@ type ShouldQuit = bool;
 pub trait SlidyBackend {
     /// The only thing the backend really needs to do is to provide the
     /// Context.
-    fn get_context(&mut self) -> Box<dyn SlidyContext + '_>;
+    ///
+    /// # Errors
+    ///
+    /// If the context can't be built, like when a window can't be opened.
+    fn get_context(
+        &mut self,
+    ) -> Result<Box<dyn SlidyContext + '_>, BackendError>;
+
+    /// Draw the slide at `idx` offscreen, in a `width` x `height` image, and
+    /// get its pixels: see [`render_slide_to_rgba`].
+    ///
+    /// # Errors
+    ///
+    /// If there is no such slide, or if the backend can't draw offscreen,
+    /// which is the default.
+    fn render_slide_to_rgba(
+        &self,
+        _slideshow: &crate::slideshow::Slideshow,
+        _idx: usize,
+        _width: u32,
+        _height: u32,
+    ) -> Result<Vec<u8>, BackendError> {
+        Err("This backend can't draw the slides offscreen.".into())
+    }
+}
+
+/// Draw the slide at `idx` offscreen, in a `width` x `height` image, as the
+/// audience would see it.
+///
+/// Each pixel takes 4 bytes, in the R, G, B, A order, and the rows go from
+/// the top to the bottom of the image.
+///
+/// No window is opened, so this can be used for thumbnails, or to compare
+/// the slides pixel by pixel in the tests.
+///
+/// # Errors
+///
+/// If there is no such slide, or if no backend able to draw offscreen is
+/// available.
+pub fn render_slide_to_rgba(
+    slideshow: &crate::slideshow::Slideshow,
+    idx: usize,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, BackendError> {
+    #[cfg(feature = "sdl")]
+    return sdl::render_slide_to_rgba(slideshow, idx, width, height);
+    #[cfg(not(feature = "sdl"))]
+    {
+        let _ = (slideshow, idx, width, height);
+        Err("Drawing the slides offscreen needs the `sdl` feature.".into())
+    }
+}
+
+/// The error returned when a backend, or its context, can't be started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendError(String);
+
+impl std::fmt::Display for BackendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+impl From<String> for BackendError {
+    fn from(e: String) -> Self {
+        Self(e)
+    }
+}
+
+impl From<&str> for BackendError {
+    fn from(e: &str) -> Self {
+        Self(e.to_string())
+    }
 }
 
 /// The internal definition of a a context for a backend.
@@ -45,12 +147,299 @@ pub trait SlidyContext {
     /// Read the slide's format and use it.
     fn set_slides(&mut self, slideshow: Slideshow);
     /// React to user's input.
-    fn manage_inputs(&mut self) -> ShouldQuit;
+    fn manage_inputs(&mut self) -> InputOutcome;
     /// Render to screen.
     fn render(&mut self);
+    /// The index of the slide being shown: the first one, unless the backend
+    /// says otherwise.
+    fn current_index(&self) -> usize {
+        0
+    }
+    /// Show the slide at `idx`, or the last one if there are not so many
+    /// slides. By default, nothing changes.
+    fn set_slide_index(&mut self, _idx: usize) {}
+    /// Give the screen (or the terminal) away for a while, like to an
+    /// editor. Nothing is rendered until [`Self::resume`] is called.
+    fn suspend(&mut self) {}
+    /// Take the screen back after [`Self::suspend`], and draw it again.
+    fn resume(&mut self) {}
+    /// If the slides are drawn in the terminal: an editor then has to run in
+    /// it, in place of the slides.
+    fn owns_terminal(&self) -> bool {
+        false
+    }
+    /// Start the talk's timer, if it is stopped.
+    fn timer_start(&mut self) {}
+    /// Stop the talk's timer, keeping the time elapsed so far.
+    fn timer_stop(&mut self) {}
+    /// Stop the talk's timer, and bring it back to zero.
+    fn timer_reset(&mut self) {}
+    /// How long the talk's timer run, or `None` if the backend has no timer.
+    fn timer_elapsed(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// A timer that can be stopped and started again, counting the time it run
+/// in total.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stopwatch {
+    /// Since when it is running, if it is.
+    since: Option<Instant>,
+    /// The time it run before `since`.
+    total: Duration,
+}
+
+impl Stopwatch {
+    /// Start the stopwatch, if it is stopped.
+    pub fn start(&mut self) {
+        self.since.get_or_insert_with(Instant::now);
+    }
+
+    /// Stop the stopwatch, keeping the time elapsed so far.
+    pub fn stop(&mut self) {
+        if let Some(since) = self.since.take() {
+            self.total += since.elapsed();
+        }
+    }
+
+    /// Stop the stopwatch, and bring it back to zero.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// The time it run, in total.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.total + self.since.map_or(Duration::ZERO, |s| s.elapsed())
+    }
+
+    /// If the stopwatch is running.
+    #[must_use]
+    pub const fn is_running(&self) -> bool {
+        self.since.is_some()
+    }
+}
+
+/// How many slides the navigation history remembers.
+pub const MAX_HISTORY: usize = 64;
+
+/// The slides shown before the current one, to jump back to them: each jump
+/// remembers the slide it leaves, and going back forgets it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct History {
+    /// The indexes of the slides, the one left last at the end.
+    left: Vec<usize>,
+}
+
+impl History {
+    /// Remember that the slide at `idx` was left. Past [`MAX_HISTORY`]
+    /// slides, the oldest one is forgotten.
+    pub fn push(&mut self, idx: usize) {
+        if self.left.len() >= MAX_HISTORY {
+            self.left.remove(0);
+        }
+        self.left.push(idx);
+    }
+
+    /// Forget the slide left last, and get it.
+    pub fn pop(&mut self) -> Option<usize> {
+        self.left.pop()
+    }
+}
+
+/// The options used to tune the backends' behavior.
+///
+/// Not all the backends are able to honor all the options.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    /// Go back to the first slide after the last one, and to the last slide
+    /// before the first one.
+    pub wrap: bool,
+    /// Fade from a slide to the next one, instead of simply swapping them.
+    pub transitions: bool,
+    /// Show the slides in a borderless window, kept on top of the others,
+    /// to write on top of something else (like a live demo).
+    pub overlay: bool,
+    /// Show the slides in a second window too, like for a second screen:
+    /// it shows the same slide as the main one, without the counter.
+    pub mirror: bool,
+    /// Show the next slide in a corner of the main window, instead of in the
+    /// side window: the key of the side window toggles it.
+    pub pip: bool,
+    /// The keys used to control the slideshow.
+    pub keymap: keymap::KeyMap,
+    /// The display where the slides are shown, like a projector. The first
+    /// display is 0.
+    pub monitor: Option<u32>,
+    /// Ignore the chapters: the next and the previous slides are always the
+    /// ones next to the current one.
+    pub linear: bool,
+    /// The length of the talk: the timer counts down from it, instead of
+    /// counting up.
+    pub countdown: Option<std::time::Duration>,
+    /// How precisely the timer shows the time.
+    pub timer_precision: TimerPrecision,
+    /// The width / height ratio of the slides: they are drawn in the
+    /// largest part of the window with this ratio, with bars around it.
+    /// Without it, the slides fill the whole window.
+    pub aspect: Option<f32>,
+    /// Scale the sizes of the sections with the shorter side of the window,
+    /// so that they keep their proportions when it is resized. Without it,
+    /// the widths follow the window's width, and the heights its height.
+    pub uniform_scale: bool,
+    /// When the slideshow does not set a font color, draw the text in black
+    /// or in white, whichever is readable on the slide's background. Without
+    /// it, the text is black.
+    pub auto_contrast: bool,
+    /// Show the title and the number of the current slide in the title bar
+    /// of the main window.
+    pub title_bar: bool,
+    /// Make room for the blank lines in the text sections, so that they
+    /// space the paragraphs. Without it, the blank lines are skipped, unless
+    /// the section has a position.
+    pub keep_blank_lines: bool,
+    /// Where the sections go, and how big they are, when neither the
+    /// sections nor the slideshow say it.
+    pub render_defaults: RenderDefaults,
+}
+
+/// How precisely the timer shows the time.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimerPrecision {
+    /// Whole seconds, like `01:02:03`.
+    #[default]
+    Seconds,
+    /// Tenths of a second, like `62:03.4`: the hours are counted in the
+    /// minutes.
+    Tenths,
+}
+
+impl std::str::FromStr for TimerPrecision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "seconds" => Ok(Self::Seconds),
+            "tenths" => Ok(Self::Tenths),
+            _ => Err(format!(
+                "Unknown timer precision {s:?}: use seconds or tenths"
+            )),
+        }
+    }
+}
+
+/// The positions and the sizes the backends fall back to, when neither the
+/// sections nor the slideshow set them. The defaults are the built-in ones,
+/// like [`crate::slideshow::DEFAULT_FONT_SIZE`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderDefaults {
+    /// Where the first text section without a position starts: the next
+    /// ones go below it.
+    pub text_position: Position,
+    /// Where the figures without a position go.
+    pub figure_position: Position,
+    /// The size of the figures without a size.
+    pub figure_size: Size,
+    /// The size of the letters, when the slideshow does not set one.
+    pub font_size: Size,
+}
+
+impl Default for RenderDefaults {
+    fn default() -> Self {
+        use crate::slideshow::{
+            DEFAULT_FIGURE_POSITION, DEFAULT_FIGURE_SIZE, DEFAULT_FONT_SIZE,
+            DEFAULT_TEXT_POSITION,
+        };
+
+        Self {
+            text_position: DEFAULT_TEXT_POSITION,
+            figure_position: DEFAULT_FIGURE_POSITION,
+            figure_size: DEFAULT_FIGURE_SIZE,
+            font_size: DEFAULT_FONT_SIZE,
+        }
+    }
+}
+
+impl RenderDefaults {
+    /// Change one of the defaults, given like `font-size=0.02,0.1`. The
+    /// names are text-position, figure-position, figure-size and font-size.
+    ///
+    /// # Errors
+    ///
+    /// If the name is unknown, or if the value is not 2 numbers.
+    pub fn set_str(&mut self, s: &str) -> Result<(), String> {
+        let (name, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected NAME=X,Y, found {s}"))?;
+        let (x, y) = value
+            .split_once(',')
+            .and_then(|(x, y)| {
+                let (x, y): (f32, f32) =
+                    (x.trim().parse().ok()?, y.trim().parse().ok()?);
+                (x.is_finite() && y.is_finite()).then_some((x, y))
+            })
+            .ok_or_else(|| format!("Expected 2 numbers, found {value}"))?;
+        match name.trim() {
+            "text-position" => self.text_position = Position { x, y },
+            "figure-position" => self.figure_position = Position { x, y },
+            "figure-size" => self.figure_size = Size { w: x, h: y },
+            "font-size" => self.font_size = Size { w: x, h: y },
+            other => return Err(format!("Unknown render default {other}")),
+        }
+        Ok(())
+    }
+}
+
+/// Read an aspect ratio like `16:9`. `auto` means no ratio at all, and the
+/// slides fill the window.
+///
+/// # Errors
+///
+/// If the ratio is not in the `width:height` form, with positive numbers.
+pub fn parse_aspect(s: &str) -> Result<Option<f32>, String> {
+    if s.eq_ignore_ascii_case("auto") {
+        return Ok(None);
+    }
+    let ratio = s.split_once(':').and_then(|(w, h)| {
+        let (w, h) =
+            (w.trim().parse::<f32>().ok()?, h.trim().parse::<f32>().ok()?);
+        (w > 0.0 && h > 0.0 && (w / h).is_finite()).then_some(w / h)
+    });
+    ratio.map(Some).ok_or_else(|| {
+        format!("The aspect must be like 16:9 or auto, found {s}")
+    })
+}
+
+/// Get the index of the slide after `idx`, out of `len` slides.
+pub(crate) const fn next_index(idx: usize, len: usize, wrap: bool) -> usize {
+    if len == 0 {
+        0
+    } else if idx + 1 < len {
+        idx + 1
+    } else if wrap {
+        0
+    } else {
+        len - 1
+    }
+}
+
+/// Get the index of the slide before `idx`, out of `len` slides.
+pub(crate) const fn prev_index(idx: usize, len: usize, wrap: bool) -> usize {
+    if len == 0 {
+        0
+    } else if idx > 0 {
+        idx - 1
+    } else if wrap {
+        len - 1
+    } else {
+        0
+    }
 }
 
 /// The available backends - once feature flags have been resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Backends {
     #[cfg(feature = "sdl")]
     /// The SDL2 variant.
@@ -58,6 +447,8 @@ pub enum Backends {
     #[cfg(feature = "cterm")]
     /// The Crossterm variant.
     Crossterm,
+    /// The variant that shows nothing, see [`null`].
+    Null,
 }
 
 fn match_try(value: &str) -> Result<Backends, String> {
@@ -66,20 +457,40 @@ fn match_try(value: &str) -> Result<Backends, String> {
         "sdl" => Ok(Backends::Sdl),
         #[cfg(feature = "cterm")]
         "crossterm" => Ok(Backends::Crossterm),
+        "null" => Ok(Backends::Null),
         _ => Err(format!("{value} backend is not supported.")),
     }
 }
 
-#[must_use]
 /// Get the actual backend implementation.
-pub fn get_backend(which: &Backends) -> Box<dyn SlidyBackend> {
-    use Backends::{Crossterm, Sdl};
-    match which {
+///
+/// # Errors
+///
+/// If the backend can't be started.
+pub fn get_backend(
+    which: &Backends,
+) -> Result<Box<dyn SlidyBackend>, BackendError> {
+    get_backend_with_options(which, Options::default())
+}
+
+/// Get the actual backend implementation, tuned with the given options.
+///
+/// # Errors
+///
+/// If the backend can't be started.
+pub fn get_backend_with_options(
+    which: &Backends,
+    options: Options,
+) -> Result<Box<dyn SlidyBackend>, BackendError> {
+    Ok(match which {
         #[cfg(feature = "sdl")]
-        Sdl => Box::new(sdl::Backend::new()),
+        Backends::Sdl => Box::new(sdl::Backend::with_options(options)?),
         #[cfg(feature = "cterm")]
-        Crossterm => Box::new(crossterm::Backend::new()),
-    }
+        Backends::Crossterm => {
+            Box::new(crossterm::Backend::with_options(options)?)
+        }
+        Backends::Null => Box::new(null::Backend::with_options(options)?),
+    })
 }
 
 impl TryFrom<String> for Backends {
@@ -95,3 +506,238 @@ impl TryFrom<&str> for Backends {
         match_try(value)
     }
 }
+
+/// Get the first and the last slide of the chapter the slide at `idx` is in.
+///
+/// The consecutive slides in the same group make a chapter, and the slides
+/// without a group are a chapter on their own.
+pub(crate) fn chapter_bounds(slides: &[Slide], idx: usize) -> (usize, usize) {
+    let Some(group) = slides.get(idx).map(|s| &s.group) else {
+        return (idx, idx);
+    };
+    if group.is_none() {
+        return (idx, idx);
+    }
+    let same = |s: &Slide| &s.group == group;
+    let first = slides[..idx]
+        .iter()
+        .rposition(|s| !same(s))
+        .map_or(0, |i| i + 1);
+    let last = slides[idx..]
+        .iter()
+        .position(|s| !same(s))
+        .map_or(slides.len() - 1, |i| idx + i - 1);
+    (first, last)
+}
+
+/// Get the index of the first slide of the chapter after the one of `idx`.
+pub(crate) fn next_chapter(slides: &[Slide], idx: usize, wrap: bool) -> usize {
+    let (_, last) = chapter_bounds(slides, idx);
+    if last + 1 < slides.len() {
+        last + 1
+    } else if wrap {
+        0
+    } else {
+        idx
+    }
+}
+
+/// Get the index of the first slide of the chapter before the one of `idx`.
+pub(crate) fn prev_chapter(slides: &[Slide], idx: usize, wrap: bool) -> usize {
+    let (first, _) = chapter_bounds(slides, idx);
+    if first > 0 {
+        chapter_bounds(slides, first - 1).0
+    } else if wrap && !slides.is_empty() {
+        chapter_bounds(slides, slides.len() - 1).0
+    } else {
+        idx
+    }
+}
+
+/// Get the number of the slide at `idx` and the total, counting only the
+/// slides that are [counted](Slide::counted). A slide that is not counted
+/// has the number of the counted one before it, or 0.
+#[cfg(any(feature = "sdl", test))]
+pub(crate) fn counted_position(slides: &[Slide], idx: usize) -> (usize, usize) {
+    let counted = |s: &[Slide]| s.iter().filter(|s| s.counted).count();
+    let upto = slides.get(..=idx).unwrap_or(slides);
+    (counted(upto), counted(slides))
+}
+
+/// Describe where the slide at `idx` is, like `3/10`, or `3.2/10` for the
+/// second slide of the third chapter. The slides that are not counted are
+/// left out, as the chapters with no counted slide.
+#[cfg(any(feature = "sdl", test))]
+pub(crate) fn position_label(slides: &[Slide], idx: usize) -> String {
+    let (mut chapters, mut current) = (0, 0);
+    let mut start = 0;
+    while start < slides.len() {
+        let (_, last) = chapter_bounds(slides, start);
+        if slides[start..=last].iter().any(|s| s.counted) {
+            chapters += 1;
+        }
+        if (start..=last).contains(&idx) {
+            current = chapters;
+        }
+        start = last + 1;
+    }
+    let (first, last) = chapter_bounds(slides, idx);
+    if last > first {
+        let (in_chapter, _) =
+            counted_position(&slides[first..=last], idx - first);
+        format!("{current}.{in_chapter}/{chapters}")
+    } else {
+        format!("{current}/{chapters}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stopwatch() {
+        let mut watch = Stopwatch::default();
+        assert!(!watch.is_running());
+        assert_eq!(watch.elapsed(), Duration::ZERO);
+        watch.start();
+        assert!(watch.is_running());
+        std::thread::sleep(Duration::from_millis(5));
+        watch.stop();
+        let stopped = watch.elapsed();
+        assert!(stopped >= Duration::from_millis(5));
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(watch.elapsed(), stopped);
+        watch.start();
+        watch.start();
+        assert!(watch.elapsed() >= stopped);
+        watch.reset();
+        assert!(!watch.is_running());
+        assert_eq!(watch.elapsed(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut history = History::default();
+        // From the first slide to the 30th, then to the 5th.
+        history.push(0);
+        history.push(29);
+        assert_eq!(history.pop(), Some(29));
+        assert_eq!(history.pop(), Some(0));
+        assert_eq!(history.pop(), None);
+
+        for idx in 0..=MAX_HISTORY {
+            history.push(idx);
+        }
+        let left: Vec<_> = std::iter::from_fn(|| history.pop()).collect();
+        assert_eq!(left.len(), MAX_HISTORY);
+        assert_eq!(left.last(), Some(&1));
+    }
+
+    /// Build slides in the given groups.
+    fn grouped(groups: &[Option<&str>]) -> Vec<Slide> {
+        groups
+            .iter()
+            .map(|g| Slide {
+                group: g.map(String::from),
+                ..Slide::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_aspect() {
+        assert_eq!(parse_aspect("auto"), Ok(None));
+        assert_eq!(parse_aspect("4:3"), Ok(Some(4.0 / 3.0)));
+        assert_eq!(parse_aspect("16 : 9"), Ok(Some(16.0 / 9.0)));
+        assert!(parse_aspect("16/9").is_err());
+        assert!(parse_aspect("16:0").is_err());
+        assert!(parse_aspect("-4:3").is_err());
+    }
+
+    #[test]
+    fn test_render_defaults() {
+        let mut defaults = RenderDefaults::default();
+        assert_eq!(defaults.font_size, crate::slideshow::DEFAULT_FONT_SIZE);
+        defaults.set_str("font-size=0.02, 0.1").unwrap();
+        defaults.set_str("text-position=0.1,0.2").unwrap();
+        assert_eq!(defaults.font_size, Size { w: 0.02, h: 0.1 });
+        assert_eq!(defaults.text_position, Position { x: 0.1, y: 0.2 });
+        assert!(defaults.set_str("figure-size=0.1").is_err());
+        assert!(defaults.set_str("margin=0.1,0.1").is_err());
+        assert!(defaults.set_str("font-size").is_err());
+    }
+
+    #[test]
+    fn test_chapters() {
+        let slides = grouped(&[None, Some("a"), Some("a"), Some("a"), None]);
+        assert_eq!(chapter_bounds(&slides, 0), (0, 0));
+        assert_eq!(chapter_bounds(&slides, 2), (1, 3));
+        assert_eq!(chapter_bounds(&slides, 4), (4, 4));
+
+        assert_eq!(next_chapter(&slides, 0, false), 1);
+        assert_eq!(next_chapter(&slides, 2, false), 4);
+        assert_eq!(next_chapter(&slides, 4, false), 4);
+        assert_eq!(next_chapter(&slides, 4, true), 0);
+        assert_eq!(prev_chapter(&slides, 4, false), 1);
+        assert_eq!(prev_chapter(&slides, 3, false), 0);
+        assert_eq!(prev_chapter(&slides, 0, false), 0);
+        assert_eq!(prev_chapter(&slides, 0, true), 4);
+
+        assert_eq!(position_label(&slides, 0), "1/3");
+        assert_eq!(position_label(&slides, 3), "2.3/3");
+        assert_eq!(position_label(&slides, 4), "3/3");
+    }
+
+    #[test]
+    fn test_chapters_need_consecutive_slides() {
+        let slides = grouped(&[Some("a"), Some("b"), Some("a")]);
+        assert_eq!(chapter_bounds(&slides, 0), (0, 0));
+        assert_eq!(next_chapter(&slides, 0, false), 1);
+        assert_eq!(position_label(&slides, 2), "3/3");
+        assert_eq!(position_label(&[], 0), "0/0");
+    }
+
+    #[test]
+    fn test_slides_not_counted() {
+        let mut slides = grouped(&[None, Some("a"), Some("a"), None, None]);
+        slides[0].counted = false;
+        slides[1].counted = false;
+        slides[3].counted = false;
+        assert_eq!(counted_position(&slides, 0), (0, 2));
+        assert_eq!(counted_position(&slides, 2), (1, 2));
+        assert_eq!(counted_position(&slides, 3), (1, 2));
+        assert_eq!(counted_position(&slides, 4), (2, 2));
+        assert_eq!(counted_position(&[], 0), (0, 0));
+
+        assert_eq!(position_label(&slides, 0), "0/2");
+        assert_eq!(position_label(&slides, 1), "1.0/2");
+        assert_eq!(position_label(&slides, 2), "1.1/2");
+        assert_eq!(position_label(&slides, 3), "1/2");
+        assert_eq!(position_label(&slides, 4), "2/2");
+    }
+
+    #[test]
+    fn test_navigation_stops_at_the_edges() {
+        assert_eq!(next_index(0, 3, false), 1);
+        assert_eq!(next_index(2, 3, false), 2);
+        assert_eq!(prev_index(2, 3, false), 1);
+        assert_eq!(prev_index(0, 3, false), 0);
+    }
+
+    #[test]
+    fn test_navigation_wraps() {
+        assert_eq!(next_index(1, 3, true), 2);
+        assert_eq!(next_index(2, 3, true), 0);
+        assert_eq!(prev_index(0, 3, true), 2);
+        assert_eq!(prev_index(1, 3, true), 0);
+    }
+
+    #[test]
+    fn test_navigation_empty_deck() {
+        assert_eq!(next_index(0, 0, true), 0);
+        assert_eq!(prev_index(0, 0, true), 0);
+        assert_eq!(next_index(0, 0, false), 0);
+        assert_eq!(prev_index(0, 0, false), 0);
+    }
+}
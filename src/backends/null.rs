@@ -0,0 +1,201 @@
+//! A backend that shows nothing, to test the event loop without a display or
+//! a terminal.
+//!
+//! The backend records the slides and the inputs it is given, and the inputs
+//! are read from a script: once the script is over, the user quits.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use super::InputOutcome;
+use crate::slideshow::Slideshow;
+
+/// What the backend went through.
+#[derive(Debug, Default, PartialEq)]
+pub struct Record {
+    /// The slides received, in order.
+    pub slides: Vec<Slideshow>,
+    /// How many times the inputs were managed.
+    pub inputs: usize,
+    /// How many times the slides were rendered.
+    pub renders: usize,
+    /// The index of the slide being shown.
+    pub index: usize,
+    /// The talk's timer.
+    pub timer: super::Stopwatch,
+}
+
+/// The backend.
+#[derive(Debug, Default)]
+pub struct Backend {
+    script: VecDeque<InputOutcome>,
+    record: Record,
+}
+
+impl super::SlidyBackend for Backend {
+    fn get_context(
+        &mut self,
+    ) -> Result<Box<dyn super::SlidyContext + '_>, super::BackendError> {
+        Ok(Box::new(Context { backend: self }))
+    }
+}
+
+impl Backend {
+    /// Create a new backend, that quits as soon as it manages the inputs.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new backend: the options are all ignored.
+    ///
+    /// # Errors
+    ///
+    /// Never, but the signature is the one of the other backends.
+    pub fn with_options(
+        _options: super::Options,
+    ) -> Result<Self, super::BackendError> {
+        Ok(Self::new())
+    }
+
+    /// Create a new backend, whose inputs are `script`, one per call to
+    /// [`super::SlidyContext::manage_inputs`]. Then, it quits.
+    #[must_use]
+    pub fn with_script<I: IntoIterator<Item = InputOutcome>>(
+        script: I,
+    ) -> Self {
+        Self {
+            script: script.into_iter().collect(),
+            record: Record::default(),
+        }
+    }
+
+    /// What the backend went through so far.
+    #[must_use]
+    pub const fn record(&self) -> &Record {
+        &self.record
+    }
+}
+
+/// The context, that writes in the backend's record.
+struct Context<'a> {
+    backend: &'a mut Backend,
+}
+
+impl super::SlidyContext for Context<'_> {
+    fn set_slides(&mut self, slideshow: Slideshow) {
+        self.backend.record.slides.push(slideshow);
+        let idx = self.backend.record.index;
+        self.set_slide_index(idx);
+    }
+
+    fn manage_inputs(&mut self) -> InputOutcome {
+        self.backend.record.inputs += 1;
+        self.backend
+            .script
+            .pop_front()
+            .unwrap_or(InputOutcome::Quit)
+    }
+
+    fn render(&mut self) {
+        self.backend.record.renders += 1;
+    }
+
+    fn current_index(&self) -> usize {
+        self.backend.record.index
+    }
+
+    fn set_slide_index(&mut self, idx: usize) {
+        let len = self
+            .backend
+            .record
+            .slides
+            .last()
+            .map_or(0, |s| s.slides.len());
+        self.backend.record.index = idx.min(len.saturating_sub(1));
+    }
+
+    fn timer_start(&mut self) {
+        self.backend.record.timer.start();
+    }
+
+    fn timer_stop(&mut self) {
+        self.backend.record.timer.stop();
+    }
+
+    fn timer_reset(&mut self) {
+        self.backend.record.timer.reset();
+    }
+
+    fn timer_elapsed(&self) -> Option<Duration> {
+        Some(self.backend.record.timer.elapsed())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backends::SlidyBackend;
+
+    #[test]
+    fn test_null_backend() {
+        let mut backend = Backend::with_script([
+            InputOutcome::Continue,
+            InputOutcome::Reload,
+        ]);
+        let parse = || {
+            crate::parser::parse_text(
+                ":sl\n:tb\nOne\n:sl\n:tb\nTwo",
+                std::path::Path::new(""),
+            )
+            .unwrap()
+        };
+        {
+            let mut context = backend.get_context().unwrap();
+            context.set_slides(parse());
+            context.set_slide_index(5);
+            assert_eq!(context.current_index(), 1);
+            assert_eq!(context.manage_inputs(), InputOutcome::Continue);
+            assert_eq!(context.manage_inputs(), InputOutcome::Reload);
+            context.render();
+            assert_eq!(context.manage_inputs(), InputOutcome::Quit);
+        }
+        let record = backend.record();
+        assert_eq!(record.slides, [parse()]);
+        assert_eq!((record.inputs, record.renders), (3, 1));
+    }
+
+    #[test]
+    fn test_null_backend_timer() {
+        let mut backend = Backend::new();
+        {
+            let mut context = backend.get_context().unwrap();
+            assert_eq!(context.timer_elapsed(), Some(Duration::ZERO));
+            context.timer_start();
+            std::thread::sleep(Duration::from_millis(5));
+            context.timer_stop();
+            let elapsed = context.timer_elapsed().unwrap();
+            assert!(elapsed >= Duration::from_millis(5));
+            assert_eq!(context.timer_elapsed(), Some(elapsed));
+        }
+        assert!(backend.record().timer.elapsed() > Duration::ZERO);
+        let mut context = backend.get_context().unwrap();
+        context.timer_reset();
+        assert_eq!(context.timer_elapsed(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_null_backend_no_slides() {
+        let mut backend = Backend::new();
+        let empty = crate::parser::parse_text("\n\n", std::path::Path::new(""))
+            .unwrap();
+        {
+            let mut context = backend.get_context().unwrap();
+            context.set_slides(empty);
+            context.set_slide_index(3);
+            context.render();
+            assert_eq!(context.current_index(), 0);
+            assert_eq!(context.manage_inputs(), InputOutcome::Quit);
+        }
+        assert_eq!(backend.record().renders, 1);
+    }
+}
@@ -24,6 +24,9 @@ use self::{
 
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
+use tracing::error;
+
+use super::keymap::{Action, Key, KeyMap};
 
 /// The primary window, used to show the slides.
 pub mod slideshow;
@@ -31,41 +34,100 @@ pub mod slideshow;
 pub mod timer;
 mod utils;
 
+/// The size the fonts are loaded with: they are scaled when drawn.
+const FONT_POINTS: u16 = 100;
+
 /// Get the default, included font. It is the `FreeMono` one, and it is
 /// included in the binary, so no need to provide any other file.
-#[must_use]
 fn get_default_font(
     context: &sdl2::ttf::Sdl2TtfContext,
-) -> sdl2::ttf::Font<'_, '_> {
+) -> Result<sdl2::ttf::Font<'_, '_>, String> {
     // TODO The font should be read from the slide directly
     //      and _then_ if nothing is provided use the default one.
     let fontbytes = include_bytes!("../../../assets/FreeMono.ttf");
-    let mut points = 100;
-    loop {
-        let rwfont = sdl2::rwops::RWops::from_bytes(fontbytes)
-            .expect("Font file has been moved");
+    let mut points = FONT_POINTS;
+    while points >= 10 {
+        let rwfont = sdl2::rwops::RWops::from_bytes(fontbytes)?;
         if let Ok(font) = context.load_font_from_rwops(rwfont, points) {
-            return font;
+            return Ok(font);
         }
         points -= 10;
-        assert!(points >= 10, "This is not enough to show the font...");
     }
+    Err("Unable to load the default font.".into())
+}
+
+/// Load the fonts of the `slides`, sorted by name, from where
+/// [`resolve_path`](crate::slideshow::Slideshow::resolve_path) finds them.
+/// The ones that can't be loaded are skipped.
+fn load_fonts<'ttf>(
+    context: &'ttf sdl2::ttf::Sdl2TtfContext,
+    slides: &crate::slideshow::Slideshow,
+) -> Vec<(String, sdl2::ttf::Font<'ttf, 'ttf>)> {
+    slides
+        .fonts
+        .iter()
+        .filter_map(|(name, path)| {
+            let path = slides.resolve_path(path);
+            match context.load_font(&path, FONT_POINTS) {
+                Ok(font) => Some((name.clone(), font)),
+                Err(e) => {
+                    error!("Unable to load the font {name} at {path}: {e}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// If the `event` asks for the windows to be drawn again: when they are
+/// shown, resized, or their content is lost.
+const fn redraws(event: &Event) -> bool {
+    use sdl2::event::WindowEvent;
+
+    match event {
+        Event::Window { win_event, .. } => matches!(
+            win_event,
+            WindowEvent::Shown
+                | WindowEvent::Exposed
+                | WindowEvent::Resized(..)
+                | WindowEvent::SizeChanged(..)
+                | WindowEvent::Maximized
+                | WindowEvent::Restored
+                | WindowEvent::DisplayChanged(..)
+        ),
+        Event::RenderTargetsReset { .. } | Event::RenderDeviceReset { .. } => {
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Helper: translate the SDL key, so that it can be looked up in the keymap.
+fn get_key(keycode: Keycode) -> Option<Key> {
+    keycode.name().parse().ok()
 }
 
-/// Helper: init the SDL context.
-#[must_use]
-fn get_sdl_context() -> sdl2::Sdl {
+/// The image formats that can be used in the figures.
+fn image_formats() -> sdl2::image::InitFlag {
+    use sdl2::image::InitFlag;
+
+    InitFlag::JPG | InitFlag::PNG | InitFlag::WEBP | InitFlag::TIF
+}
+
+/// Helper: init the SDL context, and the image one.
+fn get_sdl_context(
+) -> Result<(sdl2::Sdl, sdl2::image::Sdl2ImageContext), String> {
     // Init stuffs.
-    let sdl_context = sdl2::init().expect("Unable to init sdl.");
-    // This is unused, but needs to stay in scope to be able to use the SDL_image.
-    let _image_context = sdl2::image::init(sdl2::image::InitFlag::JPG)
-        .expect("Unable to init image.");
-    sdl_context
+    let sdl_context =
+        sdl2::init().map_err(|e| format!("Unable to init sdl: {e}"))?;
+    let image_context = sdl2::image::init(image_formats())
+        .map_err(|e| format!("Unable to init image: {e}"))?;
+    Ok((sdl_context, image_context))
 }
 
 /// Helper: init the TTF context.
-fn get_ttf_context() -> sdl2::ttf::Sdl2TtfContext {
-    sdl2::ttf::init().expect("Unable to init ttf.")
+fn get_ttf_context() -> Result<sdl2::ttf::Sdl2TtfContext, String> {
+    sdl2::ttf::init().map_err(|e| format!("Unable to init ttf: {e}"))
 }
 
 /// Define the window options.
@@ -74,6 +136,7 @@ struct WindowOptions {
     pub h: u32,
     pub w: u32,
     pub resizable: bool,
+    pub overlay: bool,
 }
 
 impl Default for WindowOptions {
@@ -82,6 +145,7 @@ impl Default for WindowOptions {
             h: 800,
             w: 600,
             resizable: true,
+            overlay: false,
         }
     }
 }
@@ -91,16 +155,67 @@ impl Default for WindowOptions {
 /// context.
 pub struct Backend {
     sdl_context: sdl2::Sdl,
+    // This is unused, but needs to stay in scope to be able to use the
+    // SDL_image.
+    _image_context: sdl2::image::Sdl2ImageContext,
     ttf_context: sdl2::ttf::Sdl2TtfContext,
+    options: super::Options,
 }
 
 impl super::SlidyBackend for Backend {
-    fn get_context(&mut self) -> Box<dyn super::SlidyContext + '_> {
-        let ctx = self.internal_get_context();
-        Box::new(ctx)
+    fn get_context(
+        &mut self,
+    ) -> Result<Box<dyn super::SlidyContext + '_>, super::BackendError> {
+        let ctx = self.internal_get_context()?;
+        Ok(Box::new(ctx))
+    }
+
+    fn render_slide_to_rgba(
+        &self,
+        slideshow: &crate::slideshow::Slideshow,
+        idx: usize,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, super::BackendError> {
+        let font = get_default_font(&self.ttf_context)?;
+        let pixels = slideshow::render_to_rgba(
+            slideshow,
+            idx,
+            (width, height),
+            &font,
+            &self.options.render_defaults,
+        )?;
+        Ok(pixels)
     }
 }
 
+/// Draw a slide offscreen, without any window, and get its pixels: see
+/// [`super::render_slide_to_rgba`].
+///
+/// # Errors
+///
+/// If SDL can't be initialized, or if there is no such slide.
+pub fn render_slide_to_rgba(
+    slideshow: &crate::slideshow::Slideshow,
+    idx: usize,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, super::BackendError> {
+    // No video is needed to draw in a surface.
+    let _image_context = sdl2::image::init(image_formats())
+        .map_err(|e| format!("Unable to init image: {e}"))?;
+    let ttf_context = get_ttf_context()?;
+    let font = get_default_font(&ttf_context)?;
+    let pixels = slideshow::render_to_rgba(
+        slideshow,
+        idx,
+        (width, height),
+        &font,
+        &super::RenderDefaults::default(),
+    )?;
+    Ok(pixels)
+}
+
 /// The context, which contains the live data.
 /// This structure has to be used to update the slides in the event loop, or
 /// manage keypresses, and so on.
@@ -111,48 +226,111 @@ pub struct Context<'backend> {
     active_win_id: u32,
     main_slide_id: u32,
     side_slide_id: u32,
+    /// The mirror window, if it is open.
+    mirror_id: Option<u32>,
     timer_id: u32,
 
+    keymap: KeyMap,
     event_pump: sdl2::EventPump,
+    /// To load the fonts of the slides.
+    ttf: &'backend sdl2::ttf::Sdl2TtfContext,
+
+    /// When the last frame was rendered.
+    last_render: Option<std::time::Instant>,
+    /// How long rendering the last frame took.
+    render_time: std::time::Duration,
 }
 
 impl Backend {
     /// Create a new backend.
-    #[must_use]
-    pub fn new() -> Self {
-        let sdl_context = get_sdl_context();
-        let ttf_context = get_ttf_context();
+    ///
+    /// # Errors
+    ///
+    /// If SDL can't be initialized, like when there is no display.
+    pub fn new() -> Result<Self, super::BackendError> {
+        Self::with_options(super::Options::default())
+    }
 
-        Self {
+    /// Create a new backend, tuned with the given options.
+    ///
+    /// # Errors
+    ///
+    /// If SDL can't be initialized, like when there is no display.
+    pub fn with_options(
+        options: super::Options,
+    ) -> Result<Self, super::BackendError> {
+        let (sdl_context, image_context) = get_sdl_context()?;
+        let ttf_context = get_ttf_context()?;
+        // SDL itself starts even without a display: check the video here, so
+        // that the caller can pick another backend early.
+        sdl_context
+            .video()
+            .map_err(|e| format!("Unable to init the video: {e}"))?;
+
+        Ok(Self {
             sdl_context,
+            _image_context: image_context,
             ttf_context,
-        }
+            options,
+        })
     }
 
     /// Get the runnable context.
     /// @TODO manage windows options.
-    fn internal_get_context(&self) -> Context {
-        let screen_options = WindowOptions::default();
+    fn internal_get_context(&self) -> Result<Context, super::BackendError> {
+        let screen_options = WindowOptions {
+            overlay: self.options.overlay,
+            ..WindowOptions::default()
+        };
 
         // 1. The slideshow window
-        let slideshow_win = SlideShowWindow::new(
+        let mut slideshow_win = SlideShowWindow::new(
             &self.sdl_context,
-            get_default_font(&self.ttf_context),
+            get_default_font(&self.ttf_context)?,
             screen_options.resizable,
+            screen_options.overlay,
             screen_options.h,
             screen_options.w,
-        );
+        )?;
+        if let Some(display) = self.options.monitor {
+            utils::move_to_display(
+                slideshow_win.main_win.canvas.window_mut(),
+                display,
+            );
+        }
+        slideshow_win.wrap = self.options.wrap;
+        slideshow_win.linear = self.options.linear;
+        slideshow_win.transitions = self.options.transitions;
+        slideshow_win.pip = self.options.pip;
+        slideshow_win.aspect = self.options.aspect;
+        slideshow_win.uniform_scale = self.options.uniform_scale;
+        slideshow_win.auto_contrast = self.options.auto_contrast;
+        slideshow_win.title_bar = self.options.title_bar;
+        slideshow_win.keep_blank_lines = self.options.keep_blank_lines;
+        slideshow_win.render_defaults = self.options.render_defaults.clone();
+        let mirror_id = if self.options.mirror {
+            Some(slideshow_win.open_mirror(
+                &self.sdl_context,
+                screen_options.resizable,
+                screen_options.h,
+                screen_options.w,
+            )?)
+        } else {
+            None
+        };
 
         // 2. The timer window
         // @todo <dp> create options for the size of this window as well?
         let mut timer_win = TimerWindow::new(
             &self.sdl_context,
-            get_default_font(&self.ttf_context),
+            get_default_font(&self.ttf_context)?,
             screen_options.resizable,
             screen_options.h / 5,
             screen_options.w / 5,
-        );
+        )?;
         timer_win.visibility_toggle();
+        timer_win.countdown = self.options.countdown;
+        timer_win.precision = self.options.timer_precision;
 
         // Get the windows ids.
         let main_slide_id = slideshow_win.main_win.id;
@@ -160,48 +338,79 @@ impl Backend {
         let timer_id = timer_win.generic_win.id;
 
         // Create the event pump.
-        let event_pump = self
-            .sdl_context
-            .event_pump()
-            .expect("Unable to get the event pump, another one is alive?");
+        let event_pump = self.sdl_context.event_pump().map_err(|e| {
+            format!("Unable to get the event pump, another one is alive? {e}")
+        })?;
 
-        Context {
+        Ok(Context {
             slideshow_win,
             timer_win,
             active_win_id: 0,
             main_slide_id,
             side_slide_id,
+            mirror_id,
             timer_id,
+            keymap: self.options.keymap.clone(),
             event_pump,
-        }
-    }
-}
-
-impl Default for Backend {
-    fn default() -> Self {
-        Self::new()
+            ttf: &self.ttf_context,
+            last_render: None,
+            render_time: std::time::Duration::ZERO,
+        })
     }
 }
 
 impl<'b> super::SlidyContext for Context<'b> {
     fn set_slides(&mut self, slides: crate::slideshow::Slideshow) {
+        let fonts = load_fonts(self.ttf, &slides);
+        self.slideshow_win.set_fonts(fonts);
         self.slideshow_win.set_slides(slides);
     }
 
     /// Manage the incoming events.
-    fn manage_inputs(&mut self) -> super::ShouldQuit {
+    fn manage_inputs(&mut self) -> super::InputOutcome {
+        let mut outcome = super::InputOutcome::Continue;
         for event in self.event_pump.poll_iter() {
-            match self.active_win_id {
-                x if x == self.main_slide_id => {
-                    self.slideshow_win.manage_keypress(&event);
-                }
-                x if x == self.side_slide_id => {
-                    self.slideshow_win.manage_keypress(&event);
-                }
-                x if x == self.timer_id => {
-                    self.timer_win.manage_keypress(&event);
+            // The keys are looked up in the keymap. Some actions make sense
+            // only for the window that has the focus.
+            let action = match event {
+                Event::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } => get_key(keycode).and_then(|k| self.keymap.action(k)),
+                _ => Option::None,
+            };
+            if let Some(action) = action {
+                match action {
+                    Action::Quit => return super::InputOutcome::Quit,
+                    Action::Reload => outcome = super::InputOutcome::Reload,
+                    Action::Edit => outcome = super::InputOutcome::Edit,
+                    Action::ToggleTimer => self.timer_win.visibility_toggle(),
+                    Action::ToggleSide => self.slideshow_win.toggle_sideslide(),
+                    Action::Next
+                    | Action::Prev
+                    | Action::NextSub
+                    | Action::PrevSub
+                    | Action::ToggleCounter
+                    | Action::TogglePointer
+                    | Action::ToggleHud
+                    | Action::CycleFont
+                    | Action::ToggleSpotlight
+                    | Action::NextFocus
+                    | Action::Back => {
+                        if self.active_win_id == self.main_slide_id
+                            || self.active_win_id == self.side_slide_id
+                            || Some(self.active_win_id) == self.mirror_id
+                        {
+                            self.slideshow_win.manage_action(action);
+                        }
+                    }
+                    Action::TimerStart | Action::TimerReset => {
+                        if self.active_win_id == self.timer_id {
+                            self.timer_win.manage_action(action);
+                        }
+                    }
                 }
-                _ => {}
+                continue;
             }
             // Then, match events that should always occur, whatever window is
             // highlighted.
@@ -212,62 +421,151 @@ impl<'b> super::SlidyContext for Context<'b> {
                     win_event: sdl2::event::WindowEvent::Close,
                     ..
                 } => match window_id {
-                    x if x == self.main_slide_id => return true,
+                    x if x == self.main_slide_id => {
+                        return super::InputOutcome::Quit
+                    }
                     x if x == self.side_slide_id => {
                         self.slideshow_win.toggle_sideslide();
                     }
+                    x if Some(x) == self.mirror_id => {
+                        self.slideshow_win.close_mirror();
+                        self.mirror_id = Option::None;
+                    }
                     x if x == self.timer_id => {
                         self.timer_win.visibility_toggle();
                     }
                     _ => {}
                 },
-                // Quit event, QUIT (I guess F4, C-c) or Q or ESC
-                Event::Quit { .. }
-                | Event::KeyUp {
-                    keycode: Some(Keycode::Escape | Keycode::Q),
-                    ..
-                } => return true,
-                // KeyUp: T
-                Event::KeyUp {
-                    keycode: Some(Keycode::T),
-                    ..
-                } => self.timer_win.visibility_toggle(),
-                // KeyUp: S
-                Event::KeyUp {
-                    keycode: Some(Keycode::S),
-                    ..
-                } => self.slideshow_win.toggle_sideslide(),
+                // Quit event, QUIT (I guess F4, C-c)
+                Event::Quit { .. } => return super::InputOutcome::Quit,
                 // Window Event: set the id of the window when focus is gained.
                 Event::Window {
                     window_id,
                     win_event: sdl2::event::WindowEvent::FocusGained,
                     ..
-                }
-                | Event::MouseMotion { window_id, .. } => {
+                } => {
                     // Store window that last gained focus.
                     self.active_win_id = window_id;
                 }
-                _ => self.slideshow_win.is_changed = true,
+                Event::MouseMotion {
+                    window_id, x, y, ..
+                } => {
+                    self.active_win_id = window_id;
+                    if window_id == self.main_slide_id {
+                        self.slideshow_win.move_pointer(x, y);
+                    }
+                }
+                // Only what shows the windows again, or changes their size,
+                // needs them drawn again: the rest is left alone, so that
+                // nothing is drawn while idle.
+                ref event if redraws(event) => {
+                    self.slideshow_win.is_changed = true;
+                    self.timer_win.invalidate();
+                }
+                _ => {}
             }
         }
-        false
+        outcome
     }
 
     /// Render the windows.
     fn render(&mut self) {
-        // Update slideshow window
-        if self.slideshow_win.is_changed {
-            self.slideshow_win.present_slide();
+        let start = std::time::Instant::now();
+        let frame = self
+            .last_render
+            .replace(start)
+            .map_or(std::time::Duration::ZERO, |last| start - last);
+        self.slideshow_win.set_frame_times(slideshow::FrameTimes {
+            frame,
+            render: self.render_time,
+        });
+
+        // Update slideshow window. Mind that presenting the slide can ask
+        // for another round, like during the transitions.
+        let changed = self.slideshow_win.is_changed;
+        if changed {
             self.slideshow_win.is_changed = false;
+            self.slideshow_win.present_slide();
+            self.slideshow_win.main_win.canvas.present();
+            self.slideshow_win.side_win.canvas.present();
+            if let Some(mirror_win) = &mut self.slideshow_win.mirror_win {
+                mirror_win.canvas.present();
+            }
         }
 
-        // Update timer window
+        // Update timer window, when what it shows changed.
         // self.timer_win.update_pseudo_random_position();
-        let (slide_idx, slide_len) = self.slideshow_win.get_slides_counters();
-        self.timer_win.update(slide_len, slide_idx + 1);
+        let (number, total) = self.slideshow_win.get_counted_counters();
+        if self.timer_win.update(total, number) {
+            self.timer_win.generic_win.canvas.present();
+        }
+        if changed {
+            self.render_time = start.elapsed();
+        }
+    }
+
+    fn current_index(&self) -> usize {
+        self.slideshow_win.get_slides_counters().0
+    }
+
+    fn set_slide_index(&mut self, idx: usize) {
+        self.slideshow_win.set_slide_index(idx);
+    }
+
+    /// Nothing is drawn while suspended, since the event loop is stuck
+    /// until the editor is closed: the slides are drawn again after it.
+    fn resume(&mut self) {
+        self.slideshow_win.is_changed = true;
+        self.timer_win.invalidate();
+        self.last_render = Option::None;
+    }
+
+    fn timer_start(&mut self) {
+        self.timer_win.timer_start();
+    }
+
+    fn timer_stop(&mut self) {
+        self.timer_win.timer_stop();
+    }
+
+    fn timer_reset(&mut self) {
+        self.timer_win.timer_reset();
+    }
+
+    fn timer_elapsed(&self) -> Option<std::time::Duration> {
+        Some(self.timer_win.elapsed())
+    }
+}
 
-        self.slideshow_win.main_win.canvas.present();
-        self.slideshow_win.side_win.canvas.present();
-        self.timer_win.generic_win.canvas.present();
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sdl2::event::WindowEvent;
+
+    #[test]
+    fn test_redraws() {
+        let window = |win_event| Event::Window {
+            timestamp: 0,
+            window_id: 1,
+            win_event,
+        };
+        assert!(redraws(&window(WindowEvent::Exposed)));
+        assert!(redraws(&window(WindowEvent::Shown)));
+        assert!(redraws(&window(WindowEvent::Resized(640, 480))));
+        assert!(redraws(&Event::RenderTargetsReset { timestamp: 0 }));
+
+        // Moving the window, or the mouse over it, changes nothing.
+        assert!(!redraws(&window(WindowEvent::Moved(10, 10))));
+        assert!(!redraws(&window(WindowEvent::Enter)));
+        assert!(!redraws(&window(WindowEvent::FocusLost)));
+        let key = Event::KeyDown {
+            timestamp: 0,
+            window_id: 1,
+            keycode: Some(Keycode::A),
+            scancode: None,
+            keymod: sdl2::keyboard::Mod::NOMOD,
+            repeat: false,
+        };
+        assert!(!redraws(&key));
     }
 }
@@ -1,19 +1,201 @@
 //! Window used to show the slides.
-use tracing::error;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color as sdl_color;
+use tracing::{error, info, warn};
 
-use super::{utils, utils::GenericWindow};
-use crate::slideshow;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{BlendMode, Canvas};
+use sdl2::surface::Surface;
+
+use super::{
+    utils,
+    utils::{DrawTarget, GenericWindow, OwnedTexture},
+};
+use crate::backends::keymap::Action;
+use crate::backends::{
+    counted_position, next_chapter, next_index, position_label, prev_chapter,
+    prev_index, History, RenderDefaults,
+};
+use crate::slideshow::{self, Slide, TextRun};
+
+/// How long the fade between 2 slides lasts.
+const TRANSITION_DURATION: Duration = Duration::from_millis(150);
+
+/// How big the letters of a caption are, compared to the text's ones.
+const CAPTION_SCALE: f32 = 0.75;
+
+/// The size of the letters of the slide counter.
+const COUNTER_LETTER_SIZE: (f32, f32) = (0.012, 0.04);
+
+/// How opaque the box behind the code is, over the color of the text.
+const CODE_BG_ALPHA: u8 = 0x20;
+
+/// How long the last frames took, shown in the performance HUD.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FrameTimes {
+    /// The time between the last 2 frames.
+    pub frame: Duration,
+    /// The time needed to draw the last frame.
+    pub render: Duration,
+}
+
+/// The text of the performance HUD.
+fn hud_label(times: FrameTimes, textures: usize) -> String {
+    let fps = if times.frame.is_zero() {
+        0.0
+    } else {
+        1.0 / times.frame.as_secs_f32()
+    };
+    let render_ms = times.render.as_secs_f32() * 1000.0;
+    format!("{fps:.1} fps, {render_ms:.1} ms, {textures} textures")
+}
+
+/// The slideshow's defaults, used when the slides do not say otherwise.
+#[derive(Clone, Copy)]
+struct Defaults {
+    bg_col: slideshow::Color,
+    /// The background of the slides, instead of the `bg_col`.
+    gradient: Option<slideshow::Gradient>,
+    font_col: slideshow::Color,
+    font_size: (f32, f32),
+    /// Where the first text section without a position starts.
+    text_position: (f32, f32),
+    /// Where the figures without a position go.
+    figure_position: (f32, f32),
+    /// The size of the figures without a size.
+    figure_size: (f32, f32),
+    /// If the sizes scale with the shorter side of the window.
+    uniform_scale: bool,
+    /// If the font color is picked to be readable on the background of each
+    /// slide, since the slideshow does not set one.
+    auto_contrast: bool,
+    /// If the blank lines in the text take some space.
+    keep_blank_lines: bool,
+}
+
+impl Defaults {
+    /// Get the defaults of the slideshow, falling back to the `render` ones.
+    fn new(slides: &slideshow::Slideshow, render: &RenderDefaults) -> Self {
+        let position = |p: &slideshow::Position| (p.x, p.y);
+        Self {
+            bg_col: slides.bg_col.unwrap_or(slideshow::DEFAULT_BG_COLOR),
+            gradient: slides.gradient,
+            font_col: slides.font_col.unwrap_or(slideshow::DEFAULT_FONT_COLOR),
+            font_size: slides
+                .font_size
+                .as_ref()
+                .unwrap_or(&render.font_size)
+                .into(),
+            text_position: position(&render.text_position),
+            figure_position: position(&render.figure_position),
+            figure_size: (&render.figure_size).into(),
+            uniform_scale: false,
+            auto_contrast: false,
+            keep_blank_lines: false,
+        }
+    }
+}
+
+/// The fonts a slide is drawn with.
+#[derive(Clone, Copy)]
+struct Fonts<'f, 'a> {
+    /// The font of the text.
+    text: &'f sdl2::ttf::Font<'a, 'a>,
+    /// The font of the code: the built-in one, that is monospace.
+    code: &'f sdl2::ttf::Font<'a, 'a>,
+}
+
+impl<'f, 'a> Fonts<'f, 'a> {
+    /// The fonts, when the text is drawn with the font at `idx` out of the
+    /// `default` one and the `loaded` ones.
+    fn new(
+        default: &'f sdl2::ttf::Font<'a, 'a>,
+        loaded: &'f [(String, sdl2::ttf::Font<'a, 'a>)],
+        idx: usize,
+    ) -> Self {
+        Self {
+            text: pick_font(default, loaded, idx),
+            code: default,
+        }
+    }
+}
+
+/// The section the spotlight shows at full brightness, while the rest of the
+/// slide is dimmed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Focus {
+    /// The section under the mouse, at this point of the window.
+    Pointer((i32, i32)),
+    /// The section at this index in the slide.
+    Section(usize),
+}
+
+/// How large the picture in picture is, next to the main window.
+const PIP_SCALE: f32 = 0.25;
+
+/// The color of the border around the picture in picture.
+const PIP_BORDER: sdl2::pixels::Color =
+    sdl2::pixels::Color::RGBA(128, 128, 128, 255);
+
+/// How dark the spotlight makes what is not in focus.
+const SPOTLIGHT_DIM: sdl2::pixels::Color =
+    sdl2::pixels::Color::RGBA(0, 0, 0, 160);
+
+/// The window a slide is drawn in.
+#[derive(Clone, Copy)]
+enum Role {
+    /// The main window, that the audience sees. It can be an overlay, and
+    /// it can have a spotlight.
+    Main {
+        overlay: bool,
+        spotlight: Option<Focus>,
+    },
+    /// The side window, that only the presenter sees.
+    Presenter,
+}
+
+/// An ongoing fade between 2 slides.
+struct Transition {
+    /// The last frame of the slide we are leaving.
+    from: OwnedTexture,
+    /// When the transition started.
+    since: Instant,
+}
+
+/// What a window needs for the transitions: each window has its own, as the
+/// textures belong to the window they are drawn in.
+#[derive(Default)]
+struct Fade {
+    /// The last frame drawn in the window.
+    last_frame: Option<OwnedTexture>,
+    /// The ongoing transition, if any.
+    transition: Option<Transition>,
+}
+
+impl Fade {
+    /// Start fading out the last frame, if there is one.
+    fn start(&mut self) {
+        if let Some(from) = self.last_frame.take() {
+            self.transition = Some(Transition {
+                from,
+                since: Instant::now(),
+            });
+        }
+    }
+}
 
 /// The window holding the slideshow.
+#[allow(clippy::struct_excessive_bools)]
 pub struct Window<'a> {
     /// Contains the generic information for a window
     pub main_win: GenericWindow,
     /// Contains the information of the sidewindow
     pub side_win: GenericWindow,
+    /// The window showing the same slide as the main one, like for a second
+    /// screen, if it is open.
+    pub mirror_win: Option<GenericWindow>,
     /// The actual slide being shown.
     idx: usize,
     /// If the slide has to be drawn again.
@@ -22,54 +204,301 @@ pub struct Window<'a> {
     slides: slideshow::Slideshow,
     /// If the side slideshow should be visible.
     pub side_win_is_visible: bool,
+    /// If the next slide is shown in a corner of the main window, instead
+    /// of in the side window.
+    pub pip: bool,
+    /// If the navigation wraps around the first and the last slides.
+    pub wrap: bool,
+    /// If the chapters are ignored, and the slides are shown one after the
+    /// other.
+    pub linear: bool,
+    /// If changing slide fades from the old one to the new one.
+    pub transitions: bool,
+    /// The width / height ratio the slides are drawn with, if not the one of
+    /// the window.
+    pub aspect: Option<f32>,
+    /// If the sizes of the sections scale with the shorter side of the
+    /// window, instead of with its width and its height, so that they keep
+    /// their proportions when the window is resized.
+    pub uniform_scale: bool,
+    /// If the text is black or white, depending on the background, when the
+    /// slideshow does not set the font color.
+    pub auto_contrast: bool,
+    /// If the blank lines in the text sections move the next lines down,
+    /// instead of being skipped.
+    pub keep_blank_lines: bool,
+    /// Where the sections go, and how big they are, when neither they nor
+    /// the slideshow say it.
+    pub render_defaults: RenderDefaults,
+    /// If the main window's title shows the current slide's title and
+    /// number.
+    pub title_bar: bool,
+    /// The title last given to the main window, so that it is changed only
+    /// when the slide changes.
+    shown_title: Option<String>,
+    /// If the slide counter is shown in a corner of the main window.
+    pub show_counter: bool,
+    /// If the laser pointer is shown in the main window.
+    pub show_pointer: bool,
+    /// Where the mouse was last seen in the main window, in pixels.
+    pointer: Option<(i32, i32)>,
+    /// If the spotlight dims the main window, but for a section.
+    pub show_spotlight: bool,
+    /// The slide and the section picked for the spotlight: without it, the
+    /// spotlight follows the mouse.
+    focused: Option<(usize, usize)>,
+    /// The slides shown before the current one, to jump back to them.
+    history: History,
+    /// If the performance HUD is shown in the main window.
+    pub show_hud: bool,
+    /// The timings shown in the HUD.
+    frame_times: FrameTimes,
+    /// If the main window had no area last time: the frames are skipped
+    /// until it has one again, and this is logged only once.
+    zero_area: bool,
+    /// If the main window is an overlay, whose fully transparent background
+    /// lets what is behind it show up.
+    overlay: bool,
+    /// The transitions of the main window.
+    fade: Fade,
+    /// The transitions of the mirror window.
+    mirror_fade: Fade,
+    /// The texture the picture in picture is drawn in, kept while its size
+    /// does not change.
+    pip_frame: Option<OwnedTexture>,
+    /// When the current slide was shown first, to animate the sequences of
+    /// images.
+    shown_since: Instant,
     // Internal structure to hold the textures in order not to load them over
     // and over.
     /// The default font to be used.
     default_font: sdl2::ttf::Font<'a, 'a>,
+    /// The fonts of the slideshow, by name.
+    loaded_fonts: Vec<(String, sdl2::ttf::Font<'a, 'a>)>,
+    /// The font the text is drawn with: 0 is the default one, then come the
+    /// loaded ones.
+    current_default_font: usize,
 }
 
 impl<'a> Window<'a> {
-    #[must_use]
     /// Create a new SDL2 window.
+    ///
+    /// # Errors
+    ///
+    /// If the windows can't be built.
     pub fn new(
         context: &sdl2::Sdl,
         font: sdl2::ttf::Font<'a, 'a>,
         resizable: bool,
+        overlay: bool,
         h: u32,
         w: u32,
-    ) -> Self {
+    ) -> Result<Self, String> {
         let main_win =
-            GenericWindow::new(context, resizable, h, w, "Slideshow");
+            GenericWindow::new(context, resizable, overlay, h, w, "Slideshow")?;
         let mut side_win = GenericWindow::new(
             context,
             resizable,
+            false,
             h,
             w,
             "Slideshow: next slide",
-        );
+        )?;
         side_win.canvas.window_mut().hide();
 
         let slides = slideshow::Slideshow::default();
-        Window {
+        Ok(Window {
             main_win,
             side_win,
+            mirror_win: None,
             idx: 0,
             is_changed: true,
             slides,
             default_font: font,
+            loaded_fonts: Vec::new(),
+            current_default_font: 0,
             side_win_is_visible: false,
-        }
+            pip: false,
+            wrap: false,
+            linear: false,
+            transitions: false,
+            aspect: None,
+            uniform_scale: false,
+            auto_contrast: false,
+            keep_blank_lines: false,
+            render_defaults: RenderDefaults::default(),
+            title_bar: false,
+            shown_title: None,
+            show_counter: false,
+            show_pointer: false,
+            pointer: None,
+            show_spotlight: false,
+            focused: None,
+            history: History::default(),
+            show_hud: false,
+            frame_times: FrameTimes::default(),
+            zero_area: false,
+            overlay,
+            fade: Fade::default(),
+            mirror_fade: Fade::default(),
+            pip_frame: None,
+            shown_since: Instant::now(),
+        })
     }
 
-    /// Toggle visibility
+    /// Toggle the visibility of the next slide: in the side window, or in a
+    /// corner of the main one with `pip`.
     pub fn toggle_sideslide(&mut self) {
+        self.side_win_is_visible = !self.side_win_is_visible;
+        if self.pip {
+            self.is_changed = true;
+            return;
+        }
         let c = &mut self.side_win.canvas;
         if self.side_win_is_visible {
-            c.window_mut().hide();
-        } else {
             c.window_mut().show();
+        } else {
+            c.window_mut().hide();
+        }
+    }
+
+    /// Open the mirror window, which shows the same slide as the main one,
+    /// without the counter, the pointer and the HUD.
+    ///
+    /// # Errors
+    ///
+    /// If the window can't be built.
+    pub fn open_mirror(
+        &mut self,
+        context: &sdl2::Sdl,
+        resizable: bool,
+        h: u32,
+        w: u32,
+    ) -> Result<u32, String> {
+        let mirror_win = GenericWindow::new(
+            context,
+            resizable,
+            false,
+            h,
+            w,
+            "Slideshow: mirror",
+        )?;
+        let id = mirror_win.id;
+        self.mirror_win = Some(mirror_win);
+        self.preload_textures();
+        self.is_changed = true;
+        Ok(id)
+    }
+
+    /// Close the mirror window, if it is open.
+    pub fn close_mirror(&mut self) {
+        self.mirror_win = None;
+        self.mirror_fade = Fade::default();
+    }
+
+    /// Toggle the slide counter in the main window.
+    pub const fn toggle_counter(&mut self) {
+        self.show_counter = !self.show_counter;
+        self.is_changed = true;
+    }
+
+    /// Toggle the laser pointer in the main window.
+    pub const fn toggle_pointer(&mut self) {
+        self.show_pointer = !self.show_pointer;
+        self.is_changed = true;
+    }
+
+    /// Toggle the performance HUD in the main window.
+    pub const fn toggle_hud(&mut self) {
+        self.show_hud = !self.show_hud;
+        self.is_changed = true;
+    }
+
+    /// Use these `fonts`, by name, besides the default one. The font in use
+    /// is kept if it is still there.
+    pub fn set_fonts(&mut self, fonts: Vec<(String, sdl2::ttf::Font<'a, 'a>)>) {
+        let current = self
+            .current_default_font
+            .checked_sub(1)
+            .and_then(|idx| self.loaded_fonts.get(idx))
+            .map(|(name, _)| name.clone());
+        self.current_default_font = current
+            .and_then(|name| fonts.iter().position(|(n, _)| *n == name))
+            .map_or(0, |idx| idx + 1);
+        self.loaded_fonts = fonts;
+        self.is_changed = true;
+    }
+
+    /// Draw the text with the next font, out of the default one and the
+    /// loaded ones.
+    pub fn cycle_default_font(&mut self) {
+        self.current_default_font =
+            next_font(self.current_default_font, self.loaded_fonts.len());
+        let name = self
+            .current_default_font
+            .checked_sub(1)
+            .map_or("the default font", |idx| {
+                self.loaded_fonts[idx].0.as_str()
+            });
+        info!("Drawing the text with {}", name);
+        self.is_changed = true;
+    }
+
+    /// Store how long the last frames took. If the HUD is shown, it is drawn
+    /// again to show them.
+    pub const fn set_frame_times(&mut self, times: FrameTimes) {
+        self.frame_times = times;
+        if self.show_hud {
+            self.is_changed = true;
+        }
+    }
+
+    /// The mouse moved to (`x`, `y`) in the main window: the laser pointer
+    /// and the spotlight, if shown, follow it.
+    pub fn move_pointer(&mut self, x: i32, y: i32) {
+        self.pointer = Some((x, y));
+        if self.show_pointer || self.show_spotlight {
+            self.is_changed = true;
+        }
+    }
+
+    /// Toggle the spotlight in the main window. It starts by following the
+    /// mouse.
+    pub const fn toggle_spotlight(&mut self) {
+        self.show_spotlight = !self.show_spotlight;
+        self.focused = None;
+        self.is_changed = true;
+    }
+
+    /// Put the spotlight on the next section of the slide, going back to
+    /// the first one after the last.
+    pub fn focus_next_section(&mut self) {
+        let Some(slide) = self.slides.slides.get(self.idx) else {
+            return;
+        };
+        if !self.show_spotlight {
+            return;
+        }
+        let current = self
+            .focused
+            .filter(|(idx, _)| *idx == self.idx)
+            .map(|(_, section)| section);
+        self.focused = next_focus(slide, current).map(|s| (self.idx, s));
+        self.is_changed = true;
+    }
+
+    /// What the spotlight is on, if it is shown: the section picked in the
+    /// current slide, or else the one under the mouse.
+    fn spotlight(&self) -> Option<Focus> {
+        if !self.show_spotlight {
+            return None;
+        }
+        match self.focused {
+            Some((idx, section)) if idx == self.idx => {
+                Some(Focus::Section(section))
+            }
+            _ => self.pointer.map(Focus::Pointer),
         }
-        self.side_win_is_visible = !self.side_win_is_visible;
     }
 
     #[must_use]
@@ -79,50 +508,126 @@ impl<'a> Window<'a> {
         (self.idx, self.slides.slides.len())
     }
 
+    #[must_use]
+    /// Get the number of the current slide and the amount of slides, as
+    /// shown to the audience: the slides that are not counted are left out.
+    pub fn get_counted_counters(&self) -> (usize, usize) {
+        counted_position(&self.slides.slides, self.idx)
+    }
+
+    /// The slide counter, as shown to the audience.
+    fn counter_label(&self) -> String {
+        if self.linear {
+            let (number, total) = self.get_counted_counters();
+            format!("{number}/{total}")
+        } else {
+            position_label(&self.slides.slides, self.idx)
+        }
+    }
+
+    /// Show the current slide's title and number in the main window's title
+    /// bar, if they changed.
+    fn update_title(&mut self) {
+        let title = self.slides.slides.get(self.idx).and_then(Slide::title);
+        let title = window_title(title, &self.counter_label());
+        if self.shown_title.as_ref() != Some(&title) {
+            if let Err(e) = self.main_win.canvas.window_mut().set_title(&title)
+            {
+                error!("Unable to set the title {}: {}", title, e);
+            }
+            self.shown_title = Some(title);
+        }
+    }
+
     /// Show the next slide.
     pub fn next_slide(&mut self) {
-        if self.idx < self.slides.slides.len() - 1 {
-            self.idx += 1;
-            self.is_changed = true;
-        }
+        self.go_to(next_index(self.idx, self.slides.slides.len(), self.wrap));
     }
 
     /// Show the previous slide.
     pub fn prev_slide(&mut self) {
-        if self.idx > 0 {
-            self.idx -= 1;
-            self.is_changed = true;
+        self.go_to(prev_index(self.idx, self.slides.slides.len(), self.wrap));
+    }
+
+    /// Show the first slide of the next chapter.
+    pub fn next_chapter(&mut self) {
+        self.go_to(next_chapter(&self.slides.slides, self.idx, self.wrap));
+    }
+
+    /// Show the first slide of the previous chapter.
+    pub fn prev_chapter(&mut self) {
+        self.go_to(prev_chapter(&self.slides.slides, self.idx, self.wrap));
+    }
+
+    /// Show the slide at `idx`, or the last one if there are not so many
+    /// slides.
+    pub fn set_slide_index(&mut self, idx: usize) {
+        let last = self.slides.slides.len().saturating_sub(1);
+        self.go_to(idx.min(last));
+    }
+
+    /// Go back to the slide shown before the last jump, if any.
+    pub fn go_back(&mut self) {
+        let last = self.slides.slides.len().saturating_sub(1);
+        if let Some(idx) = self.history.pop() {
+            self.show(idx.min(last));
+        }
+    }
+
+    /// Show the slide at `idx`, remembering the current one in the history.
+    fn go_to(&mut self, idx: usize) {
+        if idx != self.idx {
+            self.history.push(self.idx);
+            self.show(idx);
         }
     }
 
-    /// Manage the keypresses, or any other even related to this very
-    /// window. We don't want other elements to manage our keys!
-    pub fn manage_keypress(&mut self, event: &Event) {
-        match event {
-            // KeyUp: N
-            Event::KeyUp {
-                keycode: Some(Keycode::N),
-                ..
-            } => self.next_slide(),
-            // KeyUp: P
-            Event::KeyUp {
-                keycode: Some(Keycode::P),
-                ..
-            } => self.prev_slide(),
+    /// Show the slide at `idx`, fading from the current one if the
+    /// transitions are enabled.
+    fn show(&mut self, idx: usize) {
+        if idx == self.idx {
+            return;
+        }
+        self.idx = idx;
+        self.is_changed = true;
+        self.shown_since = Instant::now();
+        if self.transitions {
+            self.fade.start();
+            self.mirror_fade.start();
+        }
+    }
+
+    /// Perform the actions related to this very window. We don't want other
+    /// elements to manage our keys!
+    pub fn manage_action(&mut self, action: Action) {
+        match action {
+            Action::Next if !self.linear => self.next_chapter(),
+            Action::Prev if !self.linear => self.prev_chapter(),
+            Action::Next | Action::NextSub => self.next_slide(),
+            Action::Prev | Action::PrevSub => self.prev_slide(),
+            Action::ToggleCounter => self.toggle_counter(),
+            Action::TogglePointer => self.toggle_pointer(),
+            Action::ToggleHud => self.toggle_hud(),
+            Action::CycleFont => self.cycle_default_font(),
+            Action::ToggleSpotlight => self.toggle_spotlight(),
+            Action::NextFocus => self.focus_next_section(),
+            Action::Back => self.go_back(),
             _ => {}
         }
     }
 
     /// If we remove some slide, we might have the index pointing in a location
     /// that does not exists anymore. This would be bad, and thus we simply
-    /// loop until we find the first good index.  Note that if we add a slide,
+    /// go back to the last available one.  Note that if we add a slide,
     /// we can't really know if we add a slide before of after. Imagine the
     /// case where we add the slide in position 3 and we are showing slide in
     /// position 3 already: we will just show the new slide.  @TODO is there a
     /// better way to do it?
-    fn set_first_good_slide(&mut self) {
-        while self.idx > self.slides.slides.len() - 1 {
-            self.prev_slide();
+    const fn set_first_good_slide(&mut self) {
+        let last = self.slides.slides.len().saturating_sub(1);
+        if self.idx > last {
+            self.idx = last;
+            self.is_changed = true;
         }
     }
 
@@ -138,217 +643,1454 @@ impl<'a> Window<'a> {
         self.is_changed = true;
     }
 
+    /// Load the images of the slides. The ones already loaded are kept,
+    /// unless their files changed since then.
     fn preload_textures(&mut self) {
-        self.main_win.remove_textures();
-        self.side_win.remove_textures();
-
-        for elem in &self.slides.slides {
-            for sec in &elem.sections {
-                if let Some(slideshow::SectionMain::Figure(fig)) = &sec.sec_main
-                {
-                    self.main_win.add_texture(&fig.path);
-                    self.side_win.add_texture(&fig.path);
-                }
+        let paths: HashMap<&str, String> = self
+            .slides
+            .slides
+            .iter()
+            .flat_map(Slide::image_paths)
+            .map(|path| (path, self.slides.resolve_path(path)))
+            .collect();
+        self.main_win.retain_textures(&paths);
+        self.side_win.retain_textures(&paths);
+        if let Some(mirror_win) = &mut self.mirror_win {
+            mirror_win.retain_textures(&paths);
+        }
+
+        for (key, file) in &paths {
+            self.main_win.add_texture(key, file);
+            self.side_win.add_texture(key, file);
+            if let Some(mirror_win) = &mut self.mirror_win {
+                mirror_win.add_texture(key, file);
             }
         }
     }
 
     /// Main method to show a slide on the screen.
     pub fn present_slide(&mut self) {
+        // Some window managers let the window shrink to nothing: there is
+        // nothing to draw in it, so try again at the next frame.
+        let size = self.main_win.canvas.window().size();
+        if !utils::has_area(size) {
+            if !self.zero_area {
+                warn!("The window is {}x{}, not drawing it.", size.0, size.1);
+                self.zero_area = true;
+            }
+            self.is_changed = true;
+            return;
+        }
+        self.zero_area = false;
         if self.slides.slides.is_empty() {
-            // Nothing is given, get some "default" slide to show.
-            self.slides.slides.push(slideshow::Slide::default());
+            // Nothing is given: say so, instead of showing a blank window.
+            self.present_placeholder();
+            return;
         }
         self.set_first_good_slide();
         // prepare the rects where to write the text
         // this is a loop over all the "sections" of a slide.
         // We technically "could" store the positions in order not to
         // recompute everything each time, but... Is it worth it? :)
-        let bg_col = self
-            .slides
-            .bg_col
-            .unwrap_or_else(|| sdl_color::WHITE.into());
-        let font_col = self
-            .slides
-            .font_col
-            .unwrap_or_else(|| sdl_color::BLACK.into());
-        let font_size = self
-            .slides
-            .font_size
-            .as_ref()
-            .map_or((0.018, 0.08), |r| (r.w, r.h));
+        let defaults = Defaults {
+            uniform_scale: self.uniform_scale,
+            auto_contrast: self.auto_contrast && self.slides.font_col.is_none(),
+            keep_blank_lines: self.keep_blank_lines,
+            ..Defaults::new(&self.slides, &self.render_defaults)
+        };
 
         // First slide window.
-        draw_sections(
-            self.idx,
-            &self.slides.slides,
-            bg_col,
-            &mut self.main_win,
-            font_size,
-            font_col,
+        if self.transitions {
+            self.present_main_with_transition(&defaults);
+        } else {
+            let spotlight = self.spotlight();
+            utils::set_content_viewport(&mut self.main_win.canvas, self.aspect);
+            draw_sections(
+                &self.slides.slides[self.idx],
+                &mut self.main_win.canvas,
+                &mut self.main_win.textures,
+                &defaults,
+                Fonts::new(
+                    &self.default_font,
+                    &self.loaded_fonts,
+                    self.current_default_font,
+                ),
+                Role::Main {
+                    overlay: self.overlay,
+                    spotlight,
+                },
+                self.shown_since.elapsed(),
+            );
+            self.main_win.canvas.set_viewport(None);
+        }
+        if self.title_bar {
+            self.update_title();
+        }
+        let next_idx = (self.idx + 1).min(self.slides.slides.len() - 1);
+        if self.pip && self.side_win_is_visible {
+            self.present_pip(&defaults, next_idx);
+        }
+        if self.show_counter {
+            let counter = self.counter_label();
+            draw_counter(
+                &mut self.main_win.canvas,
+                &self.default_font,
+                &counter,
+                defaults.font_col,
+            );
+        }
+        // The pointer goes over everything else.
+        if let (true, Some(pointer)) = (self.show_pointer, self.pointer) {
+            utils::draw_pointer(&mut self.main_win.canvas, pointer);
+        }
+        if self.show_hud {
+            let label =
+                hud_label(self.frame_times, self.main_win.textures.len());
+            draw_text_run(
+                &mut self.main_win.canvas,
+                &self.default_font,
+                &label,
+                defaults.font_col,
+                (0.01, 0.01),
+                COUNTER_LETTER_SIZE,
+            );
+        }
+
+        // Second slide window.
+        if utils::has_area(self.side_win.canvas.window().size()) {
+            utils::set_content_viewport(&mut self.side_win.canvas, self.aspect);
+            draw_sections(
+                &self.slides.slides[next_idx],
+                &mut self.side_win.canvas,
+                &mut self.side_win.textures,
+                &defaults,
+                Fonts::new(
+                    &self.default_font,
+                    &self.loaded_fonts,
+                    self.current_default_font,
+                ),
+                Role::Presenter,
+                Duration::ZERO,
+            );
+            self.side_win.canvas.set_viewport(None);
+        }
+        self.present_mirror(&defaults);
+
+        // Keep on drawing while the images of a sequence are shown.
+        let animated = self.slides.slides[self.idx]
+            .figures()
+            .any(slideshow::SectionFigure::is_sequence);
+        if animated {
+            self.is_changed = true;
+        }
+    }
+
+    /// Draw the [placeholder](Slide::placeholder) in each window, for this
+    /// frame only: the slides are left as they are, with none in them.
+    fn present_placeholder(&mut self) {
+        let placeholder = Slide::placeholder();
+        let defaults = Defaults::new(&self.slides, &self.render_defaults);
+        let audience = |overlay| Role::Main {
+            overlay,
+            spotlight: None,
+        };
+        let windows = [
+            (&mut self.main_win, audience(self.overlay)),
+            (&mut self.side_win, Role::Presenter),
+        ]
+        .into_iter()
+        .chain(self.mirror_win.as_mut().map(|w| (w, audience(false))));
+        for (window, role) in windows {
+            if !utils::has_area(window.canvas.window().size()) {
+                continue;
+            }
+            utils::set_content_viewport(&mut window.canvas, self.aspect);
+            draw_sections(
+                &placeholder,
+                &mut window.canvas,
+                &mut window.textures,
+                &defaults,
+                Fonts::new(
+                    &self.default_font,
+                    &self.loaded_fonts,
+                    self.current_default_font,
+                ),
+                role,
+                Duration::ZERO,
+            );
+            window.canvas.set_viewport(None);
+        }
+    }
+
+    /// Draw the slide at `idx` in a corner of the main window: it is drawn
+    /// in a texture, and then copied there. The audience sees it, so what is
+    /// only for the presenter is left out.
+    fn present_pip(&mut self, defaults: &Defaults, idx: usize) {
+        let fonts = Fonts::new(
             &self.default_font,
+            &self.loaded_fonts,
+            self.current_default_font,
         );
+        let canvas = &mut self.main_win.canvas;
+        let textures = &mut self.main_win.textures;
+        let slide = &self.slides.slides[idx];
+        let rect =
+            utils::pip_rect(canvas.window().size(), self.aspect, PIP_SCALE);
+        let cached = self.pip_frame.take().filter(|frame| {
+            let query = frame.query();
+            (query.width, query.height) == rect.size()
+        });
+        let creator = canvas.texture_creator();
+        let mut frame = match cached.map_or_else(
+            || OwnedTexture::new_target(creator, rect.width(), rect.height()),
+            Ok,
+        ) {
+            Ok(frame) => frame,
+            Err(e) => {
+                error!("Unable to build the picture in picture: {e}");
+                return;
+            }
+        };
+        if let Err(e) = canvas.with_texture_canvas(&mut frame, |c| {
+            let role = Role::Main {
+                overlay: false,
+                spotlight: None,
+            };
+            draw_sections(
+                slide,
+                c,
+                textures,
+                defaults,
+                fonts,
+                role,
+                Duration::ZERO,
+            );
+        }) {
+            error!("Unable to draw the picture in picture: {e}");
+        }
+        if let Err(e) = canvas.copy(&frame, None, rect) {
+            error!("Unable to copy the picture in picture: {e}");
+        }
+        canvas.set_draw_color(PIP_BORDER);
+        if let Err(e) = canvas.draw_rect(rect) {
+            error!("Unable to draw the border of the picture in picture: {e}");
+        }
+        self.pip_frame = Some(frame);
+    }
 
-        // Second slide window.
-        let next_idx = if self.idx < self.slides.slides.len() - 1 {
-            self.idx + 1
-        } else {
-            self.idx
+    /// Draw the current slide in the mirror window, if it is open, as in the
+    /// main one, transitions included: the counter, the pointer and the HUD
+    /// stay in the main one.
+    fn present_mirror(&mut self, defaults: &Defaults) {
+        // The pointer is over the main window: it is moved to the same place
+        // of the slide in the mirror.
+        let main_size = self.main_win.canvas.window().size();
+        let spotlight = self.spotlight();
+        let Some(mirror_win) = &mut self.mirror_win else {
+            return;
         };
-        draw_sections(
-            next_idx,
-            &self.slides.slides,
-            bg_col,
-            &mut self.side_win,
-            font_size,
-            font_col,
+        let mirror_size = mirror_win.canvas.window().size();
+        if !utils::has_area(mirror_size) {
+            return;
+        }
+        let spotlight = spotlight.map(|focus| match focus {
+            Focus::Pointer(point) => Focus::Pointer(utils::map_point(
+                point,
+                utils::content_rect(main_size, self.aspect),
+                utils::content_rect(mirror_size, self.aspect),
+            )),
+            Focus::Section(_) => focus,
+        });
+        let role = Role::Main {
+            overlay: false,
+            spotlight,
+        };
+        let fonts = Fonts::new(
+            &self.default_font,
+            &self.loaded_fonts,
+            self.current_default_font,
+        );
+        let slide = &self.slides.slides[self.idx];
+        let elapsed = self.shown_since.elapsed();
+        if !self.transitions {
+            utils::set_content_viewport(&mut mirror_win.canvas, self.aspect);
+            draw_sections(
+                slide,
+                &mut mirror_win.canvas,
+                &mut mirror_win.textures,
+                defaults,
+                fonts,
+                role,
+                elapsed,
+            );
+            mirror_win.canvas.set_viewport(None);
+            return;
+        }
+        let what = (slide, defaults, fonts, role, elapsed);
+        if draw_with_transition(
+            mirror_win,
+            &mut self.mirror_fade,
+            what,
+            self.aspect,
+        ) {
+            self.is_changed = true;
+        }
+    }
+
+    /// Draw the current slide in the main window, fading out the previous
+    /// one if a transition is ongoing.
+    fn present_main_with_transition(&mut self, defaults: &Defaults) {
+        let role = Role::Main {
+            overlay: self.overlay,
+            spotlight: self.spotlight(),
+        };
+        let fonts = Fonts::new(
             &self.default_font,
+            &self.loaded_fonts,
+            self.current_default_font,
         );
+        let slide = &self.slides.slides[self.idx];
+        let what = (slide, defaults, fonts, role, self.shown_since.elapsed());
+        if draw_with_transition(
+            &mut self.main_win,
+            &mut self.fade,
+            what,
+            self.aspect,
+        ) {
+            self.is_changed = true;
+        }
+    }
+}
+
+/// What [`draw_sections`] draws: the slide, the defaults, the fonts, the
+/// role of the window and how long the slide has been shown.
+type Drawn<'s, 'f, 'a> = (
+    &'s slideshow::Slide,
+    &'s Defaults,
+    Fonts<'f, 'a>,
+    Role,
+    Duration,
+);
+
+/// Draw a slide in `win`, passing through a texture so that the frame can be
+/// kept in `fade` for the next transition. If a transition is ongoing, the
+/// previous slide is faded out on top of the current one: tell whether it
+/// still is, so that the window is drawn again.
+fn draw_with_transition(
+    win: &mut GenericWindow,
+    fade: &mut Fade,
+    (slide, defaults, fonts, role, elapsed): Drawn,
+    aspect: Option<f32>,
+) -> bool {
+    let canvas = &mut win.canvas;
+    let textures = &mut win.textures;
+    let (w, h) = canvas.window().size();
+    let mut frame =
+        match OwnedTexture::new_target(canvas.texture_creator(), w, h) {
+            Ok(frame) => frame,
+            Err(e) => {
+                error!("Unable to build the frame for the transition: {e}");
+                fade.transition = None;
+                utils::set_content_viewport(canvas, aspect);
+                draw_sections(
+                    slide, canvas, textures, defaults, fonts, role, elapsed,
+                );
+                canvas.set_viewport(None);
+                return false;
+            }
+        };
+    if let Err(e) = canvas.with_texture_canvas(&mut frame, |c| {
+        utils::set_content_viewport(c, aspect);
+        draw_sections(slide, c, textures, defaults, fonts, role, elapsed);
+        c.set_viewport(None);
+    }) {
+        error!("Unable to draw the slide in the frame: {e}");
+    }
+    if let Err(e) = canvas.copy(&frame, None, None) {
+        error!("Unable to copy the frame on the window: {e}");
+    }
+
+    let mut ongoing = false;
+    if let Some(transition) = &mut fade.transition {
+        let elapsed = transition.since.elapsed();
+        if elapsed < TRANSITION_DURATION {
+            let left =
+                1.0 - elapsed.as_secs_f32() / TRANSITION_DURATION.as_secs_f32();
+            #[allow(clippy::cast_possible_truncation)]
+            #[allow(clippy::cast_sign_loss)]
+            let alpha = (left * 255.0) as u8;
+            transition.from.set_blend_mode(BlendMode::Blend);
+            transition.from.set_alpha_mod(alpha);
+            if let Err(e) = canvas.copy(&transition.from, None, None) {
+                error!("Unable to draw the transition: {e}");
+            }
+            // Keep on drawing until the transition is over.
+            ongoing = true;
+        } else {
+            fade.transition = None;
+        }
+    }
+    fade.last_frame = Some(frame);
+    ongoing
+}
+
+/// The point a figure drawn in `rect` rotates around, given its `anchor`:
+/// the point is relative to the top left corner of `rect`.
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_precision_loss)]
+fn rotation_center(anchor: &slideshow::Position, rect: Rect) -> Point {
+    Point::new(
+        (anchor.x * rect.width() as f32).round() as i32,
+        (anchor.y * rect.height() as f32).round() as i32,
+    )
+}
+
+/// How the pieces of a turned text are drawn: the angle, in degrees, and the
+/// point of the window they all turn around.
+type Turn = (f64, Point);
+
+/// Draw `texture` in `rect`, turned by `turn`, if any.
+fn copy_turned<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    texture: &OwnedTexture<T::Context>,
+    rect: Rect,
+    turn: Option<Turn>,
+) -> Result<(), String> {
+    let Some((angle, center)) = turn else {
+        return canvas.copy(texture, None, rect);
+    };
+    // SDL wants the center relative to the rect.
+    let center = center - rect.top_left();
+    canvas.copy_ex(texture, None, rect, angle, center, false, false)
+}
+
+/// Draw a figure section, and get where it was drawn.
+fn draw_figure<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    textures: &mut HashMap<String, OwnedTexture<T::Context>>,
+    elem: &slideshow::Section,
+    fig: &slideshow::SectionFigure,
+    elapsed: Duration,
+    defaults: &Defaults,
+) -> Option<Rect> {
+    let path = fig.frame_path(elapsed);
+    let Some(texture) = textures.get_mut(path) else {
+        error!("Texture at {} was not ready", path);
+        return None;
+    };
+    // if we have a path, the section cannot contain anything else
+    let ((x_start, y_start), (x_size, y_size)) =
+        figure_box(elem, defaults, T::area(canvas));
+    let rect = utils::get_scaled_rect(
+        T::area(canvas),
+        x_start,
+        y_start,
+        x_size,
+        y_size,
+    );
+    if let Some(bg) = elem.bg_color {
+        utils::fill_box(canvas, bg.into(), rect);
+    }
+    // The texture is shared by all the figures with the same path: fade it
+    // only while drawing this one.
+    let blend_mode = texture.blend_mode();
+    if fig.opacity < 1.0 {
+        #[allow(clippy::cast_possible_truncation)]
+        #[allow(clippy::cast_sign_loss)]
+        let alpha = (fig.opacity * 255.0) as u8;
+        texture.set_blend_mode(BlendMode::Blend);
+        texture.set_alpha_mod(alpha);
+    }
+    if let Err(e) = canvas.copy_ex(
+        texture,
+        None,
+        rect,
+        fig.rotation.into(),
+        fig.anchor.as_ref().map(|a| rotation_center(a, rect)),
+        false,
+        false,
+    ) {
+        error!("Unable to draw the figure at {}: {e}", path);
+    }
+    texture.set_alpha_mod(u8::MAX);
+    texture.set_blend_mode(blend_mode);
+    Some(rect)
+}
+
+/// Where a figure goes, and how big it is, relative to the window.
+fn figure_box(
+    elem: &slideshow::Section,
+    defaults: &Defaults,
+    area: (u32, u32),
+) -> ((f32, f32), (f32, f32)) {
+    let start = elem
+        .position_or(&defaults.figure_position.into())
+        .map_or(defaults.figure_position, |p| (p.x, p.y));
+    let size = utils::window_size(
+        area,
+        elem.size
+            .as_ref()
+            .map_or(defaults.figure_size, |s| (s.w, s.h)),
+        defaults.uniform_scale,
+    );
+    (start, size)
+}
+
+/// Draw the caption of a figure, centered below it, with letters smaller
+/// than the text ones.
+fn draw_caption<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    font: &sdl2::ttf::Font<'_, '_>,
+    elem: &slideshow::Section,
+    caption: &str,
+    defaults: &Defaults,
+) {
+    let area = T::area(canvas);
+    let ((x_start, y_start), (x_size, y_size)) =
+        figure_box(elem, defaults, area);
+    let (w, h) = defaults.font_size;
+    let letter = utils::window_size(
+        area,
+        (w * CAPTION_SCALE, h * CAPTION_SCALE),
+        defaults.uniform_scale,
+    );
+    let pos =
+        caption_position((x_start, y_start), (x_size, y_size), caption, letter);
+    draw_text_run(canvas, font, caption, defaults.font_col, pos, letter);
+}
+
+/// Where a `caption` with `letter` sized letters starts, to be centered
+/// below the figure at `start`, of `size`.
+#[allow(clippy::cast_precision_loss)]
+fn caption_position(
+    start: (f32, f32),
+    size: (f32, f32),
+    caption: &str,
+    letter: (f32, f32),
+) -> (f32, f32) {
+    // As in `draw_text_run`, each byte of the text takes a letter.
+    let width = caption.len() as f32 * letter.0;
+    (start.0 + (size.0 - width) / 2.0, start.1 + size.1)
+}
+
+/// Draw a section, and get where it was drawn, if it shows something.
+fn draw_single_section<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    textures: &mut HashMap<String, OwnedTexture<T::Context>>,
+    elem: &slideshow::Section,
+    base_height: &mut f32,
+    fonts: Fonts<'_, '_>,
+    defaults: &Defaults,
+    elapsed: Duration,
+) -> Option<Rect> {
+    match elem.sec_main.as_ref()? {
+        // Manage pictures
+        slideshow::SectionMain::Figure(fig) => {
+            let rect =
+                draw_figure(canvas, textures, elem, fig, elapsed, defaults);
+            if let Some(caption) = &fig.caption {
+                draw_caption(canvas, fonts.text, elem, caption, defaults);
+            }
+            rect
+        }
+        // Manage text
+        slideshow::SectionMain::Text(text) => draw_text(
+            canvas,
+            textures,
+            elem,
+            text,
+            base_height,
+            if text.is_code { fonts.code } else { fonts.text },
+            defaults,
+        ),
     }
 }
 
-fn draw_single_section<'a>(
-    window: &mut GenericWindow,
+/// Draw a text section, below `base_height` if it has no position, and get
+/// the box its lines take, if it has some.
+fn draw_text<'a, T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    textures: &HashMap<String, OwnedTexture<T::Context>>,
     elem: &slideshow::Section,
+    text: &slideshow::SectionText,
     base_height: &mut f32,
     default_font: &sdl2::ttf::Font<'a, 'a>,
-    font_size: (f32, f32),
+    defaults: &Defaults,
+) -> Option<Rect> {
+    let sec_text = text;
+    let slideshow::SectionText {
+        text,
+        color,
+        font: _new_font,
+        box_width,
+        line_height,
+        vcenter,
+        is_code,
+        lang: _,
+        fit,
+        columns,
+        rotation,
+    } = text;
+    let (font_size, font_col) = (defaults.font_size, defaults.font_col);
+    // The code is never wrapped.
+    let box_width = box_width.filter(|_| !is_code);
+    // Get the default size for each letter.
+    let size = elem.size.as_ref().map_or(font_size, |p| (p.w, p.h));
+    // How many letters fit in the box, if there is one.
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    let max_chars = box_width.map(|w| (w / size.0).floor() as usize);
+    // From now on, the sizes are relative to the window, like the positions.
+    let area = T::area(canvas);
+    let uniform = defaults.uniform_scale;
+    let (x_size, y_size) = utils::window_size(area, size, uniform);
+    let box_width =
+        box_width.map(|w| utils::window_size(area, (w, 0.0), uniform).0);
+    // How much each line is lower than the previous one.
+    let line_step = y_size * line_height.unwrap_or(1.0);
+    let lines = wrapped_lines(text, max_chars.filter(|_| columns.is_none()));
+    // The letters shrink until the text fits in its box, if it has one.
+    let scale = fit.as_ref().map_or(1.0, |fit| {
+        let fit = utils::window_size(area, (fit.w, fit.h), uniform);
+        fit_scale(&lines, (x_size, y_size), line_step, fit)
+    });
+    let (x_size, y_size, line_step) =
+        (x_size * scale, y_size * scale, line_step * scale);
+
+    let start = (defaults.text_position.0, *base_height);
+    let mut position = elem.position_or(&start.into());
+    // The empty lines take some space when there is a position, or when they
+    // are kept.
+    let keep_blank = defaults.keep_blank_lines;
+    let block = text_block(&lines, keep_blank || position.is_some());
+    if let (true, Some((above, rows))) = (*vcenter, block) {
+        let top =
+            line_step.mul_add(-above, centered_top(rows, line_step, y_size));
+        if let Some(p) = &mut position {
+            p.y = top;
+        } else {
+            *base_height = top;
+        }
+    }
+    // The box of the text goes from the first line to the last one.
+    let text_box = block.map(|block| {
+        let start = position
+            .as_ref()
+            .map_or((defaults.text_position.0, *base_height), |p| (p.x, p.y));
+        let letter = (x_size, y_size);
+        text_box(&lines, block, start, box_width, letter, line_step)
+    });
+    let text_rect =
+        text_box.map(|(x, y, w, h)| utils::get_scaled_rect(area, x, y, w, h));
+    // The lines turn around the middle of their box, with it.
+    let turn = text_rect
+        .filter(|_| *rotation != 0.0)
+        .map(|rect| (f64::from(*rotation), rect.center()));
+    if let (Some(text_box), Some(rect)) = (text_box, text_rect) {
+        let letter = (x_size, y_size);
+        draw_text_box(
+            canvas,
+            default_font,
+            elem,
+            sec_text,
+            font_col,
+            letter,
+            (text_box, rect, turn),
+        );
+    }
+
+    // The text ends with a new line, that is not a blank line.
+    let n_lines = lines.len();
+    for (idx, chunk) in lines.into_iter().enumerate() {
+        #[allow(clippy::cast_possible_truncation)]
+        #[allow(clippy::cast_sign_loss)]
+        let f32_max_usize = f32::MAX.ceil() as usize;
+        assert!(idx <= f32_max_usize);
+        #[allow(clippy::cast_precision_loss)]
+        let idx_f32 = idx as f32;
+
+        if chunk.is_empty() {
+            if keep_blank && idx + 1 < n_lines {
+                *base_height += line_step;
+            }
+            continue;
+        }
+
+        let (x_start, y_start) = position.as_ref().map_or(
+            // If we don't have any default, starts from base_height
+            (defaults.text_position.0, *base_height),
+            // Each line starts 0.1 lower than the size
+            |p| (p.x, line_step.mul_add(idx_f32, p.y)),
+        );
+        // Update base_height so what next run we already are
+        // down this much and we won't overwrite new text.
+        *base_height += line_step;
+        draw_row(
+            canvas,
+            textures,
+            default_font,
+            (sec_text, idx, chunk),
+            (color.unwrap_or(font_col), turn),
+            (x_start, y_start),
+            (x_size, y_size),
+        );
+    }
+    text_rect
+}
+
+/// Draw the box behind a text, if it has one, in `rect`, turned by `turn`,
+/// if any: the code always has one, with its language above its top-right
+/// corner.
+fn draw_text_box<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    font: &sdl2::ttf::Font<'_, '_>,
+    elem: &slideshow::Section,
+    text: &slideshow::SectionText,
     font_col: slideshow::Color,
+    letter: (f32, f32),
+    ((x, y, width, _), rect, turn): ((f32, f32, f32, f32), Rect, Option<Turn>),
 ) {
-    let canvas = &mut window.canvas;
-    let textures = &mut window.textures;
-
-    if let Some(sec_main) = &elem.sec_main {
-        match sec_main {
-            // Manage pictures
-            slideshow::SectionMain::Figure(fig) => {
-                {
-                    textures.get(&fig.path).map_or_else(
-                        || {
-                            error!("Texture at {} was not ready", fig.path);
-                        },
-                        |texture| {
-                            // if we have a path, the section cannot contain anything else
-                            let (x_start, y_start) = elem
-                                .position
-                                .as_ref()
-                                .map_or((0.01, 0.01), |p| (p.x, p.y));
-                            let (x_size, y_size) = elem
-                                .size
-                                .as_ref()
-                                .map_or((0.1, 0.1), |p| (p.w, p.h));
-                            let rect = utils::get_scaled_rect(
-                                canvas.window(),
-                                x_start,
-                                y_start,
-                                x_size,
-                                y_size,
-                            );
-                            canvas
-                                .copy_ex(
-                                    texture,
-                                    None,
-                                    rect,
-                                    fig.rotation.into(),
-                                    None,
-                                    false,
-                                    false,
-                                )
-                                .unwrap();
-                        },
-                    );
-                }
-            }
-            // Manage text
-            slideshow::SectionMain::Text(slideshow::SectionText {
-                text,
+    let bg_color = elem.bg_color.or_else(|| {
+        text.is_code.then_some(slideshow::Color {
+            a: CODE_BG_ALPHA,
+            ..font_col
+        })
+    });
+    let Some(bg) = bg_color else {
+        return;
+    };
+    fill_turned_box(canvas, bg.into(), rect, turn);
+    if let (true, Some(lang)) = (text.is_code, &text.lang) {
+        draw_lang(canvas, font, lang, font_col, (x + width, y), letter, turn);
+    }
+}
+
+/// Fill `rect` with `color`, turned by `turn`, if any: then, a single pixel
+/// of that color is stretched over it.
+fn fill_turned_box<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    color: sdl2::pixels::Color,
+    rect: Rect,
+    turn: Option<Turn>,
+) {
+    if turn.is_none() {
+        utils::fill_box(canvas, color, rect);
+        return;
+    }
+    let pixel =
+        Surface::new(1, 1, PixelFormatEnum::RGBA32).and_then(|mut s| {
+            s.fill_rect(None, color)?;
+            Ok(s)
+        });
+    let texture = pixel.and_then(|pixel| {
+        OwnedTexture::from_surface(T::texture_creator(canvas), &pixel)
+            .map_err(|e| e.to_string())
+    });
+    let drawn = texture.and_then(|mut texture| {
+        texture.set_blend_mode(BlendMode::Blend);
+        copy_turned(canvas, &texture, rect, turn)
+    });
+    if let Err(e) = drawn {
+        error!("Unable to draw the box {:?}: {}", rect, e);
+    }
+}
+
+/// The box the `block` of `lines` takes, as `(x, y, width, height)`, when
+/// the lines start at `start`. Without a `box_width`, it is as wide as the
+/// longest line.
+fn text_box(
+    lines: &[&str],
+    (above, rows): (f32, usize),
+    (x, top): (f32, f32),
+    box_width: Option<f32>,
+    (x_size, y_size): (f32, f32),
+    line_step: f32,
+) -> (f32, f32, f32, f32) {
+    let y = line_step.mul_add(above, top);
+    #[allow(clippy::cast_precision_loss)]
+    let width = box_width.unwrap_or_else(|| {
+        let longest = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+        longest as f32 * x_size
+    });
+    #[allow(clippy::cast_precision_loss)]
+    let height = line_step.mul_add(rows.saturating_sub(1) as f32, y_size);
+    (x, y, width, height)
+}
+
+/// Draw the `line` at `row` of `text`, from `start`. The cells of a text laid
+/// out in columns are drawn one by one, so that they are aligned whatever
+/// the font is.
+fn draw_row<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    textures: &HashMap<String, OwnedTexture<T::Context>>,
+    font: &sdl2::ttf::Font<'_, '_>,
+    (text, row, line): (&slideshow::SectionText, usize, &str),
+    color: (slideshow::Color, Option<Turn>),
+    (x_start, y_start): (f32, f32),
+    (x_size, y_size): (f32, f32),
+) {
+    let cells = text.cells(row).unwrap_or_else(|| vec![(0, line)]);
+    for (start, cell) in cells.into_iter().filter(|(_, c)| !c.is_empty()) {
+        #[allow(clippy::cast_precision_loss)]
+        let x_cell = (start as f32).mul_add(x_size, x_start);
+        draw_line(
+            canvas,
+            textures,
+            font,
+            cell,
+            color,
+            (x_cell, y_start),
+            (x_size, y_size),
+        );
+    }
+}
+
+/// Draw a line of text, with its inline images, from `start`, turned by
+/// `turn`, if any.
+fn draw_line<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    textures: &HashMap<String, OwnedTexture<T::Context>>,
+    font: &sdl2::ttf::Font<'_, '_>,
+    line: &str,
+    (color, turn): (slideshow::Color, Option<Turn>),
+    (x_start, y_start): (f32, f32),
+    (x_size, y_size): (f32, f32),
+) {
+    // Each run of the line starts where the previous one ended.
+    let mut x_run = x_start;
+    for run in TextRun::split(line) {
+        x_run += match run {
+            TextRun::Text(run_text) => draw_turned_run(
+                canvas,
+                font,
+                run_text,
                 color,
-                font: _new_font,
-            }) => {
-                let text_slice = text.as_str();
-                for (idx, chunk) in text_slice.split('\n').enumerate() {
-                    #[allow(clippy::cast_possible_truncation)]
-                    #[allow(clippy::cast_sign_loss)]
-                    let f32_max_usize = f32::MAX.ceil() as usize;
-                    assert!(idx <= f32_max_usize);
-                    #[allow(clippy::cast_precision_loss)]
-                    let idx_f32 = idx as f32;
-
-                    if chunk.is_empty() {
-                        continue;
-                    }
-
-                    assert!(chunk.len() <= f32_max_usize);
-                    #[allow(clippy::cast_sign_loss)]
-                    #[allow(clippy::cast_precision_loss)]
-                    let chunk_len = chunk.len() as f32;
-
-                    // Get the default size for each letter.
-                    let (x_size, y_size) =
-                        elem.size.as_ref().map_or(font_size, |p| (p.w, p.h));
-                    let (x_start, y_start) = elem.position.as_ref().map_or(
-                        // If we don't have any default, starts from base_height
-                        // and 0.01
-                        (0.01, *base_height),
-                        // Each line starts 0.1 lower than the size
-                        |p| (p.x, y_size.mul_add(idx_f32, p.y)),
-                    );
-                    // Update base_height so what next run we already are
-                    // down this much and we won't overwrite new text.
-                    *base_height += y_size;
-                    // The chunk size is the whole line.
-                    // We build a single rect that contains the whole line.
-                    let chunk_size: f32 = chunk_len * x_size;
-                    let rect = utils::get_scaled_rect(
-                        canvas.window(),
-                        x_start,
-                        y_start,
-                        chunk_size,
-                        y_size,
-                    );
-                    //let rect = Rect::new(x_start, y_start, chunk_size, 0.01);
-                    let surface_text = default_font
-                        .render(chunk)
-                        .solid(color.map_or(font_col, |c| c))
-                        .unwrap();
-                    let texture_creator = canvas.texture_creator();
-                    let texture =
-                        surface_text.as_texture(&texture_creator).unwrap();
-                    canvas.copy(&texture, None, rect).unwrap();
-                    // @safety This is ok, since the texture has been copied to the canvas and we can
-                    // safely remove the one in here.
-                    unsafe {
-                        texture.destroy();
-                    }
-                }
-            }
-        }
+                (x_run, y_start),
+                (x_size, y_size),
+                turn,
+            ),
+            TextRun::Image { path, .. } => draw_inline_image(
+                canvas,
+                textures,
+                path,
+                (x_run, y_start),
+                y_size,
+                turn,
+            ),
+        };
     }
 }
 
-fn draw_sections(
+/// Draw the language of some code above the top-right `corner` of its box,
+/// with letters half as high as the code's ones, turned by `turn`, if any.
+fn draw_lang<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    font: &sdl2::ttf::Font<'_, '_>,
+    lang: &str,
+    color: slideshow::Color,
+    (right, top): (f32, f32),
+    (x_size, y_size): (f32, f32),
+    turn: Option<Turn>,
+) {
+    let letter = (x_size / 2.0, y_size / 2.0);
+    #[allow(clippy::cast_precision_loss)]
+    let len = lang.chars().count() as f32;
+    let pos = (len.mul_add(-letter.0, right), top - letter.1);
+    draw_turned_run(canvas, font, lang, color, pos, letter, turn);
+}
+
+/// The title of the main window, for a slide with `title` and `counter`.
+/// Long titles are cut, since some window managers show them poorly.
+fn window_title(title: Option<&str>, counter: &str) -> String {
+    const MAX_CHARS: usize = 40;
+    let title = title.unwrap_or(slideshow::UNTITLED);
+    if title.chars().count() > MAX_CHARS {
+        let cut: String = title.chars().take(MAX_CHARS - 1).collect();
+        format!("Slideshow: {cut}… ({counter})")
+    } else {
+        format!("Slideshow: {title} ({counter})")
+    }
+}
+
+/// Where the lines with some text are, out of `lines`: how many lines are
+/// above the first one, and how many lines there are from it to the last one.
+/// Unless the empty lines are `spaced`, they take no space.
+#[allow(clippy::cast_precision_loss)]
+fn text_block(lines: &[&str], spaced: bool) -> Option<(f32, usize)> {
+    let first = lines.iter().position(|l| !l.is_empty())?;
+    let last = lines.iter().rposition(|l| !l.is_empty())?;
+    if spaced {
+        Some((first as f32, last - first + 1))
+    } else {
+        Some((0.0, lines.iter().filter(|l| !l.is_empty()).count()))
+    }
+}
+
+/// The lines of `text`, wrapped at `max_chars` letters, if any.
+fn wrapped_lines(text: &str, max_chars: Option<usize>) -> Vec<&str> {
+    text.split('\n')
+        .flat_map(|chunk| {
+            max_chars
+                .map_or_else(|| vec![chunk], |max| utils::wrap_line(chunk, max))
+        })
+        .collect()
+}
+
+/// How much the letters of `lines`, of `letter` size and `line_step` apart,
+/// must shrink so that the text fits in a `fit` box: 1 if it already does.
+///
+/// The text is stretched on a grid of letters when drawn, so its size does
+/// not depend on the font: the largest size that fits is known right away.
+#[allow(clippy::cast_precision_loss)]
+fn fit_scale(
+    lines: &[&str],
+    letter: (f32, f32),
+    line_step: f32,
+    fit: (f32, f32),
+) -> f32 {
+    // As in `draw_text_run`, each byte of the text takes a letter.
+    let widest = lines
+        .iter()
+        .map(|line| {
+            TextRun::split(line)
+                .iter()
+                .map(|run| match run {
+                    TextRun::Text(text) => text.len(),
+                    TextRun::Image { .. } => 0,
+                })
+                .sum::<usize>()
+        })
+        .max()
+        .unwrap_or(0);
+    let width = widest as f32 * letter.0;
+    let height = text_block(lines, true).map_or(0.0, |(_, rows)| {
+        line_step.mul_add(rows.saturating_sub(1) as f32, letter.1)
+    });
+    let mut scale: f32 = 1.0;
+    if width > 0.0 {
+        scale = scale.min(fit.0 / width);
+    }
+    if height > 0.0 {
+        scale = scale.min(fit.1 / height);
+    }
+    scale
+}
+
+/// Where the first of `rows` lines goes, so that they are in the middle of
+/// the slide.
+#[allow(clippy::cast_precision_loss)]
+fn centered_top(rows: usize, line_step: f32, y_size: f32) -> f32 {
+    let height = line_step.mul_add(rows.saturating_sub(1) as f32, y_size);
+    (1.0 - height) / 2.0
+}
+
+/// The font at `idx`, out of the `default` one (at 0) and the `loaded` ones.
+fn pick_font<'f, 'a>(
+    default: &'f sdl2::ttf::Font<'a, 'a>,
+    loaded: &'f [(String, sdl2::ttf::Font<'a, 'a>)],
     idx: usize,
-    slides: &[slideshow::Slide],
-    bg_col: slideshow::Color,
-    window: &mut GenericWindow,
-    font_size: (f32, f32),
-    font_col: slideshow::Color,
-    default_font: &sdl2::ttf::Font<'_, '_>,
+) -> &'f sdl2::ttf::Font<'a, 'a> {
+    idx.checked_sub(1)
+        .and_then(|idx| loaded.get(idx))
+        .map_or(default, |(_, font)| font)
+}
+
+/// The index of the font after `current`, when `loaded` fonts are there
+/// besides the default one.
+const fn next_font(current: usize, loaded: usize) -> usize {
+    (current + 1) % (loaded + 1)
+}
+
+/// Draw the slide counter in the bottom-right corner.
+fn draw_counter<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    font: &sdl2::ttf::Font<'_, '_>,
+    counter: &str,
+    color: slideshow::Color,
 ) {
-    let mut base_height: f32 = 0.01;
-    let col = slides[idx].bg_color.unwrap_or(bg_col).into();
+    #[allow(clippy::cast_precision_loss)]
+    let width = counter.len() as f32 * COUNTER_LETTER_SIZE.0;
+    let pos = (0.99 - width, 0.99 - COUNTER_LETTER_SIZE.1);
+    draw_text_run(canvas, font, counter, color, pos, COUNTER_LETTER_SIZE);
+}
+
+/// Draw a chunk of text starting at `pos`, and return the width it took. A
+/// chunk that can't be drawn is skipped, but it still takes its room.
+fn draw_text_run<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    font: &sdl2::ttf::Font<'_, '_>,
+    text: &str,
+    color: slideshow::Color,
+    pos: (f32, f32),
+    letter_size: (f32, f32),
+) -> f32 {
+    draw_turned_run(canvas, font, text, color, pos, letter_size, None)
+}
+
+/// Draw a chunk of text like [`draw_text_run`] does, turned by `turn`, if
+/// any.
+fn draw_turned_run<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    font: &sdl2::ttf::Font<'_, '_>,
+    text: &str,
+    color: slideshow::Color,
+    pos: (f32, f32),
+    letter_size: (f32, f32),
+    turn: Option<Turn>,
+) -> f32 {
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    let f32_max_usize = f32::MAX.ceil() as usize;
+    assert!(text.len() <= f32_max_usize);
+    #[allow(clippy::cast_precision_loss)]
+    let text_len = text.len() as f32;
+
+    // We build a single rect that contains the whole run.
+    let width = text_len * letter_size.0;
+    let rect = utils::get_scaled_rect(
+        T::area(canvas),
+        pos.0,
+        pos.1,
+        width,
+        letter_size.1,
+    );
+    let surface_text = match font.render(text).solid(color) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Unable to render the text {:?}: {e}", text);
+            return width;
+        }
+    };
+    let texture = match OwnedTexture::from_surface(
+        T::texture_creator(canvas),
+        &surface_text,
+    ) {
+        Ok(t) => t,
+        Err(e) => {
+            error!("Unable to create the texture for {:?}: {e}", text);
+            return width;
+        }
+    };
+    if let Err(e) = copy_turned(canvas, &texture, rect, turn) {
+        error!("Unable to draw the text {:?}: {e}", text);
+    }
+    width
+}
+
+/// Draw an inline image starting at `pos`, as high as the line, turned by
+/// `turn`, if any, and return the width it took.
+fn draw_inline_image<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    textures: &HashMap<String, OwnedTexture<T::Context>>,
+    path: &str,
+    pos: (f32, f32),
+    line_height: f32,
+    turn: Option<Turn>,
+) -> f32 {
+    let Some(texture) = textures.get(path) else {
+        error!("Texture at {} was not ready", path);
+        return 0.0;
+    };
+    let (win_w, win_h) = T::area(canvas);
+    let query = texture.query();
+    if win_w == 0 || query.height == 0 {
+        return 0.0;
+    }
+    // Keep the image's aspect ratio, converted in window-relative units.
+    #[allow(clippy::cast_precision_loss)]
+    let ratio = (query.width as f32 / query.height as f32)
+        * (win_h as f32 / win_w as f32);
+    let width = line_height * ratio;
+    let rect = utils::get_scaled_rect(
+        (win_w, win_h),
+        pos.0,
+        pos.1,
+        width,
+        line_height,
+    );
+    if let Err(e) = copy_turned(canvas, texture, rect, turn) {
+        error!("Unable to draw the image at {}: {e}", path);
+    }
+    width
+}
+
+/// Draw the sections of `slide`, `elapsed` after it was shown first.
+fn draw_sections<T: DrawTarget>(
+    slide: &Slide,
+    canvas: &mut Canvas<T>,
+    textures: &mut HashMap<String, OwnedTexture<T::Context>>,
+    defaults: &Defaults,
+    fonts: Fonts<'_, '_>,
+    role: Role,
+    elapsed: Duration,
+) {
+    let slide = slide.place_on_grid();
+    let mut base_height = defaults.text_position.1;
+    // The slide's own background wins, be it a color or a gradient.
+    let gradient = match (slide.bg_color, slide.gradient) {
+        (Some(_), _) => None,
+        (None, Some(gradient)) => Some(gradient),
+        (None, None) => defaults.gradient,
+    };
+    let bg_col = slide.bg_color.unwrap_or(defaults.bg_col);
+    // The text is read in the middle of the gradient, more or less.
+    let contrast_col = gradient.map_or(bg_col, |g| g.color_at(0.5));
+    let defaults = &Defaults {
+        font_col: if defaults.auto_contrast {
+            slideshow::Color::readable_on(contrast_col)
+        } else {
+            defaults.font_col
+        },
+        ..*defaults
+    };
+    let col = bg_col.into();
     {
-        utils::canvas_change_color(&mut window.canvas, col);
+        if let Some(gradient) = &gradient {
+            utils::fill_gradient(canvas, gradient);
+        } else if matches!(role, Role::Main { overlay: true, .. }) {
+            utils::overlay_change_color(canvas, col);
+        } else {
+            utils::canvas_change_color(canvas, col);
+        }
 
-        for section in &slides[idx].sections {
-            draw_single_section(
-                window,
+        let visible =
+            slide
+                .sections
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| match role {
+                    Role::Main { .. } => s.visibility.on_main(),
+                    Role::Presenter => s.visibility.on_presenter(),
+                });
+        // Where each section was drawn, and from which height, so that it
+        // can be drawn again in the spotlight.
+        let mut drawn = vec![];
+        for (idx, section) in visible {
+            let from_height = base_height;
+            if let Some(rect) = draw_single_section(
+                canvas,
+                textures,
                 section,
                 &mut base_height,
-                default_font,
-                font_size,
-                font_col,
+                fonts,
+                defaults,
+                elapsed,
+            ) {
+                drawn.push((idx, from_height, rect));
+            }
+        }
+        let Role::Main {
+            spotlight: Some(focus),
+            ..
+        } = role
+        else {
+            return;
+        };
+        // Dim everything, and then draw the section in focus again on top.
+        let viewport = canvas.viewport();
+        let focused = match focus {
+            Focus::Section(idx) => Some(idx),
+            Focus::Pointer((x, y)) => {
+                hit_section(&drawn, (x - viewport.x(), y - viewport.y()))
+            }
+        };
+        let (w, h) = (viewport.width(), viewport.height());
+        utils::fill_box(canvas, SPOTLIGHT_DIM, Rect::new(0, 0, w, h));
+        if let Some(&(idx, mut from_height, _)) =
+            drawn.iter().find(|(idx, ..)| Some(*idx) == focused)
+        {
+            draw_single_section(
+                canvas,
+                textures,
+                &slide.sections[idx],
+                &mut from_height,
+                fonts,
+                defaults,
+                elapsed,
             );
         }
     }
 }
+
+/// The section drawn at `point`, out of the `drawn` ones (with their index,
+/// and where they are). The last one drawn is on top of the others.
+fn hit_section(
+    drawn: &[(usize, f32, Rect)],
+    point: (i32, i32),
+) -> Option<usize> {
+    drawn
+        .iter()
+        .rev()
+        .find(|(_, _, rect)| rect.contains_point(point))
+        .map(|(idx, ..)| *idx)
+}
+
+/// The index of the section the spotlight goes to after the `current` one:
+/// the next one the audience sees, or the first one after the last.
+fn next_focus(slide: &Slide, current: Option<usize>) -> Option<usize> {
+    let mut candidates = slide
+        .sections
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.visibility.on_main() && s.sec_main.is_some())
+        .map(|(idx, _)| idx);
+    let first = candidates.clone().next();
+    current
+        .and_then(|current| candidates.find(|idx| *idx > current))
+        .or(first)
+}
+
+/// Draw the slide at `idx` in an offscreen surface of `width` x `height`
+/// pixels, as the audience would see it, and get its pixels. Each pixel is 4
+/// bytes, in the R, G, B, A order, and the rows go from top to bottom.
+pub(super) fn render_to_rgba(
+    slides: &slideshow::Slideshow,
+    idx: usize,
+    (width, height): (u32, u32),
+    font: &sdl2::ttf::Font<'_, '_>,
+    render: &RenderDefaults,
+) -> Result<Vec<u8>, String> {
+    let Some(slide) = slides.slide_at(idx) else {
+        let len = slides.slides.len();
+        return Err(format!(
+            "There is no slide {idx}, there are {len} slides."
+        ));
+    };
+    let surface = Surface::new(width, height, PixelFormatEnum::RGBA32)?;
+    let mut canvas = surface.into_canvas()?;
+
+    let mut textures = HashMap::new();
+    for path in slide.image_paths() {
+        let file = slides.resolve_path(path);
+        match OwnedTexture::from_figure(canvas.texture_creator(), &file) {
+            Ok(texture) => {
+                textures.insert(path.to_string(), texture);
+            }
+            Err(e) => error!("Error while loading {}: {}", path, e),
+        }
+    }
+    let defaults = Defaults::new(slides, render);
+    let role = Role::Main {
+        overlay: false,
+        spotlight: None,
+    };
+    draw_sections(
+        slide,
+        &mut canvas,
+        &mut textures,
+        &defaults,
+        Fonts {
+            text: font,
+            code: font,
+        },
+        role,
+        Duration::ZERO,
+    );
+    canvas.present();
+    // The textures need the canvas to be around.
+    drop(textures);
+
+    let surface = canvas.into_surface();
+    let row = width as usize * 4;
+    let pitch = surface.pitch() as usize;
+    Ok(surface.with_lock(|pixels| {
+        pixels
+            .chunks(pitch)
+            .flat_map(|line| &line[..row])
+            .copied()
+            .collect()
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_to_rgba() {
+        let ttf_context = sdl2::ttf::init().unwrap();
+        let font = super::super::get_default_font(&ttf_context).unwrap();
+        let slides = crate::parser::parse_text(
+            ":ge :bc 10 20 30 255\n:sl\n:tb :ps 0.5 0.5\nhello",
+            std::path::Path::new(""),
+        )
+        .unwrap();
+        let pixels = render_to_rgba(
+            &slides,
+            0,
+            (40, 30),
+            &font,
+            &RenderDefaults::default(),
+        )
+        .unwrap();
+        assert_eq!(pixels.len(), 40 * 30 * 4);
+        // The top-left corner is empty, so it has the background's color.
+        assert_eq!(pixels[..4], [10, 20, 30, 255]);
+
+        assert!(render_to_rgba(
+            &slides,
+            1,
+            (40, 30),
+            &font,
+            &RenderDefaults::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_render_bad_text() {
+        let ttf_context = sdl2::ttf::init().unwrap();
+        let font = super::super::get_default_font(&ttf_context).unwrap();
+        let render = |text: &str| {
+            let slides =
+                crate::parser::parse_text(text, std::path::Path::new(""));
+            let defaults = RenderDefaults::default();
+            render_to_rgba(&slides.unwrap(), 0, (40, 30), &font, &defaults)
+                .unwrap()
+        };
+        // SDL_ttf can't render a nul character: the run is skipped, and the
+        // rest of the slide is drawn anyway.
+        let bad = render(":sl\n:tb\nbad \u{0} char");
+        let pixels = render(":sl\n:tb\nbad \u{0} char\n:tb :ps 0.5 0.5\nfine");
+        assert_eq!(pixels.len(), 40 * 30 * 4);
+        assert_ne!(pixels, bad);
+    }
+
+    #[test]
+    fn test_render_edge_cases() {
+        let ttf_context = sdl2::ttf::init().unwrap();
+        let font = super::super::get_default_font(&ttf_context).unwrap();
+        let defaults = RenderDefaults::default();
+        let base = std::path::Path::new("./resources");
+        for text in [
+            // Glyphs the built-in font does not have.
+            ":sl\n:tb\n🦀 日本語 e\u{301}",
+            // Only blank lines, that make empty runs.
+            ":sl\n:tb :ps 0.5 0.5\n \n\t",
+            // A figure that is not an image, and an image that can't be
+            // decoded: they are left out.
+            ":sl\n:fg simple_slide.txt\n:tb\nHi",
+            ":sl\n:fg data:image/png;base64,aGVsbG8=\n:tb\nHi",
+        ] {
+            let slides = crate::parser::parse_text(text, base).unwrap();
+            let pixels = render_to_rgba(&slides, 0, (40, 30), &font, &defaults);
+            assert_eq!(pixels.map(|p| p.len()), Ok(40 * 30 * 4), "{text:?}");
+            // Even in a single pixel.
+            let pixels = render_to_rgba(&slides, 0, (1, 1), &font, &defaults);
+            assert_eq!(pixels.map(|p| p.len()), Ok(4), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn test_next_font() {
+        assert_eq!(next_font(0, 0), 0);
+        assert_eq!(next_font(0, 2), 1);
+        assert_eq!(next_font(1, 2), 2);
+        assert_eq!(next_font(2, 2), 0);
+    }
+
+    #[test]
+    fn test_window_title() {
+        assert_eq!(
+            window_title(Some("Intro"), "1/3"),
+            "Slideshow: Intro (1/3)"
+        );
+        assert_eq!(
+            window_title(None, "2.1/3"),
+            "Slideshow: (untitled) (2.1/3)"
+        );
+        let long = "a".repeat(50);
+        let title = window_title(Some(&long), "1/1");
+        assert_eq!(title, format!("Slideshow: {}… (1/1)", "a".repeat(39)));
+    }
+
+    #[test]
+    fn test_text_block() {
+        let lines = ["", "a", "", "b", ""];
+        assert_eq!(text_block(&lines, true), Some((1.0, 3)));
+        assert_eq!(text_block(&lines, false), Some((0.0, 2)));
+        assert_eq!(text_block(&["", ""], true), None);
+    }
+
+    #[test]
+    fn test_centered_top() {
+        assert!((centered_top(1, 0.1, 0.1) - 0.45).abs() < 1e-6);
+        // 3 lines, 0.2 apart: from 0.3 to 0.5 + 0.2.
+        assert!((centered_top(3, 0.2, 0.2) - 0.2).abs() < 1e-6);
+        assert!((centered_top(0, 0.1, 0.1) - 0.45).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fit_scale() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-6;
+        // 10 letters 0.1 wide, in a box 0.5 wide: half the size.
+        let lines = ["0123456789", ""];
+        assert!(close(fit_scale(&lines, (0.1, 0.1), 0.1, (0.5, 1.0)), 0.5));
+        // 3 lines, 0.1 high: 0.3 high in a 0.15 box.
+        let lines = ["a", "b", "c"];
+        assert!(close(fit_scale(&lines, (0.1, 0.1), 0.1, (1.0, 0.15)), 0.5));
+        // The text already fits: it is not made bigger.
+        assert!(close(fit_scale(&lines, (0.1, 0.1), 0.1, (1.0, 1.0)), 1.0));
+        assert!(close(fit_scale(&[], (0.1, 0.1), 0.1, (0.1, 0.1)), 1.0));
+    }
+
+    #[test]
+    fn test_caption_position() {
+        let close = |(a, b): (f32, f32), (c, d): (f32, f32)| {
+            (a - c).abs() < 1e-6 && (b - d).abs() < 1e-6
+        };
+        // 4 letters 0.05 wide, below a figure from 0.1 to 0.5.
+        let pos = caption_position((0.1, 0.2), (0.4, 0.3), "Fig.", (0.05, 0.1));
+        assert!(close(pos, (0.2, 0.5)), "{pos:?}");
+        // A caption wider than the figure goes past both sides.
+        let pos = caption_position((0.1, 0.2), (0.1, 0.1), "Fig.", (0.05, 0.1));
+        assert!(close(pos, (0.05, 0.3)), "{pos:?}");
+    }
+
+    #[test]
+    fn test_rotation_center() {
+        let rect = Rect::new(10, 20, 100, 50);
+        let corner = slideshow::Position { x: 1.0, y: 1.0 };
+        assert_eq!(rotation_center(&corner, rect), Point::new(100, 50));
+        let left = slideshow::Position { x: 0.0, y: 0.5 };
+        assert_eq!(rotation_center(&left, rect), Point::new(0, 25));
+    }
+
+    #[test]
+    fn test_hud_label() {
+        let times = FrameTimes {
+            frame: Duration::from_millis(100),
+            render: Duration::from_micros(2500),
+        };
+        assert_eq!(hud_label(times, 3), "10.0 fps, 2.5 ms, 3 textures");
+        assert_eq!(
+            hud_label(FrameTimes::default(), 0),
+            "0.0 fps, 0.0 ms, 0 textures"
+        );
+    }
+
+    #[test]
+    fn test_hit_section() {
+        let drawn = [
+            (0, 0.0, Rect::new(0, 0, 100, 100)),
+            (2, 0.0, Rect::new(50, 50, 100, 100)),
+        ];
+        assert_eq!(hit_section(&drawn, (10, 10)), Some(0));
+        // The section drawn last is on top.
+        assert_eq!(hit_section(&drawn, (60, 60)), Some(2));
+        assert_eq!(hit_section(&drawn, (200, 200)), None);
+    }
+
+    #[test]
+    fn test_next_focus() {
+        let slides = crate::parser::parse_text(
+            ":sl :tb\nfirst\n:tb :only presenter\nreminder\n:tb\nlast",
+            std::path::Path::new(""),
+        )
+        .unwrap();
+        let slide = &slides.slides[0];
+        assert_eq!(next_focus(slide, None), Some(0));
+        assert_eq!(next_focus(slide, Some(0)), Some(2));
+        assert_eq!(next_focus(slide, Some(2)), Some(0));
+    }
+}
@@ -1,10 +1,21 @@
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
+
+use tracing::error;
 
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::ttf::Font;
+use sdl2::video::Window as SdlWindow;
+
+use super::{
+    utils,
+    utils::{GenericWindow, OwnedTexture},
+};
+use crate::backends::{keymap::Action, TimerPrecision};
 
-use super::{utils, utils::GenericWindow};
+/// When the countdown is this close to the end, the timer turns yellow.
+const COUNTDOWN_WARNING: Duration = Duration::from_mins(2);
 
 /// Define the status of the timer.
 enum Status {
@@ -19,49 +30,56 @@ pub struct Window<'a> {
     /// Contains the generic information for a window
     pub generic_win: GenericWindow,
     timer_status: Status,
-    /// Total amount of elapsed seconds the timer run.
-    total_elapsed: u64,
+    /// Total amount of time the timer run, before it was last started.
+    total_elapsed: Duration,
     /// If the window is visible
     is_visible: bool,
+    /// The length of the talk, if the timer counts down.
+    pub countdown: Option<Duration>,
+    /// How precisely the time is shown.
+    pub precision: TimerPrecision,
+    /// The time and the counter last drawn: they are drawn again only when
+    /// they change.
+    last_drawn: Option<(String, usize, usize)>,
     /// The default font to be used.
     default_font: sdl2::ttf::Font<'a, 'a>,
 }
 
 impl<'a> Window<'a> {
-    #[must_use]
     /// Create a new Timer window.
+    ///
+    /// # Errors
+    ///
+    /// If the window can't be built.
     pub fn new(
         context: &sdl2::Sdl,
         font: sdl2::ttf::Font<'a, 'a>,
         resizable: bool,
         h: u32,
         w: u32,
-    ) -> Self {
+    ) -> Result<Self, String> {
         let timer_status = Status::Stopped;
-        let total_elapsed = 0;
-        Window {
-            generic_win: GenericWindow::new(context, resizable, h, w, "Timer"),
+        let total_elapsed = Duration::ZERO;
+        Ok(Window {
+            generic_win: GenericWindow::new(
+                context, resizable, false, h, w, "Timer",
+            )?,
             timer_status,
             total_elapsed,
             is_visible: true,
+            countdown: None,
+            precision: TimerPrecision::default(),
+            last_drawn: None,
             default_font: font,
-        }
+        })
     }
 
-    /// Manage the keypresses, or any other even related to this very
-    /// window. We don't want other elements to manage our keys!
-    pub fn manage_keypress(&mut self, event: &Event) {
-        match event {
-            // KeyUp: SPACEBAR
-            Event::KeyUp {
-                keycode: Some(Keycode::Space),
-                ..
-            } => self.timer_toggle(),
-            // KeyUp: R
-            Event::KeyUp {
-                keycode: Some(Keycode::R),
-                ..
-            } => self.timer_reset(),
+    /// Perform the actions related to this very window. We don't want other
+    /// elements to manage our keys!
+    pub fn manage_action(&mut self, action: Action) {
+        match action {
+            Action::TimerStart => self.timer_toggle(),
+            Action::TimerReset => self.timer_reset(),
             _ => {}
         }
     }
@@ -89,81 +107,194 @@ impl<'a> Window<'a> {
     /// Reset timer.
     pub fn timer_reset(&mut self) {
         self.timer_stop();
-        self.total_elapsed = 0;
+        self.total_elapsed = Duration::ZERO;
     }
 
-    /// Start the timer.
+    /// Start the timer, if it is stopped.
     pub fn timer_start(&mut self) {
-        self.timer_status = Status::Running(SystemTime::now());
+        if matches!(self.timer_status, Status::Stopped) {
+            self.timer_status = Status::Running(SystemTime::now());
+        }
     }
 
     /// Stop the timer, and update the elapsed time.
     pub fn timer_stop(&mut self) {
-        let elapsed = match self.timer_status {
-            Status::Running(since) => since.elapsed().unwrap().as_secs(),
-            Status::Stopped => 0,
-        };
-        self.total_elapsed += elapsed;
+        self.total_elapsed = self.elapsed();
         self.timer_status = Status::Stopped;
     }
 
-    /// Returns a tuple with hours/minutes/seconds elapsed
-    fn get_time(&self) -> (u8, u8, u8) {
+    /// The time the timer run, in total, fractions of a second included.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
         let elapsed = match self.timer_status {
-            Status::Running(since) => since.elapsed().unwrap().as_secs(),
-            Status::Stopped => 0,
+            // The clock may go backwards: count nothing then.
+            Status::Running(since) => since.elapsed().unwrap_or_default(),
+            Status::Stopped => Duration::ZERO,
         };
+        self.total_elapsed + elapsed
+    }
 
-        let total_secs = self.total_elapsed + elapsed;
-        let seconds = total_secs % 60;
-        let minutes = ((total_secs - seconds) % (60 * 60)) / 60;
-        let hours = (total_secs - (minutes * 60) - seconds) / (60 * 60);
-
-        #[allow(clippy::cast_possible_truncation)]
-        let seconds = seconds as u8; // % 60, so ok
-        #[allow(clippy::cast_possible_truncation)]
-        let minutes = minutes as u8; // % 3600 / 60 = % 60, so ok
-        #[allow(clippy::cast_possible_truncation)]
-        // This does not have to be u8, so we're just capping to 255 hours :)
-        let hours = (hours % 255) as u8;
-
-        (hours, minutes, seconds)
+    /// Draw the window again at the next update, even if nothing changed,
+    /// like when it is shown again.
+    pub fn invalidate(&mut self) {
+        self.last_drawn = None;
     }
 
-    /// Main method to show a slide on the screen.
-    pub fn update(&mut self, slides_tot: usize, slides_idx: usize) {
-        let (h, m, s) = self.get_time();
+    /// Main method to show a slide on the screen. Nothing is drawn if the
+    /// time and the counter are the ones already shown: get if the window
+    /// was drawn.
+    pub fn update(&mut self, slides_tot: usize, slides_idx: usize) -> bool {
+        let (time, bg_color, fg_color) =
+            show_time(self.elapsed(), self.countdown, self.precision);
+        let shown = (time, slides_tot, slides_idx);
+        if self.last_drawn.as_ref() == Some(&shown) {
+            return false;
+        }
+        let (time, ..) = self.last_drawn.insert(shown);
         let c = &mut self.generic_win.canvas;
-        utils::canvas_change_color(c, Color::CYAN);
+        utils::canvas_change_color(c, bg_color);
         // Draw the timer
-        let surface_text = self
-            .default_font
-            .render(format!("{h:02}:{m:02}:{s:02}").as_str())
-            .solid(Color::RED)
-            .unwrap();
-        let texture_creator = c.texture_creator();
-        let texture = surface_text.as_texture(&texture_creator).unwrap();
-        let rect = utils::get_scaled_rect(c.window(), 0.04, 0.04, 0.6, 0.6);
-        c.copy(&texture, None, rect).unwrap();
-        // @safety This is ok, since the texture has been copied and we can
-        // safely remove it.
-        unsafe {
-            texture.destroy();
-        }
+        let rect =
+            utils::get_scaled_rect(c.window().size(), 0.04, 0.04, 0.6, 0.6);
+        draw_label(c, &self.default_font, time, fg_color, rect);
         // Draw the slide counter
-        let surface_text = self
-            .default_font
-            .render(format!("{slides_idx}/{slides_tot}").as_str())
-            .solid(Color::BLACK)
-            .unwrap();
-        let texture_creator = c.texture_creator();
-        let texture = surface_text.as_texture(&texture_creator).unwrap();
-        let rect = utils::get_scaled_rect(c.window(), 0.65, 0.65, 0.33, 0.33);
-        c.copy(&texture, None, rect).unwrap();
-        // @safety This is ok, since the texture has been copied and we can
-        // safely remove it.
-        unsafe {
-            texture.destroy();
+        let rect =
+            utils::get_scaled_rect(c.window().size(), 0.65, 0.65, 0.33, 0.33);
+        let counter = format!("{slides_idx}/{slides_tot}");
+        draw_label(c, &self.default_font, &counter, Color::BLACK, rect);
+        true
+    }
+}
+
+/// Draw `text` in `rect`. Errors are logged, and the text is skipped.
+fn draw_label(
+    canvas: &mut Canvas<SdlWindow>,
+    font: &Font<'_, '_>,
+    text: &str,
+    color: Color,
+    rect: Rect,
+) {
+    let texture = font
+        .render(text)
+        .solid(color)
+        .map_err(|e| e.to_string())
+        .and_then(|surface| {
+            OwnedTexture::from_surface(canvas.texture_creator(), &surface)
+                .map_err(|e| e.to_string())
+        });
+    match texture.and_then(|t| canvas.copy(&t, None, rect)) {
+        Ok(()) => (),
+        Err(e) => error!("Unable to draw {:?} in the timer: {}", text, e),
+    }
+}
+
+/// Returns a tuple with hours/minutes/seconds in `total_secs`.
+const fn split_time(total_secs: u64) -> (u8, u8, u8) {
+    let seconds = total_secs % 60;
+    let minutes = ((total_secs - seconds) % (60 * 60)) / 60;
+    let hours = (total_secs - (minutes * 60) - seconds) / (60 * 60);
+
+    #[allow(clippy::cast_possible_truncation)]
+    let seconds = seconds as u8; // % 60, so ok
+    #[allow(clippy::cast_possible_truncation)]
+    let minutes = minutes as u8; // % 3600 / 60 = % 60, so ok
+    #[allow(clippy::cast_possible_truncation)]
+    // This does not have to be u8, so we're just capping to 255 hours :)
+    let hours = (hours % 255) as u8;
+
+    (hours, minutes, seconds)
+}
+
+/// Write `time` as `HH:MM:SS`, or as `MM:SS.d` with the tenths of a second.
+fn format_time(time: Duration, precision: TimerPrecision) -> String {
+    match precision {
+        TimerPrecision::Seconds => {
+            let (h, m, s) = split_time(time.as_secs());
+            format!("{h:02}:{m:02}:{s:02}")
+        }
+        TimerPrecision::Tenths => {
+            let tenths = time.as_millis() / 100;
+            let (m, s, d) = (tenths / 600, tenths / 10 % 60, tenths % 10);
+            format!("{m:02}:{s:02}.{d}")
         }
     }
 }
+
+/// Get the text of the timer, and its background and foreground colors.
+///
+/// Without a countdown, the elapsed time is shown. With a countdown, the
+/// time left is shown: the timer turns yellow near the end, and red once the
+/// time is over, where the extra time is shown with a `-`.
+fn show_time(
+    elapsed: Duration,
+    countdown: Option<Duration>,
+    precision: TimerPrecision,
+) -> (String, Color, Color) {
+    let Some(countdown) = countdown else {
+        let time = format_time(elapsed, precision);
+        return (time, Color::CYAN, Color::RED);
+    };
+    if elapsed >= countdown {
+        let time = format_time(elapsed.saturating_sub(countdown), precision);
+        (format!("-{time}"), Color::RED, Color::WHITE)
+    } else {
+        let left = countdown.saturating_sub(elapsed);
+        let bg_color = if left <= COUNTDOWN_WARNING {
+            Color::YELLOW
+        } else {
+            Color::CYAN
+        };
+        (format_time(left, precision), bg_color, Color::RED)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_countdown() {
+        let secs = Duration::from_secs;
+        let show = |elapsed, countdown| {
+            show_time(elapsed, countdown, TimerPrecision::Seconds)
+        };
+        let (time, bg, _) = show(secs(3723), None);
+        assert_eq!(time, "01:02:03");
+        assert_eq!(bg, Color::CYAN);
+
+        let twenty_minutes = Some(secs(20 * 60));
+        let (time, bg, _) = show(secs(60), twenty_minutes);
+        assert_eq!(time, "00:19:00");
+        assert_eq!(bg, Color::CYAN);
+        let (time, bg, _) = show(secs(18 * 60), twenty_minutes);
+        assert_eq!(time, "00:02:00");
+        assert_eq!(bg, Color::YELLOW);
+        let (time, bg, _) = show(secs(20 * 60), twenty_minutes);
+        assert_eq!(time, "-00:00:00");
+        assert_eq!(bg, Color::RED);
+        let (time, _, _) = show(secs(21 * 60 + 5), twenty_minutes);
+        assert_eq!(time, "-00:01:05");
+    }
+
+    #[test]
+    fn test_tenths() {
+        let millis = Duration::from_millis;
+        let tenths = |elapsed| format_time(elapsed, TimerPrecision::Tenths);
+        assert_eq!(tenths(Duration::ZERO), "00:00.0");
+        assert_eq!(tenths(millis(1_299)), "00:01.2");
+        assert_eq!(tenths(millis(754_950)), "12:34.9");
+        // The hours are counted in the minutes.
+        assert_eq!(tenths(millis(3_723_400)), "62:03.4");
+        // The whole seconds are truncated as before.
+        let seconds = format_time(millis(1_999), TimerPrecision::Seconds);
+        assert_eq!(seconds, "00:00:01");
+
+        let countdown = Some(Duration::from_secs(60));
+        let (time, _, _) =
+            show_time(millis(500), countdown, TimerPrecision::Tenths);
+        assert_eq!(time, "00:59.5");
+        let (time, _, _) =
+            show_time(millis(61_250), countdown, TimerPrecision::Tenths);
+        assert_eq!(time, "-00:01.2");
+    }
+}
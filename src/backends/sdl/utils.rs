@@ -1,115 +1,356 @@
 use std::collections::HashMap;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::time::SystemTime;
 
 use sdl2::pixels::Color;
-use sdl2::rect::Rect;
-use sdl2::render::{Canvas, Texture};
-use sdl2::video::Window;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{
+    Canvas, RenderTarget, Texture, TextureCreator, TextureValueError,
+};
+use sdl2::surface::Surface;
+use sdl2::video::{Window, WindowContext, WindowPos};
 use tracing::{debug, error, warn};
 
+/// A texture that is destroyed when dropped.
+///
+/// Since we use the `unsafe_textures` feature, SDL textures are not bound to
+/// their creator's lifetime and have to be destroyed by hand. This wrapper
+/// keeps the creator alive next to the texture, and destroys the texture
+/// (before the creator goes away) on drop: this is the only place where we
+/// need some `unsafe`.
+pub struct OwnedTexture<T = WindowContext> {
+    texture: ManuallyDrop<Texture>,
+    _creator: TextureCreator<T>,
+}
+
+impl<T> OwnedTexture<T> {
+    /// Build the texture out of a surface, like a rendered text.
+    pub fn from_surface(
+        creator: TextureCreator<T>,
+        surface: &Surface,
+    ) -> Result<Self, TextureValueError> {
+        let texture = creator.create_texture_from_surface(surface)?;
+        Ok(Self {
+            texture: ManuallyDrop::new(texture),
+            _creator: creator,
+        })
+    }
+
+    /// Build an empty texture that can be used as a rendering target.
+    pub fn new_target(
+        creator: TextureCreator<T>,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, TextureValueError> {
+        let texture = creator.create_texture_target(None, width, height)?;
+        Ok(Self {
+            texture: ManuallyDrop::new(texture),
+            _creator: creator,
+        })
+    }
+
+    /// Load the texture from the bytes of an image, like the content of a
+    /// PNG file.
+    pub fn from_bytes(
+        creator: TextureCreator<T>,
+        bytes: &[u8],
+    ) -> Result<Self, String> {
+        use sdl2::image::ImageRWops;
+
+        let surface = sdl2::rwops::RWops::from_bytes(bytes)?.load()?;
+        Self::from_surface(creator, &surface).map_err(|e| e.to_string())
+    }
+
+    /// Load the texture of a figure: `path` is either the path of the image,
+    /// or a `data:` URI with the image itself.
+    pub fn from_figure(
+        creator: TextureCreator<T>,
+        path: &str,
+    ) -> Result<Self, String> {
+        match crate::slideshow::decode_data_uri(path) {
+            Some(bytes) => Self::from_bytes(creator, &bytes?),
+            None => Self::from_file(creator, Path::new(path)),
+        }
+    }
+
+    /// Load the texture from the image at `path`.
+    pub fn from_file(
+        creator: TextureCreator<T>,
+        path: &Path,
+    ) -> Result<Self, String> {
+        use sdl2::image::LoadTexture;
+
+        let texture = creator.load_texture(path)?;
+        Ok(Self {
+            texture: ManuallyDrop::new(texture),
+            _creator: creator,
+        })
+    }
+}
+
+impl<T> Deref for OwnedTexture<T> {
+    type Target = Texture;
+
+    fn deref(&self) -> &Texture {
+        &self.texture
+    }
+}
+
+impl<T> DerefMut for OwnedTexture<T> {
+    fn deref_mut(&mut self) -> &mut Texture {
+        &mut self.texture
+    }
+}
+
+impl<T> Drop for OwnedTexture<T> {
+    fn drop(&mut self) {
+        // Safety: the texture is never given away, and it is taken out only
+        // here, once. The creator (and so the renderer) is still alive, since
+        // the fields are dropped after this function returns.
+        unsafe { ManuallyDrop::take(&mut self.texture).destroy() };
+    }
+}
+
+/// Something the slides can be drawn on, like a window or an offscreen
+/// surface.
+pub trait DrawTarget: RenderTarget + Sized {
+    /// The size of the drawing area, in pixels: the viewport, if one is
+    /// set.
+    fn area(canvas: &Canvas<Self>) -> (u32, u32);
+
+    /// The creator of the textures that can be drawn on the canvas.
+    fn texture_creator(canvas: &Canvas<Self>) -> TextureCreator<Self::Context>;
+}
+
+impl DrawTarget for Window {
+    fn area(canvas: &Canvas<Self>) -> (u32, u32) {
+        let viewport = canvas.viewport();
+        (viewport.width(), viewport.height())
+    }
+
+    fn texture_creator(canvas: &Canvas<Self>) -> TextureCreator<Self::Context> {
+        canvas.texture_creator()
+    }
+}
+
+impl DrawTarget for Surface<'_> {
+    fn area(canvas: &Canvas<Self>) -> (u32, u32) {
+        let viewport = canvas.viewport();
+        (viewport.width(), viewport.height())
+    }
+
+    fn texture_creator(canvas: &Canvas<Self>) -> TextureCreator<Self::Context> {
+        canvas.texture_creator()
+    }
+}
+
 /// A Generic SDL window.
 pub struct GenericWindow {
     /// All the canvases where we need to draw.
     pub canvas: Canvas<Window>,
     /// The textures related to the canvas.
-    pub textures: HashMap<String, Texture>,
+    pub textures: HashMap<String, OwnedTexture>,
+    /// When the files of the textures were last changed, as they were
+    /// loaded.
+    modified: HashMap<String, SystemTime>,
     /// The window id.
     pub id: u32,
 }
 
 impl GenericWindow {
-    #[must_use]
+    /// Build the window. An `overlay` window has no borders and stays on
+    /// top of the other windows, where the platform allows it.
+    ///
+    /// # Errors
+    ///
+    /// If the video subsystem is not available, or if the window can't be
+    /// built.
     pub fn new(
         context: &sdl2::Sdl,
         resizable: bool,
+        overlay: bool,
         height: u32,
         width: u32,
         name: &str,
-    ) -> Self {
+    ) -> Result<Self, String> {
         let video_subsystem = context
             .video()
-            .expect("Unable to build the video subsystem?");
+            .map_err(|e| format!("Unable to build the video subsystem: {e}"))?;
         video_subsystem
             .gl_load_library_default()
-            .expect("unable to initialize opengl");
+            .map_err(|e| format!("Unable to initialize opengl: {e}"))?;
 
         // Create window, canvas
-        let mut windowbuilder = video_subsystem.window(name, height, width);
-        if resizable {
-            windowbuilder.resizable();
-        }
-        let window = windowbuilder.build().expect("Unable to build the window");
+        let build_window = || {
+            let mut windowbuilder = video_subsystem.window(name, height, width);
+            if resizable {
+                windowbuilder.resizable();
+            }
+            if overlay {
+                windowbuilder.borderless().always_on_top();
+            }
+            windowbuilder
+                .build()
+                .map_err(|e| format!("Unable to build the window: {e}"))
+        };
 
-        let canvas = window
+        let accelerated = build_window()?
             .into_canvas()
             .target_texture()
             .accelerated()
-            .build().map_or({
+            .build();
+        let canvas = match accelerated {
+            Ok(canvas) => canvas,
+            Err(e) => {
                 warn!(
-                    "Unable to build an accelerated context, trying the plain one."
+                    "Unable to build an accelerated context ({}), trying the plain one.",
+                    e
                 );
                 // If accelerated is does not work, try not accelerated one.
-                windowbuilder = video_subsystem.window(name, height, width);
-                if resizable {
-                    windowbuilder.resizable();
-                }
-                let window =
-                    windowbuilder.build().expect("Unable to build the window");
-
-                window.into_canvas().target_texture().build().expect(
-                    "Unable to build even the non-accelerated window...",
-                )
-            }, |c| c)
-        ;
+                build_window()?.into_canvas().target_texture().build().map_err(
+                    |e| {
+                        format!(
+                            "Unable to build even the non-accelerated window: {e}"
+                        )
+                    },
+                )?
+            }
+        };
 
         let id = &canvas.window().id();
-        Self {
+        Ok(Self {
             canvas,
             textures: HashMap::new(),
+            modified: HashMap::new(),
             id: *id,
-        }
+        })
     }
 
     /// Clean the textures hashmap, by destroying them.
     pub fn remove_textures(&mut self) {
-        // Remove the old textures
-        for (_name, texture) in self.textures.drain() {
-            // Safety: we are cleaning the map at the same time, so we won't be
-            // able to find the textures anyway after this.
-            unsafe { texture.destroy() };
-        }
         self.textures.clear();
+        self.modified.clear();
     }
 
-    /// Add the texture that can be found at `texture_path`, and use that path
-    /// as a key to retrieve it.
-    pub fn add_texture<T>(&mut self, texture_path: &T)
-    where
-        T: AsRef<str>,
-    {
-        use sdl2::image::LoadTexture;
+    /// Keep only the textures of `paths` whose files did not change since
+    /// they were loaded: the other ones are destroyed. The `paths` map the
+    /// keys of the textures to their files.
+    pub fn retain_textures(&mut self, paths: &HashMap<&str, String>) {
+        let modified = &mut self.modified;
+        self.textures.retain(|key, _| {
+            let keep = paths.get(key.as_str()).is_some_and(|file| {
+                modified.get(key) == file_modified(file).as_ref()
+            });
+            if !keep {
+                debug!("Dropping the texture of {}.", key);
+                modified.remove(key);
+            }
+            keep
+        });
+    }
 
-        // Put the textures in the map.
-        let texture_creator = self.canvas.texture_creator();
+    /// Add the texture built out of the bytes of an image, and use `key` to
+    /// retrieve it.
+    pub fn add_texture_bytes(&mut self, key: &str, bytes: &[u8]) {
+        match OwnedTexture::from_bytes(self.canvas.texture_creator(), bytes) {
+            Ok(texture) => {
+                self.textures.insert(String::from(key), texture);
+            }
+            Err(e) => error!("Error while loading an image in memory: {}", e),
+        }
+    }
 
-        if !self.textures.contains_key(texture_path.as_ref()) {
-            let res = texture_creator.load_texture(texture_path.as_ref());
+    /// Add the texture of the image in `file`, and use `key`, the path as
+    /// written in the slides, to retrieve it. The file can also be a `data:`
+    /// URI, with the image itself.
+    pub fn add_texture(&mut self, key: &str, file: &str) {
+        if let Some(bytes) = crate::slideshow::decode_data_uri(file) {
+            if !self.textures.contains_key(key) {
+                match bytes {
+                    Ok(bytes) => self.add_texture_bytes(key, &bytes),
+                    Err(e) => error!("Error while decoding an image: {}", e),
+                }
+            }
+            return;
+        }
+        // Put the textures in the map.
+        if !self.textures.contains_key(key) {
+            let res = OwnedTexture::from_file(
+                self.canvas.texture_creator(),
+                Path::new(file),
+            );
             if let Ok(texture) = res {
-                debug!("Loading {} into the hashmap.", texture_path.as_ref());
-                self.textures
-                    .insert(String::from(texture_path.as_ref()), texture);
+                debug!("Loading {} into the hashmap.", file);
+                if let Some(modified) = file_modified(file) {
+                    self.modified.insert(String::from(key), modified);
+                }
+                self.textures.insert(String::from(key), texture);
             } else {
-                error!(
-                    "Error while loading to show: {}",
-                    texture_path.as_ref()
-                );
+                error!("Error while loading to show: {}", file);
             }
         }
     }
 }
 
+/// When the file at `path` was last changed, if it is a file.
+fn file_modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Move the window to the center of the display at `index`. If there is no such
+/// display, the window is left where it is.
+pub fn move_to_display(window: &mut Window, index: u32) {
+    let displays = window.subsystem().num_video_displays().unwrap_or(1);
+    let bounds = i32::try_from(index)
+        .ok()
+        .filter(|d| *d < displays)
+        .map(|d| window.subsystem().display_bounds(d));
+    match bounds {
+        Some(Ok(bounds)) => {
+            let (w, h) = window.size();
+            let center = |start: i32, full: u32, size: u32| {
+                let offset = full.saturating_sub(size) / 2;
+                start.saturating_add(i32::try_from(offset).unwrap_or(0))
+            };
+            window.set_position(
+                WindowPos::Positioned(center(bounds.x(), bounds.width(), w)),
+                WindowPos::Positioned(center(bounds.y(), bounds.height(), h)),
+            );
+        }
+        Some(Err(e)) => {
+            warn!("Unable to get the bounds of display {}: {}", index, e);
+        }
+        None => warn!(
+            "There is no display {} ({} available), using the primary one.",
+            index, displays
+        ),
+    }
+}
+
+/// Bring `v` in the `[0, 1]` range, where `NaN` is 0, so that it can be
+/// converted to pixels safely. The parser already warned about it.
+const fn clamp_unit(v: f32) -> f32 {
+    if v.is_nan() {
+        0.0
+    } else {
+        v.clamp(0.0, 1.0)
+    }
+}
+
+/// If something can be drawn in `area`, that is if it is not empty.
 #[must_use]
-pub fn convert_point(win: &Window, x: f32, y: f32) -> (u32, u32) {
-    let (sx, sy) = win.size();
+pub const fn has_area((w, h): (u32, u32)) -> bool {
+    w > 0 && h > 0
+}
+
+/// Convert the window-relative (`x`, `y`) to pixels in `area`. Values out of
+/// the `[0, 1]` range are clamped.
+#[must_use]
+pub fn convert_point(area: (u32, u32), x: f32, y: f32) -> (u32, u32) {
+    let (sx, sy) = area;
+    let (x, y) = (clamp_unit(x), clamp_unit(y));
 
     #[allow(clippy::cast_possible_truncation)]
     #[allow(clippy::cast_sign_loss)]
@@ -129,11 +370,56 @@ pub fn convert_point(win: &Window, x: f32, y: f32) -> (u32, u32) {
     (px, py)
 }
 
+/// Convert the size (`w`, `h`) of a section to window-relative units. With
+/// `uniform`, both are relative to the shorter side of `area`, so a square
+/// stays a square whatever the shape of the window; otherwise they are
+/// relative to the width and the height of `area`, and they are unchanged.
 #[must_use]
-pub fn get_scaled_rect(win: &Window, x: f32, y: f32, w: f32, h: f32) -> Rect {
-    let (nx, ny) = convert_point(win, x, y);
-    let (nw, nh) = convert_point(win, w, h);
-    let (sx, sy) = win.size();
+pub fn window_size(
+    area: (u32, u32),
+    (w, h): (f32, f32),
+    uniform: bool,
+) -> (f32, f32) {
+    let (sx, sy) = area;
+    if !uniform || sx == 0 || sy == 0 {
+        return (w, h);
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let (sx, sy) = (sx as f32, sy as f32);
+    let side = sx.min(sy);
+    (w * side / sx, h * side / sy)
+}
+
+/// Convert the window-relative size (`w`, `h`) to pixels in `area`. Unlike a
+/// point, a size can be larger than the window, like a long line or a large
+/// figure: only the negative sizes (and `NaN`) become 0.
+#[must_use]
+pub fn convert_size(area: (u32, u32), w: f32, h: f32) -> (u32, u32) {
+    let length = |side: u32, v: f32| {
+        let v = if v.is_nan() { 0.0 } else { v.max(0.0) };
+        // The conversion saturates, for the sizes that are too large.
+        #[allow(clippy::cast_possible_truncation)]
+        #[allow(clippy::cast_sign_loss)]
+        #[allow(clippy::cast_precision_loss)]
+        let pixels = (side as f32 * v).floor() as u32;
+        pixels
+    };
+    (length(area.0, w), length(area.1, h))
+}
+
+/// The rect at the window-relative (`x`, `y`), with the window-relative
+/// size (`w`, `h`), in pixels of `area`. It starts in the window, but it can
+/// go past its edges.
+#[must_use]
+pub fn get_scaled_rect(
+    area: (u32, u32),
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+) -> Rect {
+    let (nx, ny) = convert_point(area, x, y);
+    let (nw, nh) = convert_size(area, w, h);
 
     assert!(nx < i32::MAX as u32);
     assert!(ny < i32::MAX as u32);
@@ -143,25 +429,201 @@ pub fn get_scaled_rect(win: &Window, x: f32, y: f32, w: f32, h: f32) -> Rect {
     #[allow(clippy::cast_possible_wrap)]
     let nyy = ny as i32;
 
-    let rect = Rect::new(nxx, nyy, nw, nh);
-
-    if (nx + nw) > sx || (ny + nh) > sy {
-        // Something will not fit in the image, show a log,
-        // but still display the thing that lives on the screen area.
-        warn!("Building rect outside of the visible area: {:?}", rect);
-    }
-    rect
+    Rect::new(nxx, nyy, nw, nh)
 }
 
 /// Change the color of a canvas.
-pub fn canvas_change_color(
-    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+pub fn canvas_change_color<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
     color: Color,
 ) {
     canvas.set_draw_color(color);
     canvas.clear();
 }
 
+/// Fill the canvas with `gradient`: it is cleared with the first color, and
+/// then the viewport is drawn line by line.
+pub fn fill_gradient<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    gradient: &crate::slideshow::Gradient,
+) {
+    use crate::slideshow::GradientDirection::{Horizontal, Vertical};
+
+    canvas_change_color(canvas, gradient.from.into());
+    let viewport = canvas.viewport();
+    let (w, h) = (viewport.width(), viewport.height());
+    let lines = match gradient.direction {
+        Vertical => h,
+        Horizontal => w,
+    };
+    let last = i32::try_from(w.max(h)).unwrap_or(i32::MAX) - 1;
+    for line in 0..lines {
+        #[allow(clippy::cast_precision_loss)]
+        let t = line as f32 / lines.saturating_sub(1).max(1) as f32;
+        canvas.set_draw_color(gradient.color_at(t));
+        let line = i32::try_from(line).unwrap_or(i32::MAX);
+        let (start, end) = match gradient.direction {
+            Vertical => (Point::new(0, line), Point::new(last, line)),
+            Horizontal => (Point::new(line, 0), Point::new(line, last)),
+        };
+        if let Err(e) = canvas.draw_line(start, end) {
+            error!("Unable to draw the gradient: {}", e);
+            return;
+        }
+    }
+}
+
+/// Change the color of an overlay's canvas: a fully transparent color clears
+/// the canvas to transparent black, so that what is behind the window shows
+/// up where the platform supports it, and black can be used as a chroma key
+/// elsewhere.
+pub fn overlay_change_color<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    color: Color,
+) {
+    if color.a == 0 {
+        canvas_change_color(canvas, Color::RGBA(0, 0, 0, 0));
+    } else {
+        canvas_change_color(canvas, color);
+    }
+}
+
+/// The largest rect with the given `aspect` (width / height) centered in
+/// `area`. Without an aspect, it is the whole area.
+#[must_use]
+pub fn content_rect(area: (u32, u32), aspect: Option<f32>) -> Rect {
+    let (w, h) = area;
+    let Some(aspect) = aspect else {
+        return Rect::new(0, 0, w, h);
+    };
+    #[allow(clippy::cast_precision_loss)]
+    let (wf, hf) = (w as f32, h as f32);
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    let (cw, ch) = if wf > hf * aspect {
+        // Bars on the left and on the right.
+        ((hf * aspect).round() as u32, h)
+    } else {
+        // Bars on the top and on the bottom.
+        (w, (wf / aspect).round() as u32)
+    };
+    let x = i32::try_from((w - cw.min(w)) / 2).unwrap_or(0);
+    let y = i32::try_from((h - ch.min(h)) / 2).unwrap_or(0);
+    Rect::new(x, y, cw, ch)
+}
+
+/// Where the picture in picture goes: a copy of the content of `area` (see
+/// [`content_rect`]), `scale` times as large, in its top right corner.
+#[must_use]
+pub fn pip_rect(area: (u32, u32), aspect: Option<f32>, scale: f32) -> Rect {
+    let content = content_rect(area, aspect);
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    let scaled = |v: u32| (v as f32 * scale).round().max(1.0) as u32;
+    let (w, h) = (scaled(content.width()), scaled(content.height()));
+    // The margin grows with the window, like the pointer.
+    let margin = content.width().min(content.height()) / 50;
+    let x = content.right() - i32::try_from(w + margin).unwrap_or(0);
+    let y = content.top() + i32::try_from(margin).unwrap_or(0);
+    Rect::new(x, y, w, h)
+}
+
+/// Move `point`, over the slide drawn in `from`, to the same place of the
+/// slide drawn in `to`, like the pointer from a window to another.
+pub fn map_point(point: (i32, i32), from: Rect, to: Rect) -> (i32, i32) {
+    let along = |v: i32,
+                 (from_start, from_len): (i32, u32),
+                 (to_start, to_len): (i32, u32)| {
+        if from_len == 0 {
+            return to_start;
+        }
+        let moved =
+            i64::from(v - from_start) * i64::from(to_len) / i64::from(from_len);
+        to_start + i32::try_from(moved).unwrap_or(0)
+    };
+    (
+        along(point.0, (from.x(), from.width()), (to.x(), to.width())),
+        along(point.1, (from.y(), from.height()), (to.y(), to.height())),
+    )
+}
+
+/// Draw only in the part of the canvas with the given `aspect`, see
+/// [`content_rect`]. Mind that clearing the canvas still clears it all, so
+/// the bars get the background color.
+pub fn set_content_viewport<T: DrawTarget>(
+    canvas: &mut Canvas<T>,
+    aspect: Option<f32>,
+) {
+    canvas.set_viewport(None);
+    if aspect.is_some() {
+        let rect = content_rect(T::area(canvas), aspect);
+        canvas.set_viewport(rect);
+    }
+}
+
+/// Fill `rect` with `color`, like the box behind a section. A translucent
+/// color lets what is behind the box show up.
+pub fn fill_box<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    color: Color,
+    rect: Rect,
+) {
+    let blend = canvas.blend_mode();
+    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+    canvas.set_draw_color(color);
+    if let Err(e) = canvas.fill_rect(rect) {
+        error!("Unable to draw the box {:?}: {}", rect, e);
+    }
+    canvas.set_blend_mode(blend);
+}
+
+/// Draw the laser pointer, a translucent red dot centered in `center`.
+pub fn draw_pointer<T: DrawTarget>(canvas: &mut Canvas<T>, center: (i32, i32)) {
+    let (w, h) = T::area(canvas);
+    // The dot grows with the window, but it is always visible.
+    let radius = i32::try_from(w.min(h) / 80).unwrap_or(0).max(4);
+    let blend = canvas.blend_mode();
+    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(255, 0, 0, 160));
+    // A filled circle, drawn as a stack of horizontal lines.
+    for dy in -radius..=radius {
+        #[allow(clippy::cast_possible_truncation)]
+        let dx = f64::from(radius * radius - dy * dy).sqrt() as i32;
+        let (x, y) = (center.0, center.1 + dy);
+        if let Err(e) = canvas.draw_line((x - dx, y), (x + dx, y)) {
+            error!("Unable to draw the pointer: {}", e);
+            break;
+        }
+    }
+    canvas.set_blend_mode(blend);
+}
+
+/// Split `line` in chunks of at most `max_chars` characters, breaking it at
+/// the spaces when possible. Words longer than `max_chars` are broken as well.
+#[must_use]
+pub fn wrap_line(line: &str, max_chars: usize) -> Vec<&str> {
+    let max_chars = max_chars.max(1);
+    let mut chunks = vec![];
+    let mut rem = line;
+    while let Some((limit, _)) = rem.char_indices().nth(max_chars) {
+        let (chunk, next) = if rem[limit..].starts_with(' ') {
+            (&rem[..limit], &rem[limit..])
+        } else {
+            match rem[..limit].rfind(' ') {
+                Some(space) if space > 0 => (&rem[..space], &rem[space..]),
+                _ => (&rem[..limit], &rem[limit..]),
+            }
+        };
+        chunks.push(chunk);
+        rem = next.trim_start_matches(' ');
+    }
+    if !rem.is_empty() || chunks.is_empty() {
+        chunks.push(rem);
+    }
+    chunks
+}
+
 impl From<crate::slideshow::Color> for Color {
     fn from(c: crate::slideshow::Color) -> Self {
         Self::from((c.r, c.g, c.b, c.a))
@@ -175,3 +637,155 @@ impl From<Color> for crate::slideshow::Color {
         Self { r, g, b, a }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use sdl2::pixels::PixelFormatEnum;
+
+    use super::*;
+
+    #[test]
+    fn test_owned_textures_are_destroyed() {
+        // A software canvas is enough, no need for a window here.
+        let surface = Surface::new(64, 64, PixelFormatEnum::RGBA8888).unwrap();
+        let canvas = surface.into_canvas().unwrap();
+        let mut textures = HashMap::new();
+        for idx in 0..1000 {
+            let sprite = Surface::new(8, 8, PixelFormatEnum::RGBA8888).unwrap();
+            let texture =
+                OwnedTexture::from_surface(canvas.texture_creator(), &sprite)
+                    .unwrap();
+            // Replacing a value drops, and so destroys, the old texture.
+            textures.insert(idx % 10, texture);
+        }
+        assert_eq!(textures.len(), 10);
+        // The textures keep their creator alive, so the canvas can go first.
+        drop(canvas);
+        textures.clear();
+    }
+
+    #[test]
+    fn test_load_png() {
+        let _image_context =
+            sdl2::image::init(super::super::image_formats()).unwrap();
+        let surface = Surface::new(64, 64, PixelFormatEnum::RGBA8888).unwrap();
+        let canvas = surface.into_canvas().unwrap();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("resources")
+            .join("square.png");
+        let texture = OwnedTexture::from_file(canvas.texture_creator(), &path)
+            .expect("PNG figures must be supported.");
+        assert_eq!(texture.query().width, 16);
+    }
+
+    #[test]
+    fn test_load_data_uri() {
+        let _image_context =
+            sdl2::image::init(super::super::image_formats()).unwrap();
+        let surface = Surface::new(64, 64, PixelFormatEnum::RGBA8888).unwrap();
+        let canvas = surface.into_canvas().unwrap();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("resources")
+            .join("square.png");
+        let bytes = std::fs::read(path).unwrap();
+        let texture =
+            OwnedTexture::from_bytes(canvas.texture_creator(), &bytes)
+                .expect("Images in memory must be supported.");
+        assert_eq!(texture.query().width, 16);
+    }
+
+    #[test]
+    fn test_convert_point_clamps() {
+        let area = (200, 100);
+        assert_eq!(convert_point(area, 0.5, 0.5), (100, 50));
+        assert_eq!(convert_point(area, -0.5, 1.5), (0, 100));
+        assert_eq!(convert_point(area, f32::NAN, f32::INFINITY), (0, 100));
+
+        // The sizes are not clamped: what is large goes past the window.
+        assert_eq!(convert_size(area, 1.5, -0.5), (300, 0));
+        assert_eq!(convert_size(area, f32::NAN, 0.5), (0, 50));
+        let rect = get_scaled_rect(area, -0.5, f32::NAN, 1.5, 0.5);
+        assert_eq!(rect, Rect::new(0, 0, 300, 50));
+    }
+
+    #[test]
+    fn test_has_area() {
+        assert!(has_area((1, 1)));
+        assert!(!has_area((0, 600)));
+        assert!(!has_area((800, 0)));
+    }
+
+    #[test]
+    fn test_window_size() {
+        assert_eq!(window_size((1600, 800), (0.2, 0.4), false), (0.2, 0.4));
+        // The width follows the height, the shorter side.
+        assert_eq!(window_size((1600, 800), (0.2, 0.4), true), (0.1, 0.4));
+        assert_eq!(window_size((400, 800), (0.2, 0.4), true), (0.2, 0.2));
+        let (w, h) = window_size((1600, 800), (0.5, 0.5), true);
+        let rect = get_scaled_rect((1600, 800), 0.0, 0.0, w, h);
+        assert_eq!((rect.width(), rect.height()), (400, 400));
+        assert_eq!(window_size((0, 800), (0.2, 0.4), true), (0.2, 0.4));
+    }
+
+    #[test]
+    fn test_content_rect() {
+        let area = (1600, 900);
+        assert_eq!(content_rect(area, None), Rect::new(0, 0, 1600, 900));
+        // A 4:3 slide on a 16:9 projector has bars on the sides.
+        assert_eq!(
+            content_rect(area, Some(4.0 / 3.0)),
+            Rect::new(200, 0, 1200, 900)
+        );
+        // A 16:9 slide on a 4:3 screen has bars on the top and the bottom.
+        assert_eq!(
+            content_rect((1200, 900), Some(16.0 / 9.0)),
+            Rect::new(0, 112, 1200, 675)
+        );
+    }
+
+    #[test]
+    fn test_pip_rect() {
+        let area = (1600, 900);
+        assert_eq!(pip_rect(area, None, 0.25), Rect::new(1182, 18, 400, 225));
+        // The picture stays in the slide, not in the bars.
+        assert_eq!(
+            pip_rect(area, Some(4.0 / 3.0), 0.25),
+            Rect::new(1082, 18, 300, 225)
+        );
+        // It is never empty.
+        assert_eq!(pip_rect((10, 10), None, 0.0), Rect::new(9, 0, 1, 1));
+    }
+
+    #[test]
+    fn test_map_point() {
+        let from = Rect::new(0, 0, 1600, 900);
+        let to = Rect::new(0, 0, 800, 450);
+        assert_eq!(map_point((800, 300), from, to), (400, 150));
+        // The bars around the slides are left out.
+        let to = Rect::new(200, 0, 1200, 900);
+        assert_eq!(map_point((0, 0), from, to), (200, 0));
+        assert_eq!(map_point((1600, 450), from, to), (1400, 450));
+        // An empty window has no slide to point at.
+        assert_eq!(map_point((5, 5), Rect::new(0, 0, 0, 0), to), (200, 0));
+    }
+
+    #[test]
+    fn test_wrap_line() {
+        assert_eq!(wrap_line("short", 10), vec!["short"]);
+        assert_eq!(wrap_line("", 10), vec![""]);
+        assert_eq!(
+            wrap_line("a line that is too long", 10),
+            vec!["a line", "that is", "too long"]
+        );
+        assert_eq!(
+            wrap_line("exactly 10 chars", 10),
+            vec!["exactly 10", "chars"]
+        );
+        assert_eq!(
+            wrap_line("averyveryverylongword", 8),
+            vec!["averyver", "yverylon", "gword"]
+        );
+        assert_eq!(wrap_line("àèìòù àèìòù", 5), vec!["àèìòù", "àèìòù"]);
+        assert_eq!(wrap_line("abc", 0), vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,486 @@
+/*!
+Draw the slides as SVG images, for crisp assets on the web or in print.
+
+Nothing is shown and no input is read: each slide becomes a standalone
+document, where the background is a `<rect>`, the texts are `<text>`
+elements, and the figures are `<image>` elements linking to their files.
+
+The positions and the sizes of the slides, from 0 to 1, are mapped to a
+view box like 1920x1080. As in SDL, each letter of a text takes the size of
+the section, whatever the font is; the lines are not wrapped nor fitted in
+their box, though.
+*/
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use super::RenderDefaults;
+use crate::slideshow::{
+    Color, Gradient, GradientDirection, Position, Section, SectionFigure,
+    SectionMain, SectionText, Slide, Slideshow, TextRun, DEFAULT_BG_COLOR,
+    DEFAULT_FONT_COLOR,
+};
+
+/// The size of the images, when nothing else is asked: a full HD screen.
+pub const DEFAULT_VIEW_BOX: (u32, u32) = (1920, 1080);
+
+/// How much the box behind the code covers the background, as in SDL.
+const CODE_BG_ALPHA: u8 = 0x20;
+
+/// How big the letters of a caption are, compared to the text ones.
+const CAPTION_SCALE: f32 = 0.75;
+
+/// Read the size of the images, like `1920x1080`.
+///
+/// # Errors
+///
+/// If the size is not in the `widthxheight` form, with positive integers.
+pub fn parse_view_box(s: &str) -> Result<(u32, u32), String> {
+    s.split_once(['x', 'X'])
+        .and_then(|(w, h)| {
+            let (w, h) = (w.trim().parse().ok()?, h.trim().parse().ok()?);
+            (w > 0 && h > 0).then_some((w, h))
+        })
+        .ok_or_else(|| format!("The size must be like 1920x1080, found {s}"))
+}
+
+/// What the sections of a slide fall back to, and the size of the image.
+struct Style<'a> {
+    /// The width and the height of the view box.
+    area: (f32, f32),
+    /// Where the sections go, and how big they are, when nothing says it.
+    defaults: &'a RenderDefaults,
+    /// The size of the letters, when the section does not set it.
+    font_size: (f32, f32),
+    /// The color of the text, when the section does not set it.
+    font_col: Color,
+    /// The slides, to find the images of the figures.
+    slideshow: &'a Slideshow,
+}
+
+/// Draw the slide at `idx` as an SVG document, `view_box` units wide and
+/// high, as the audience would see it. The sections without a position or
+/// a size get the ones in `defaults`.
+///
+/// If there is no slide at `idx`, there is no document.
+#[must_use]
+pub fn render_slide(
+    slideshow: &Slideshow,
+    idx: usize,
+    view_box: (u32, u32),
+    defaults: &RenderDefaults,
+) -> Option<String> {
+    let slide = slideshow.slides.get(idx)?;
+    let mut out = String::new();
+    write_slide(&mut out, slideshow, slide, view_box, defaults).ok()?;
+    Some(out)
+}
+
+/// Write each slide of `slideshow` in `dir`, as `slide-001.svg`,
+/// `slide-002.svg` and so on, and get the paths of the files: see
+/// [`render_slide`].
+///
+/// # Errors
+///
+/// If a file can't be written.
+pub fn export_slides(
+    slideshow: &Slideshow,
+    dir: &Path,
+    view_box: (u32, u32),
+    defaults: &RenderDefaults,
+) -> std::io::Result<Vec<PathBuf>> {
+    let mut written = vec![];
+    for idx in 0..slideshow.slides.len() {
+        let Some(svg) = render_slide(slideshow, idx, view_box, defaults) else {
+            continue;
+        };
+        let path = dir.join(format!("slide-{:03}.svg", idx + 1));
+        std::fs::write(&path, svg)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Helper for [`render_slide`].
+fn write_slide(
+    out: &mut impl Write,
+    slideshow: &Slideshow,
+    slide: &Slide,
+    (width, height): (u32, u32),
+    defaults: &RenderDefaults,
+) -> std::fmt::Result {
+    let slide = slide.place_on_grid();
+    #[allow(clippy::cast_precision_loss)]
+    let style = Style {
+        area: (width as f32, height as f32),
+        defaults,
+        font_size: slideshow
+            .font_size
+            .as_ref()
+            .map_or((defaults.font_size.w, defaults.font_size.h), |s| {
+                (s.w, s.h)
+            }),
+        font_col: slideshow.font_col.unwrap_or(DEFAULT_FONT_COLOR),
+        slideshow,
+    };
+    // The slide's own background wins, be it a color or a gradient, as in
+    // SDL.
+    let gradient = match (slide.bg_color, slide.gradient) {
+        (Some(_), _) => None,
+        (None, Some(gradient)) => Some(gradient),
+        (None, None) => slideshow.gradient,
+    };
+    let bg_col = slide
+        .bg_color
+        .or(slideshow.bg_col)
+        .unwrap_or(DEFAULT_BG_COLOR);
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" \
+         height=\"{height}\" viewBox=\"0 0 {width} {height}\">"
+    )?;
+    write_background(out, gradient, bg_col)?;
+    let mut base_height = defaults.text_position.y;
+    let visible = slide.sections.iter().filter(|s| s.visibility.on_main());
+    for section in visible {
+        match &section.sec_main {
+            Some(SectionMain::Text(text)) => {
+                write_text(out, section, text, &mut base_height, &style)?;
+            }
+            Some(SectionMain::Figure(fig)) => {
+                write_figure(out, section, fig, &style)?;
+            }
+            None => {}
+        }
+    }
+    writeln!(out, "</svg>")
+}
+
+/// The `name` attribute painting with `color`, and the `opacity` one when it
+/// is not opaque: SVG colors have no alpha.
+fn paint(name: &str, opacity: &str, color: Color) -> String {
+    let Color { r, g, b, a } = color;
+    let mut attrs = format!("{name}=\"#{r:02x}{g:02x}{b:02x}\"");
+    if a < u8::MAX {
+        let _ = write!(attrs, " {opacity}=\"{:.3}\"", f32::from(a) / 255.0);
+    }
+    attrs
+}
+
+/// Helper: the `fill` attributes for `color`.
+fn fill(color: Color) -> String {
+    paint("fill", "fill-opacity", color)
+}
+
+/// Write the background, covering the whole image.
+fn write_background(
+    out: &mut impl Write,
+    gradient: Option<Gradient>,
+    bg_col: Color,
+) -> std::fmt::Result {
+    let Some(gradient) = gradient else {
+        return writeln!(
+            out,
+            "<rect width=\"100%\" height=\"100%\" {}/>",
+            fill(bg_col)
+        );
+    };
+    let end = match gradient.direction {
+        GradientDirection::Vertical => "x2=\"0\" y2=\"1\"",
+        GradientDirection::Horizontal => "x2=\"1\" y2=\"0\"",
+    };
+    writeln!(
+        out,
+        "<defs><linearGradient id=\"bg\" x1=\"0\" y1=\"0\" {end}>"
+    )?;
+    for (offset, color) in [(0, gradient.from), (1, gradient.to)] {
+        writeln!(
+            out,
+            "<stop offset=\"{offset}\" {}/>",
+            paint("stop-color", "stop-opacity", color)
+        )?;
+    }
+    writeln!(out, "</linearGradient></defs>")?;
+    writeln!(
+        out,
+        "<rect width=\"100%\" height=\"100%\" fill=\"url(#bg)\"/>"
+    )
+}
+
+/// Write a text section, below `base_height` if it has no position. Each
+/// line is stretched to take a letter per character, like SDL does.
+fn write_text(
+    out: &mut impl Write,
+    section: &Section,
+    text: &SectionText,
+    base_height: &mut f32,
+    style: &Style,
+) -> std::fmt::Result {
+    let (w, h) = style.area;
+    let (x_size, y_size) = section
+        .size
+        .as_ref()
+        .map_or(style.font_size, |s| (s.w, s.h));
+    let line_step = y_size * text.line_height.unwrap_or(1.0);
+    let color = text.color.unwrap_or(style.font_col);
+    let lines = plain_lines(text);
+    // The empty lines only take some space when the text has a position.
+    let rows: Vec<(usize, &String)> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| section.position.is_some() || !line.is_empty())
+        .collect();
+    let default = Position {
+        x: style.defaults.text_position.x,
+        y: *base_height,
+    };
+    let (x, top) = section
+        .position_or(&default)
+        .map_or((default.x, default.y), |p| (p.x, p.y));
+    #[allow(clippy::cast_precision_loss)]
+    let height = line_step.mul_add(rows.len().saturating_sub(1) as f32, y_size);
+    let bg = section.bg_color.or_else(|| {
+        text.is_code.then_some(Color {
+            a: CODE_BG_ALPHA,
+            ..color
+        })
+    });
+    let longest = lines.iter().map(|l| l.chars().count()).max();
+    #[allow(clippy::cast_precision_loss)]
+    let width = text
+        .box_width
+        .unwrap_or_else(|| longest.unwrap_or(0) as f32 * x_size);
+    // The whole text, box included, turns around its middle.
+    let turned = text.rotation != 0.0 && !rows.is_empty();
+    if turned {
+        writeln!(
+            out,
+            "<g transform=\"rotate({:.2} {:.2} {:.2})\">",
+            text.rotation,
+            width.mul_add(0.5, x) * w,
+            height.mul_add(0.5, top) * h
+        )?;
+    }
+    if let (Some(bg), false) = (bg, rows.is_empty()) {
+        writeln!(
+            out,
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" \
+             {}/>",
+            x * w,
+            top * h,
+            width * w,
+            height * h,
+            fill(bg)
+        )?;
+    }
+    let font = if text.is_code {
+        "monospace"
+    } else {
+        "sans-serif"
+    };
+    for (row, (idx, line)) in rows.iter().enumerate() {
+        #[allow(clippy::cast_precision_loss)]
+        let y = line_step.mul_add(row as f32, top);
+        // The cells of the columns are written one by one, so that they are
+        // aligned whatever the font is.
+        let cells = text.cells(*idx).unwrap_or_else(|| vec![(0, line)]);
+        for (start, cell) in cells.into_iter().filter(|(_, c)| !c.is_empty()) {
+            #[allow(clippy::cast_precision_loss)]
+            let (x, length) = (
+                (start as f32).mul_add(x_size, x),
+                cell.chars().count() as f32 * x_size,
+            );
+            writeln!(
+                out,
+                "<text x=\"{:.2}\" y=\"{:.2}\" font-family=\"{font}\" \
+                 font-size=\"{:.2}\" dominant-baseline=\"hanging\" \
+                 textLength=\"{:.2}\" lengthAdjust=\"spacingAndGlyphs\" \
+                 xml:space=\"preserve\" {}>{}</text>",
+                x * w,
+                y * h,
+                y_size * h,
+                length * w,
+                fill(color),
+                escape(cell)
+            )?;
+        }
+    }
+    if turned {
+        writeln!(out, "</g>")?;
+    }
+    if section.position.is_none() && !rows.is_empty() {
+        #[allow(clippy::cast_precision_loss)]
+        let rows = rows.len() as f32;
+        *base_height = line_step.mul_add(rows, *base_height);
+    }
+    Ok(())
+}
+
+/// The lines of `text`. The inline images can't be drawn in a text element:
+/// only the text around them is kept.
+fn plain_lines(text: &SectionText) -> Vec<String> {
+    text.text
+        .trim_end_matches('\n')
+        .split('\n')
+        .map(|line| {
+            TextRun::split(line)
+                .into_iter()
+                .filter_map(|run| match run {
+                    TextRun::Text(text) => Some(text),
+                    TextRun::Image { .. } => None,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Write a figure section, with its caption, if it has one. A sequence of
+/// images shows its first one.
+fn write_figure(
+    out: &mut impl Write,
+    section: &Section,
+    fig: &SectionFigure,
+    style: &Style,
+) -> std::fmt::Result {
+    let (w, h) = style.area;
+    let pos = section
+        .position_or(&style.defaults.figure_position)
+        .unwrap_or_else(|| style.defaults.figure_position.clone());
+    let size = section.size.as_ref().unwrap_or(&style.defaults.figure_size);
+    let (x, y, width, height) = (pos.x * w, pos.y * h, size.w * w, size.h * h);
+    if let Some(bg) = section.bg_color {
+        writeln!(
+            out,
+            "<rect x=\"{x:.2}\" y=\"{y:.2}\" width=\"{width:.2}\" \
+             height=\"{height:.2}\" {}/>",
+            fill(bg)
+        )?;
+    }
+    // The figure turns around its center, unless it has an anchor.
+    let (ax, ay) = fig.anchor.as_ref().map_or((0.5, 0.5), |a| (a.x, a.y));
+    let (cx, cy) = (width.mul_add(ax, x), height.mul_add(ay, y));
+    write!(
+        out,
+        "<image href=\"{}\" x=\"{x:.2}\" y=\"{y:.2}\" width=\"{width:.2}\" \
+         height=\"{height:.2}\" preserveAspectRatio=\"none\"",
+        escape(&style.slideshow.resolve_path(&fig.path))
+    )?;
+    if fig.opacity < 1.0 {
+        write!(out, " opacity=\"{:.3}\"", fig.opacity)?;
+    }
+    if fig.rotation != 0.0 {
+        write!(
+            out,
+            " transform=\"rotate({:.2} {cx:.2} {cy:.2})\"",
+            fig.rotation
+        )?;
+    }
+    writeln!(out, "/>")?;
+    let Some(caption) = &fig.caption else {
+        return Ok(());
+    };
+    // Centered below the image, with smaller letters.
+    let letter_h = style.font_size.1 * CAPTION_SCALE * h;
+    writeln!(
+        out,
+        "<text x=\"{:.2}\" y=\"{:.2}\" font-family=\"sans-serif\" \
+         font-size=\"{letter_h:.2}\" dominant-baseline=\"hanging\" \
+         text-anchor=\"middle\" {}>{}</text>",
+        width.mul_add(0.5, x),
+        y + height,
+        fill(style.font_col),
+        escape(caption)
+    )
+}
+
+/// Escape the characters that have a meaning in XML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn test_render_slide() {
+        let slides = crate::parser::parse_text(
+            ":ge :bc 10 20 30 255\n:sl\n:tb :ps 0.5 0.25 :fc #ff000080\n\
+             Fish & chips\n",
+            Path::new(""),
+        )
+        .unwrap();
+        let svg =
+            render_slide(&slides, 0, (200, 100), &RenderDefaults::default())
+                .unwrap();
+        assert!(svg.starts_with("<svg "), "{svg}");
+        assert!(svg.contains("viewBox=\"0 0 200 100\""), "{svg}");
+        assert!(svg.contains("fill=\"#0a141e\""), "{svg}");
+        assert!(svg.contains(">Fish &amp; chips</text>"), "{svg}");
+        assert!(
+            svg.contains("fill=\"#ff0000\" fill-opacity=\"0.502\""),
+            "{svg}"
+        );
+        assert!(svg.contains("x=\"100.00\" y=\"25.00\""), "{svg}");
+        assert!(svg.trim_end().ends_with("</svg>"), "{svg}");
+
+        let defaults = RenderDefaults::default();
+        assert!(render_slide(&slides, 1, (200, 100), &defaults).is_none());
+    }
+
+    #[test]
+    fn test_render_turned_text() {
+        let slides = crate::parser::parse_text(
+            ":sl\n:tb :ps 0.1 0.2 :sz 0.1 0.2 :rt 90\nAbcd\n",
+            Path::new(""),
+        )
+        .unwrap();
+        let svg =
+            render_slide(&slides, 0, (100, 100), &RenderDefaults::default())
+                .unwrap();
+        assert!(
+            svg.contains("<g transform=\"rotate(90.00 30.00 30.00)\">"),
+            "{svg}"
+        );
+        assert!(svg.contains("</text>\n</g>"), "{svg}");
+    }
+
+    #[test]
+    fn test_render_figure() {
+        let slides = crate::parser::parse_text(
+            ":sl :bc gradient #000000ff #ffffffff horizontal\n\
+             :fg resources/star.jpg :ps 0.1 0.2 :sz 0.5 0.5 :rt 30",
+            Path::new(""),
+        )
+        .unwrap();
+        let svg =
+            render_slide(&slides, 0, (100, 100), &RenderDefaults::default())
+                .unwrap();
+        assert!(svg.contains("<linearGradient id=\"bg\""), "{svg}");
+        assert!(svg.contains("x2=\"1\" y2=\"0\""), "{svg}");
+        assert!(svg.contains("star.jpg\" x=\"10.00\" y=\"20.00\""), "{svg}");
+        assert!(svg.contains("width=\"50.00\""), "{svg}");
+        assert!(svg.contains("rotate(30.00 35.00 45.00)"), "{svg}");
+    }
+
+    #[test]
+    fn test_parse_view_box() {
+        assert_eq!(parse_view_box("1920x1080"), Ok((1920, 1080)));
+        assert_eq!(parse_view_box("800 X 600"), Ok((800, 600)));
+        assert!(parse_view_box("0x10").is_err());
+        assert!(parse_view_box("16:9").is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! Export the slides to other formats, like handouts.
+
+use std::io::Write;
+
+use crate::slideshow::Slideshow;
+
+/// Write the notes of the slideshow as a markdown document, to be used as a
+/// handout: each slide has its own heading, with its title, followed by the
+/// text of the slide.
+///
+/// # Errors
+///
+/// If writing to `out` fails.
+pub fn write_notes(
+    slideshow: &Slideshow,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    for (idx, slide) in slideshow.slides.iter().enumerate() {
+        if idx > 0 {
+            writeln!(out)?;
+        }
+        match slide.title() {
+            Some(title) => writeln!(out, "## {}. {}", idx + 1, title)?,
+            None => writeln!(out, "## {}.", idx + 1)?,
+        }
+        // The title is already in the heading.
+        let mut lines = slide
+            .text_lines()
+            .skip_while(|l| l.trim().is_empty())
+            .skip(1)
+            .peekable();
+        if lines.peek().is_some() {
+            writeln!(out)?;
+        }
+        for line in lines {
+            writeln!(out, "{}", line.trim_end())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn test_write_notes() {
+        let text = ":sl :tb
+Title
+First line
+  second line
+:sl :fg resources/star.jpg
+:sl :tb
+
+Only a title
+";
+        let slideshow = crate::parser::parse_text(text, Path::new("")).unwrap();
+        let mut out = vec![];
+        write_notes(&slideshow, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "## 1. Title
+
+First line
+  second line
+
+## 2.
+
+## 3. Only a title
+"
+        );
+    }
+}
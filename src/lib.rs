@@ -41,7 +41,9 @@ println!("{:?}", slides);
 
 Since the [Slideshow](`crate::slideshow::Slideshow`) struct implements
 `serde`'s `Serialize` and `Deserialize`, slides can also be defined in other
-formats. A json example is provided.
+formats. A json example is provided, and [`parser::parse_file`] reads the
+`.json` files, as well as the `.yaml` and `.toml` ones with the `yaml` and
+`toml` features.
 
 ### The available backends
 
@@ -69,8 +71,8 @@ An easy way to use them is described below.
 
 // let slides = ...
 
-let mut backend = slidy::backends::get_backend(&slidy::backends::Backends::Crossterm);
-let mut context = backend.get_context();
+let mut backend = slidy::backends::get_backend(&slidy::backends::Backends::Crossterm).unwrap();
+let mut context = backend.get_context().unwrap();
 
 // Here, an event loop should be used, but we skip that in this example.
 context.set_slides(slides)
@@ -82,6 +84,14 @@ context.set_slides(slides)
 // context.render();
 ```
 
+The [`run`] module packs all of this, with the event loop and the reloading
+of the file when it changes, the way the executable does:
+
+```no_run
+let options = slidy::run::RunOptions::default();
+slidy::run::run_file(std::path::Path::new("talk.txt"), options).unwrap();
+```
+
 Just check the provided executable or the examples for more details.
 
 # Slidy, as an executable
@@ -93,7 +103,15 @@ the slides written with the slidy language.
 
 /// The available backends.
 pub mod backends;
+/// Export the slides to other formats.
+pub mod export;
 /// The parser for `slidy`'s language.
 pub mod parser;
+/// Show the slides until the user quits.
+pub mod run;
 /// The slideshow structure.
 pub mod slideshow;
+/// Keep many slideshows on the same slide.
+pub mod sync;
+/// The themes, to restyle the slides without changing them.
+pub mod theme;
@@ -1,14 +1,18 @@
 use std::fs::canonicalize;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::mpsc::channel;
-use std::thread;
-use std::thread::sleep;
+use std::sync::Arc;
 use std::time::Duration;
 
 use clap::Parser;
-use notify::{RecursiveMode, Watcher};
-use tracing::{error, info, level_filters, warn};
+use tracing::{info, level_filters, warn};
+
+use slidy::run::is_markdown;
+
+/// Where the logs are written.
+const LOG_DIR: &str = "/tmp/";
+/// The name of the log files, before the hour they are about.
+const LOG_PREFIX: &str = "slidy.log";
 
 #[derive(Debug, Parser)]
 /// My Amazing Personal Slideshow command line options.
@@ -22,6 +26,409 @@ struct Args {
     #[arg(short = 'b', long = "backend")]
     /// The log level to be used.
     backend: Option<String>,
+    #[arg(long = "wrap")]
+    /// Go back to the first slide after the last one (and vice versa).
+    wrap: bool,
+    #[arg(long = "linear")]
+    /// Ignore the chapters made with `:group`: the next and the previous
+    /// slides are always the ones next to the current one.
+    linear: bool,
+    #[arg(long = "transitions")]
+    /// Fade between the slides (SDL only).
+    transitions: bool,
+    #[arg(long = "keep-blank-lines")]
+    /// Make the blank lines in a text section space its paragraphs (SDL
+    /// only). By default, they are skipped, unless the section has a
+    /// position.
+    keep_blank_lines: bool,
+    #[arg(long = "title-bar")]
+    /// Show the title and the number of the current slide in the title bar
+    /// of the main window (SDL only).
+    title_bar: bool,
+    #[arg(long = "overlay")]
+    /// Show the slides in a borderless window on top of the others (SDL
+    /// only). A fully transparent background color lets what is behind the
+    /// window show up, where the platform supports it.
+    overlay: bool,
+    #[arg(long = "mirror")]
+    /// Show the slides in a second window too, like for a second screen
+    /// (SDL only). It follows the main window; the timer and the counter
+    /// stay in the main one.
+    mirror: bool,
+    #[arg(long = "pip")]
+    /// Show the next slide in a corner of the main window, instead of in a
+    /// second window (SDL only): handy with a single screen.
+    pip: bool,
+    #[arg(long = "monitor", value_name = "INDEX")]
+    /// Show the slides on the given display, like a projector (SDL only).
+    /// The first display is 0.
+    monitor: Option<u32>,
+    #[arg(
+        long = "aspect",
+        value_name = "W:H",
+        default_value = "auto",
+        value_parser = slidy::backends::parse_aspect
+    )]
+    /// Draw the slides with the given width:height ratio, like `4:3` or
+    /// `16:9`, with bars around them where the window has another shape
+    /// (SDL only). With `auto` (the default), the slides fill the window.
+    // The full path keeps clap from taking the value as optional: `auto` is
+    // the ratio that is not set.
+    aspect: std::option::Option<f32>,
+    #[arg(long = "uniform-scale")]
+    /// Scale the sizes of the texts and the figures with the shorter side of
+    /// the window, so that a square figure stays square when the window is
+    /// resized (SDL only). The positions still follow the window.
+    uniform_scale: bool,
+    #[arg(long = "auto-contrast")]
+    /// When the slides do not set the font color, draw the text in black or
+    /// in white, whichever is readable on the background (SDL only).
+    auto_contrast: bool,
+    #[arg(
+        long = "timer-countdown",
+        value_name = "MINUTES",
+        value_parser = parse_minutes
+    )]
+    /// Make the timer count down from the given minutes (SDL only): it turns
+    /// yellow 2 minutes before the end, and red when the time is over.
+    timer_countdown: Option<Duration>,
+    #[arg(
+        long = "timer-precision",
+        value_name = "PRECISION",
+        value_parser = slidy::backends::TimerPrecision::from_str
+    )]
+    /// Show the timer in whole seconds (`seconds`, the default), or with the
+    /// tenths of a second as `MM:SS.d` (`tenths`), for the lightning talks
+    /// (SDL only).
+    timer_precision: Option<slidy::backends::TimerPrecision>,
+    #[arg(long = "slide-range", value_name = "RANGE")]
+    /// Show only some of the slides, like `--slide-range 10-20` (both
+    /// included) or `10..20` (without the 20th), to rehearse a part of the
+    /// talk. The slides are numbered from 1.
+    slide_range: Option<String>,
+    #[arg(long = "profile")]
+    /// Show only the slides and the sections for the given audience, like
+    /// `--profile advanced`: the ones tagged for other profiles with `:tag`
+    /// are left out, and the ones with no tags are always shown.
+    profile: Option<String>,
+    #[arg(long = "bind", value_name = "KEY=ACTION")]
+    /// Change what a key does, like `--bind space=next`. The actions are
+    /// next, prev, next-sub, prev-sub, quit, reload, toggle-timer,
+    /// toggle-side, toggle-counter, toggle-pointer, toggle-hud, cycle-font,
+    /// toggle-spotlight, next-focus, back, timer-start,
+    /// timer-reset and edit. Can be given more than once.
+    bind: Vec<String>,
+    #[arg(long = "render-default", value_name = "NAME=X,Y")]
+    /// Change where the sections go, and how big they are, when the slides
+    /// do not say it, like `--render-default font-size=0.02,0.1`. The names
+    /// are text-position, figure-position, figure-size and font-size. Can be
+    /// given more than once.
+    render_default: Vec<String>,
+    #[arg(
+        long = "poll-interval",
+        value_parser = clap::value_parser!(u64).range(10..)
+    )]
+    /// Check the slides for changes every given milliseconds, instead of
+    /// relying on the file system notifications. At least 10.
+    poll_interval: Option<u64>,
+    #[arg(long = "watch-assets")]
+    /// Read the slides again when one of the images they show changes too,
+    /// and not only when the slides' file does.
+    watch_assets: bool,
+    #[arg(long = "show-errors")]
+    /// When the slides can't be read again, show the error in place of
+    /// them, instead of keeping the last good ones.
+    show_errors: bool,
+    #[arg(long = "stats")]
+    /// Print some statistics about the slides, and exit.
+    stats: bool,
+    #[arg(long = "print-resolved")]
+    /// Print the colors and sizes each slide and section is drawn with, once
+    /// the defaults are applied, and exit.
+    print_resolved: bool,
+    #[arg(long = "wpm", default_value_t = slidy::slideshow::DEFAULT_WPM)]
+    /// The words per minute used to estimate the duration with `--stats`.
+    wpm: u32,
+    #[arg(long = "export-notes", value_name = "DIR")]
+    /// Write the titles and the text of the slides in `DIR/notes.md`, to be
+    /// used as a handout, and exit.
+    export_notes: Option<PathBuf>,
+    #[arg(long = "export-svg", value_name = "DIR")]
+    /// Write each slide as an SVG image in `DIR`, as `slide-001.svg` and so
+    /// on, and exit. The `--render-default`s apply.
+    export_svg: Option<PathBuf>,
+    #[arg(long = "svg-size", value_name = "WxH")]
+    /// The size of the images written with `--export-svg`, like `1280x720`.
+    /// By default, 1920x1080.
+    svg_size: Option<String>,
+    #[arg(long = "strict-vars")]
+    /// Using a variable that was not defined with `:def` is an error, instead
+    /// of leaving the `${NAME}` in the text.
+    strict_vars: bool,
+    #[arg(long = "strict")]
+    /// Stop on what is only warned about, like a color of `:ge` set again
+    /// with another value, or a variable that is not defined.
+    strict: bool,
+    #[arg(long = "merge-imports")]
+    /// Use the colors and sizes set with `:ge` in the imported files, where
+    /// the importing file does not set them. By default only the slides are
+    /// imported.
+    merge_imports: bool,
+    #[arg(long = "dump-tokens")]
+    /// Print the tokens found in the slides as JSON, for the tools like the
+    /// editors, and exit.
+    dump_tokens: bool,
+    #[arg(long = "theme", value_parser = slidy::theme::Theme::from_str)]
+    /// Restyle the slides with a theme: dark, light or solarized. The colors
+    /// and sizes set in the slides win, unless `--force-theme` is given.
+    theme: Option<slidy::theme::Theme>,
+    #[arg(long = "force-theme", requires = "theme")]
+    /// The theme replaces the colors set with `:ge` as well, but not the
+    /// size of the letters. The ones set in the sections still win.
+    force_theme: bool,
+    #[arg(long = "serve", value_name = "ADDR", conflicts_with = "follow")]
+    /// Send the slide being shown to the slidy instances that `--follow`
+    /// this one, like `--serve 0.0.0.0:7878`.
+    serve: Option<String>,
+    #[arg(long = "follow", value_name = "ADDR")]
+    /// Show the same slide as the slidy instance that `--serve`s at the
+    /// given address, like `--follow 192.168.1.10:7878`.
+    follow: Option<String>,
+    #[arg(long = "verbose-parse")]
+    /// Print the tokens found in the slides, and how the parser reads them
+    /// with the other parse options, and exit.
+    verbose_parse: bool,
+    #[arg(long = "from-markdown")]
+    /// Read the slides as Markdown, even if the file does not end in `.md`.
+    from_markdown: bool,
+}
+
+/// Parse the slides at `path`, in Markdown or in the slidy language, tuned
+/// with the given options.
+fn parse_slides(
+    path: &Path,
+    markdown: bool,
+    options: &slidy::parser::ParseOptions,
+) -> Result<slidy::slideshow::Slideshow, Box<dyn std::error::Error>> {
+    if markdown {
+        slidy::parser::parse_markdown_file_with_options(path, options)
+    } else {
+        slidy::parser::parse_file_with_options(path, options)
+    }
+}
+
+/// What is done to the slides once they are parsed, as asked on the command
+/// line: the slides shown and the exported ones go through the same steps.
+struct PostParse {
+    theme: Option<slidy::theme::Theme>,
+    force_theme: bool,
+    profile: Option<String>,
+    slide_range: Option<std::ops::Range<usize>>,
+}
+
+impl PostParse {
+    fn new(args: &Args) -> Self {
+        let slide_range = args.slide_range.as_deref().map(|range| {
+            slidy::slideshow::parse_range(range)
+                .unwrap_or_else(|e| panic!("Invalid slide range: {e}"))
+        });
+        Self {
+            theme: args.theme,
+            force_theme: args.force_theme,
+            profile: args.profile.clone(),
+            slide_range,
+        }
+    }
+
+    /// Restyle the slides with the theme, and keep only the ones of the
+    /// profile and of the range, if any.
+    fn apply(&self, slides: &mut slidy::slideshow::Slideshow) {
+        if let Some(theme) = &self.theme {
+            theme.apply(slides, self.force_theme);
+        }
+        if let Some(profile) = &self.profile {
+            slides.retain_profile(profile);
+        }
+        if let Some(range) = &self.slide_range {
+            keep_slide_range(slides, range.clone());
+        }
+    }
+}
+
+/// Print the statistics of the slides, once they went through `post` like
+/// the slides shown.
+fn print_stats(
+    path: &Path,
+    markdown: bool,
+    options: &slidy::parser::ParseOptions,
+    post: &PostParse,
+    wpm: u32,
+) {
+    let mut slideshow =
+        parse_slides(path, markdown, options).unwrap_or_else(|e| {
+            panic!("Unable to parse {}: {}", path.display(), e)
+        });
+    post.apply(&mut slideshow);
+    let stats = slideshow.stats();
+    println!("Slides: {}", stats.slides);
+    println!("Words: {}", stats.words);
+    for (idx, words) in stats.words_per_slide.iter().enumerate() {
+        println!("  slide {}: {} words", idx + 1, words);
+    }
+    let duration = stats.estimated_duration(wpm).as_secs();
+    println!(
+        "Estimated duration: {}m{:02}s at {} words per minute",
+        duration / 60,
+        duration % 60,
+        wpm
+    );
+}
+
+/// Describe the colors and sizes each slide and section is drawn with, once
+/// the defaults are applied.
+fn resolved_report(slideshow: &slidy::slideshow::Slideshow) -> String {
+    use slidy::slideshow::SectionMain;
+
+    let mut report = String::new();
+    for (idx, slide) in slideshow.resolved_slides().enumerate() {
+        report += &format!("Slide {}: background {}\n", idx + 1, slide.bg_col);
+        for (sec_idx, section) in slide.sections.iter().enumerate() {
+            let what = match &section.section.sec_main {
+                Some(SectionMain::Text(text)) => {
+                    let first =
+                        text.text.lines().find(|l| !l.trim().is_empty());
+                    format!("text {:?}", first.unwrap_or_default().trim())
+                }
+                Some(SectionMain::Figure(fig)) => {
+                    format!("figure {}", fig.path)
+                }
+                None => "empty".to_string(),
+            };
+            let (w, h) = section.size;
+            report += &format!("  {}. {what}: size {w} x {h}", sec_idx + 1);
+            if let Some(color) = section.font_col {
+                report += &format!(", color {color}");
+            }
+            report.push('\n');
+        }
+    }
+    report
+}
+
+/// The suffix the hourly log files get at `now`: the date and the hour, in
+/// UTC, like `2024-03-01-13`.
+fn hourly_log_suffix(now: std::time::SystemTime) -> String {
+    let secs = now
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (days, hour) = (secs / 86_400, secs % 86_400 / 3600);
+    // The civil date out of the days since 1970-01-01, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let (era, doe) = (z / 146_097, z % 146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}-{hour:02}")
+}
+
+/// Keep only the slides in `range`, warning when it goes past the last one.
+fn keep_slide_range(
+    slides: &mut slidy::slideshow::Slideshow,
+    range: std::ops::Range<usize>,
+) {
+    let kept = slides.retain_range(range.clone());
+    // An open range goes to the last slide, wherever it is.
+    if kept.start != range.start
+        || (range.end != usize::MAX && kept.end != range.end)
+    {
+        let end = if range.end == usize::MAX {
+            String::new()
+        } else {
+            range.end.to_string()
+        };
+        warn!(
+            "The slide range {}..{end} goes past the last slide, showing {}..{}",
+            range.start + 1,
+            kept.start + 1,
+            kept.end
+        );
+    }
+}
+
+/// Write the notes of the slides in `dir`, once they went through `post`
+/// like the slides shown.
+fn export_notes(
+    path: &Path,
+    markdown: bool,
+    options: &slidy::parser::ParseOptions,
+    post: &PostParse,
+    dir: &Path,
+) {
+    let mut slideshow =
+        parse_slides(path, markdown, options).unwrap_or_else(|e| {
+            panic!("Unable to parse {}: {}", path.display(), e)
+        });
+    post.apply(&mut slideshow);
+    std::fs::create_dir_all(dir).unwrap_or_else(|e| {
+        panic!("Unable to create {}: {}", dir.display(), e)
+    });
+    let notes = dir.join("notes.md");
+    let mut file = std::fs::File::create(&notes).unwrap_or_else(|e| {
+        panic!("Unable to create {}: {}", notes.display(), e)
+    });
+    slidy::export::write_notes(&slideshow, &mut file).unwrap_or_else(|e| {
+        panic!("Unable to write {}: {}", notes.display(), e)
+    });
+    println!("Notes written to {}", notes.display());
+}
+
+/// Write the slides as SVG images in `dir`, once they went through `post`
+/// like the slides shown.
+fn export_svg(
+    (path, markdown): (&Path, bool),
+    options: &slidy::parser::ParseOptions,
+    post: &PostParse,
+    dir: &Path,
+    view_box: (u32, u32),
+    defaults: &slidy::backends::RenderDefaults,
+) {
+    let mut slideshow =
+        parse_slides(path, markdown, options).unwrap_or_else(|e| {
+            panic!("Unable to parse {}: {}", path.display(), e)
+        });
+    post.apply(&mut slideshow);
+    std::fs::create_dir_all(dir).unwrap_or_else(|e| {
+        panic!("Unable to create {}: {}", dir.display(), e)
+    });
+    let written = slidy::backends::svg::export_slides(
+        &slideshow, dir, view_box, defaults,
+    )
+    .unwrap_or_else(|e| panic!("Unable to write in {}: {}", dir.display(), e));
+    println!("{} slides written to {}", written.len(), dir.display());
+}
+
+/// The duration of `minutes`, given as a whole number.
+fn parse_minutes(minutes: &str) -> Result<Duration, String> {
+    let minutes: u64 = minutes.parse().map_err(|e| format!("{e}"))?;
+    minutes
+        .checked_mul(60)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("{minutes} minutes is too long"))
+}
+
+/// The positions and the sizes given with `--render-default`.
+fn render_defaults(values: &[String]) -> slidy::backends::RenderDefaults {
+    let mut render_defaults = slidy::backends::RenderDefaults::default();
+    for default in values {
+        render_defaults
+            .set_str(default)
+            .unwrap_or_else(|e| panic!("Invalid render default: {e}"));
+    }
+    render_defaults
 }
 
 #[doc(hidden)]
@@ -31,8 +438,16 @@ fn main() {
     let filter = level_filters::LevelFilter::from_str(&args.log_level)
         .expect("Please provide a valid log level.");
 
-    // Init logger.
-    let file_appender = tracing_appender::rolling::hourly("/tmp/", "slidy.log");
+    // Init logger. Tell the user where the logs are, since they are not
+    // shown anywhere else.
+    let log_path = Path::new(LOG_DIR).join(format!(
+        "{LOG_PREFIX}.{}",
+        hourly_log_suffix(std::time::SystemTime::now())
+    ));
+    if filter >= level_filters::LevelFilter::INFO {
+        eprintln!("Logging to {} (a new file every hour)", log_path.display());
+    }
+    let file_appender = tracing_appender::rolling::hourly(LOG_DIR, LOG_PREFIX);
     let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
     tracing_subscriber::fmt()
         .with_max_level(filter)
@@ -43,116 +458,207 @@ fn main() {
         panic!("`{}` is not a valid path: {}", &args.slide_path, e)
     });
     info!("Using file {}", &path.display());
+    let markdown = is_markdown(&path, args.from_markdown);
 
-    // Prepare the 3 channels to be used.
-    // 1. Send slides from parser to graphical loop.
-    let (send_slides_tx, send_slides_rx) = channel();
-    // 2. Ask the parser to create new slides.
-    let (request_update_tx, request_update_rx) = channel();
-    // 3. Notify a change in the input file.
-    let (watcher_tx, watcher_rx) = channel();
-    let mut watcher = notify::recommended_watcher(watcher_tx)
-        .expect("Unable to create the watcher");
-    watcher
-        .watch(&path, RecursiveMode::NonRecursive)
-        .unwrap_or_else(|e| panic!("Unable to watch {:?}: {}", &path, e));
-
-    // Let's start the threads now.
-    // The first one is related to the slider. Whenever a request to request_update is sent,
-    // the parser will parse the file and respond with the prepared array in the send_slides
-    // channel.
-    // This thread does something only when there is a new request to update
-    // the slides (mind that the only component that can send the requests is
-    // the inotify component described below.
-    // The recv call is blocking, which means that this thread sleeps until
-    // inotify wakes it up.
-    thread::spawn(move || {
-        loop {
-            if request_update_rx.recv().is_ok() {
-                // If we can't parse or send the slides, just print the reason,
-                // and then loop again waiting for a new request.
-
-                let slides = slidy::parser::parse_file(&path);
-                match slides {
-                    Err(e) => error!("Error when parsing {:?}: {}", &path, e),
-                    Ok(slides) => {
-                        if let Err(e) = send_slides_tx.send(slides) {
-                            error!("Error when sending the slides: {}", e)
-                        }
-                    }
-                };
-            }
-        }
-    });
+    let parse_options = slidy::parser::ParseOptions {
+        strict_vars: args.strict_vars,
+        merge_imported_defaults: args.merge_imports,
+        strict: args.strict,
+    };
+    let post_parse = PostParse::new(&args);
 
-    // This second thread is way less useful: we just use it to listed for the watcher thread
-    // and transfer the message to  the parser using the single request_update channel.
-    // This thread will basically sleep all the time (the recv() call is blocking) until there
-    // is a change in the slide file.
-    let request_update_tx_watcher = request_update_tx.clone();
-    thread::spawn(move || loop {
-        if watcher_rx.recv().is_ok() {
-            request_update_tx_watcher
-                .send(())
-                .expect("Unable request a slide's update")
+    if args.verbose_parse {
+        let mut stdout = std::io::stdout();
+        // Markdown has no tokens: it is only parsed, the way it is shown.
+        let parsed = if markdown {
+            println!("{} is read as Markdown: no tokens.", path.display());
+            parse_slides(&path, markdown, &parse_options)
+        } else {
+            slidy::parser::trace_file_with_options(
+                &path,
+                &parse_options,
+                &mut stdout,
+            )
+        };
+        match parsed {
+            Ok(slides) => println!("Parsed {} slides.", slides.slides.len()),
+            Err(e) => println!("Error when parsing {}: {}", path.display(), e),
         }
-    });
+        return;
+    }
 
-    // Request slides
-    request_update_tx
-        .send(())
-        .expect("Unable to request slide update");
-
-    // ... And finally the main graphical loop. This will receive new slides in the send_slides
-    // channel and show them whenever they are ready. Moreover, it can send update requests to
-    // to the request_update channel - even if this is only useful on initialization to tell
-    // the slider "hey give me the slides you have since I'm pretty new here ;)
-    // It could also have been done with a reference to the slider, maybe, but this looks nicer
-    // since I want the slider to live on another thread.
-
-    // @TODO change this based on the feature? Add a method in backend?
-    let preferred_backend = "sdl";
-    // Init backend and context.
-    let backend: slidy::backends::Backends = match args.backend {
-        Some(v) => v.try_into().unwrap(),
-        None => preferred_backend.try_into().unwrap(),
-    };
+    if args.dump_tokens {
+        if markdown {
+            println!("{} is read as Markdown: no tokens.", path.display());
+            return;
+        }
+        let mut stdout = std::io::stdout();
+        if let Err(e) = slidy::parser::dump_tokens_file(&path, &mut stdout) {
+            println!("Error when reading {}: {}", path.display(), e);
+        }
+        return;
+    }
 
-    let mut backend = slidy::backends::get_backend(&backend);
-    let mut context = backend.get_context();
+    if let Some(dir) = &args.export_notes {
+        export_notes(&path, markdown, &parse_options, &post_parse, dir);
+        return;
+    }
 
-    // Fix the max fps.
-    let fixed_fps = Duration::from_nanos(1_000_000_000 / 10);
+    if let Some(dir) = &args.export_svg {
+        let view_box = args.svg_size.as_deref().map_or(
+            Ok(slidy::backends::svg::DEFAULT_VIEW_BOX),
+            slidy::backends::svg::parse_view_box,
+        );
+        let view_box = view_box.unwrap_or_else(|e| panic!("{e}"));
+        let defaults = render_defaults(&args.render_default);
+        export_svg(
+            (&path, markdown),
+            &parse_options,
+            &post_parse,
+            dir,
+            view_box,
+            &defaults,
+        );
+        return;
+    }
 
-    // The event loop.
-    'running: loop {
-        let timer = std::time::SystemTime::now();
-        // Check if we have new slides
-        if let Ok(slides) = send_slides_rx.try_recv() {
-            context.set_slides(slides)
-        };
+    if args.stats {
+        print_stats(&path, markdown, &parse_options, &post_parse, args.wpm);
+        return;
+    }
 
-        if context.manage_inputs() {
-            break 'running;
-        }
-        context.render();
-
-        match timer.elapsed() {
-            Ok(elapsed) => {
-                if elapsed < fixed_fps {
-                    let sleeptime = fixed_fps - elapsed;
-                    // Fix framerate to 10 fps
-                    sleep(sleeptime);
-                } else {
-                    warn!(
-                        "Unable to have 10 fps, needed {:?} to show the slide",
-                        elapsed
-                    );
-                }
+    if args.print_resolved {
+        match parse_slides(&path, markdown, &parse_options) {
+            Ok(mut slideshow) => {
+                post_parse.apply(&mut slideshow);
+                print!("{}", resolved_report(&slideshow));
             }
             Err(e) => {
-                error!("Previous measured time is later than actual one: {}", e)
+                eprintln!("Unable to parse {}: {}", path.display(), e);
+                std::process::exit(1);
             }
         }
+        return;
+    }
+
+    let mut keymap = slidy::backends::keymap::KeyMap::default();
+    for binding in &args.bind {
+        keymap
+            .bind_str(binding)
+            .unwrap_or_else(|e| panic!("Invalid key binding: {e}"));
+    }
+    let render_defaults = render_defaults(&args.render_default);
+    let backend_options = slidy::backends::Options {
+        wrap: args.wrap,
+        transitions: args.transitions,
+        overlay: args.overlay,
+        mirror: args.mirror,
+        pip: args.pip,
+        keymap,
+        monitor: args.monitor,
+        linear: args.linear,
+        aspect: args.aspect,
+        uniform_scale: args.uniform_scale,
+        auto_contrast: args.auto_contrast,
+        title_bar: args.title_bar,
+        keep_blank_lines: args.keep_blank_lines,
+        render_defaults,
+        countdown: args.timer_countdown,
+        timer_precision: args.timer_precision.unwrap_or_default(),
+    };
+    let backend = args.backend.as_deref().map(|which| {
+        slidy::backends::Backends::try_from(which)
+            .unwrap_or_else(|e| panic!("{e}"))
+    });
+    let options = slidy::run::RunOptions {
+        backend,
+        backend_options,
+        poll_interval: args.poll_interval.map(Duration::from_millis),
+        markdown,
+        show_errors: args.show_errors,
+        parse_options,
+        post_parse: Some(Arc::new(move |slides| post_parse.apply(slides))),
+        watch_assets: args.watch_assets,
+        serve: args.serve,
+        follow: args.follow,
+        ..slidy::run::RunOptions::default()
+    };
+
+    slidy::run::run_file(&path, options)
+        .unwrap_or_else(|e| panic!("Unable to show {}: {e}", path.display()));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_poll_interval() {
+        let parse = |ms: &str| {
+            Args::try_parse_from(["slidy", "talk.txt", "--poll-interval", ms])
+        };
+        assert_eq!(parse("10").unwrap().poll_interval, Some(10));
+        assert!(parse("0").is_err());
+        assert!(parse("9").is_err());
+    }
+
+    #[test]
+    fn test_aspect() {
+        let parse = |aspect: &[&str]| {
+            Args::try_parse_from([&["slidy", "talk.txt"], aspect].concat())
+        };
+        assert_eq!(parse(&[]).unwrap().aspect, None);
+        assert_eq!(parse(&["--aspect", "auto"]).unwrap().aspect, None);
+        let args = parse(&["--aspect", "4:3"]).unwrap();
+        assert_eq!(args.aspect, Some(4.0 / 3.0));
+        assert!(parse(&["--aspect", "4/3"]).is_err());
+    }
+
+    #[test]
+    fn test_timer_countdown() {
+        let parse = |minutes: &str| {
+            let args = ["slidy", "talk.txt", "--timer-countdown", minutes];
+            Args::try_parse_from(args)
+        };
+        let args = parse("20").unwrap();
+        assert_eq!(args.timer_countdown, Some(Duration::from_secs(1200)));
+        assert!(parse("-1").is_err());
+        assert!(parse(&u64::MAX.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_timer_precision() {
+        use slidy::backends::TimerPrecision;
+        let parse = |precision: &str| {
+            let args = ["slidy", "talk.txt", "--timer-precision", precision];
+            Args::try_parse_from(args)
+        };
+        let args = parse("tenths").unwrap();
+        assert_eq!(args.timer_precision, Some(TimerPrecision::Tenths));
+        assert!(parse("minutes").is_err());
+    }
+
+    #[test]
+    fn test_hourly_log_suffix() {
+        let at = |secs| {
+            hourly_log_suffix(std::time::UNIX_EPOCH + Duration::from_secs(secs))
+        };
+        assert_eq!(at(0), "1970-01-01-00");
+        assert_eq!(at(951_782_400), "2000-02-29-00");
+        assert_eq!(at(1_709_251_200 + 13 * 3600 + 59), "2024-03-01-13");
+    }
+
+    #[test]
+    fn test_resolved_report() {
+        let slides = slidy::parser::parse_text(
+            ":ge :bc black\n:sl\n:tb :sz 0.1 0.2\n\nTitle\nmore\n:sl :bc red",
+            Path::new(""),
+        )
+        .unwrap();
+        let expected = "Slide 1: background #000000ff
+  1. text \"Title\": size 0.1 x 0.2, color #000000ff
+Slide 2: background #ff0000ff
+";
+        assert_eq!(resolved_report(&slides), expected);
     }
 }
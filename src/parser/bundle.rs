@@ -0,0 +1,137 @@
+//! Load the slides from a zip bundle.
+//!
+//! A bundle is a zip archive (usually named `something.slidy.zip`) containing
+//! a `deck.txt` file, plus all the assets the deck refers to. The archive is
+//! extracted in a temporary folder, so that `:fg` and `:im` paths are resolved
+//! against the archive's content and not against the folder where the bundle
+//! lives.
+
+use std::error::Error;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tracing::debug;
+
+use crate::slideshow::TempFolder;
+
+/// The file, inside the bundle, that contains the slides.
+pub(super) const DECK_NAME: &str = "deck.txt";
+
+/// How many bundles were extracted by this process, to name their folders.
+static EXTRACTED: AtomicUsize = AtomicUsize::new(0);
+
+/// Extract the bundle at `path`, and return the path of the deck to parse,
+/// with the folder it was extracted into.
+///
+/// Each extraction gets its own folder, which is removed once the returned
+/// [`TempFolder`] (and the slideshow that keeps it) is dropped: this way
+/// live reload keeps working, and nothing is left behind.
+pub(super) fn extract(
+    path: &Path,
+) -> Result<(PathBuf, TempFolder), Box<dyn Error + 'static>> {
+    let name = path
+        .file_name()
+        .ok_or("The bundle must have a file name.")?
+        .to_string_lossy();
+    let target = std::env::temp_dir().join(format!(
+        "slidy-{}-{}-{}",
+        std::process::id(),
+        EXTRACTED.fetch_add(1, Ordering::Relaxed),
+        name
+    ));
+    // A folder left by a process that had the same id.
+    if target.exists() {
+        std::fs::remove_dir_all(&target)?;
+    }
+    std::fs::create_dir_all(&target)?;
+    let folder = TempFolder::new(target.clone());
+
+    debug!("Extracting {} into {}", path.display(), target.display());
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+    // `extract` already refuses entries that would end up outside `target`.
+    archive.extract(&target)?;
+
+    let deck = target.join(DECK_NAME);
+    if !deck.is_file() {
+        return Err(format!(
+            "The bundle {} does not contain a `{DECK_NAME}` file.",
+            path.display()
+        )
+        .into());
+    }
+    Ok((deck, folder))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+    use crate::slideshow::SectionMain;
+
+    /// Build a bundle in the temp folder, with the given entries. The name
+    /// is the test's own, so that the tests can run together.
+    fn build_bundle(name: &str, entries: &[(&str, &[u8])]) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("slidy-{}-{name}", std::process::id()));
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        for (entry, content) in entries {
+            writer
+                .start_file(*entry, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn test_bundle_with_figure_and_import() {
+        let star = include_bytes!("../../resources/star.jpg");
+        let path = build_bundle(
+            "test_bundle_ok.slidy.zip",
+            &[
+                (DECK_NAME, b":sl\n:fg img/star.jpg\n:im more.txt\n"),
+                ("more.txt", b":sl\n:tb\nImported from the bundle\n"),
+                ("img/star.jpg", star),
+            ],
+        );
+
+        let slideshow = super::super::parse_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        let slideshow = slideshow.expect("should be able to parse the bundle.");
+        assert_eq!(slideshow.slides.len(), 2);
+
+        let Some(SectionMain::Figure(fig)) =
+            &slideshow.slides[0].sections[0].sec_main
+        else {
+            panic!("The first section must be a figure.");
+        };
+        // The figure must be the extracted one, not something near the zip.
+        let extracted = slideshow.temp_folder.path().unwrap().to_path_buf();
+        let resolved = slideshow.resolve_path(&fig.path);
+        let canonical = extracted.canonicalize().unwrap();
+        assert!(Path::new(&resolved).starts_with(canonical), "{fig:?}");
+
+        // The folder lives as long as the slideshow, or a copy of it.
+        let copy = slideshow.clone();
+        drop(slideshow);
+        assert!(extracted.is_dir());
+        drop(copy);
+        assert!(!extracted.exists());
+    }
+
+    #[test]
+    fn test_bundle_without_deck() {
+        let path = build_bundle(
+            "test_bundle_ko.slidy.zip",
+            &[("slides.txt", b":sl\n")],
+        );
+        let extracted = extract(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(extracted.is_err());
+    }
+}
@@ -0,0 +1,318 @@
+//! Parse a text again each time it changes, reusing the slides before the
+//! first change.
+//!
+//! The lexer goes through the slides in order, so the slides before the
+//! first line that changed come out the same: before each `:sl`, the state
+//! of the lexer is kept, and the next parse starts again from the last `:sl`
+//! before the change.
+//!
+//! The imported files may change while the text does not: when the text
+//! imports any, it is parsed again as a whole each time.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use super::lexer::{Lexer, LexerInternal};
+use super::tokenizer::{tokenizer_from, Structure, Token};
+use super::ParseOptions;
+use crate::slideshow::{Slide, Slideshow};
+
+/// The state of the lexer right before a `:sl`.
+struct Checkpoint {
+    /// Where the `:sl` is: its line, and its first column.
+    at: (usize, usize),
+    /// How many slides were done.
+    slides_len: usize,
+    /// The slideshow's defaults, without the slides.
+    slideshow: Slideshow,
+    internals: LexerInternal,
+    variables: HashMap<String, String>,
+}
+
+impl Checkpoint {
+    /// Keep the state of `lexer`, that is about to read the `:sl` at `at`.
+    fn new(lexer: &mut Lexer, at: (usize, usize)) -> Self {
+        let slides = std::mem::take(&mut lexer.slideshow.slides);
+        let slideshow = lexer.slideshow.clone();
+        lexer.slideshow.slides = slides;
+        Self {
+            at,
+            slides_len: lexer.slideshow.slides.len(),
+            slideshow,
+            internals: lexer.internals.clone(),
+            variables: lexer.variables.clone(),
+        }
+    }
+
+    /// Bring `lexer` back to this state, with the `slides` done by then.
+    fn restore(&self, lexer: &mut Lexer, slides: &[Slide]) {
+        lexer.slideshow = Slideshow {
+            slides: slides[..self.slides_len].to_vec(),
+            ..self.slideshow.clone()
+        };
+        lexer.internals.clone_from(&self.internals);
+        lexer.variables.clone_from(&self.variables);
+    }
+}
+
+/// A parser that parses the same text over and over, as it is edited: only
+/// the slides from the first one that changed onward are parsed again.
+///
+/// ```
+/// use slidy::parser::IncrementalParser;
+/// use std::path::Path;
+///
+/// let mut parser = IncrementalParser::default();
+/// let base = Path::new("");
+/// let slides = parser.parse_text(":sl\n:tb\nOne\n:sl\n:tb\nTwo", base);
+/// assert_eq!(slides.unwrap().slides.len(), 2);
+/// // Only the second slide is parsed again.
+/// let slides = parser.parse_text(":sl\n:tb\nOne\n:sl\n:tb\nThree", base);
+/// assert_eq!(slides.unwrap().slides.len(), 2);
+/// ```
+#[derive(Default)]
+pub struct IncrementalParser {
+    /// How the input is parsed.
+    options: ParseOptions,
+    /// The text parsed last time, and the folder of its relative paths.
+    text: String,
+    base_folder: PathBuf,
+    /// The slides parsed last time, before they are sorted by their order.
+    slides: Vec<Slide>,
+    /// The state of the lexer before each `:sl`, in order.
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl IncrementalParser {
+    /// Create a parser, tuned with the given options.
+    #[must_use]
+    pub fn new(options: ParseOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Parse the input text, like [`super::parse_text`] does. The slides
+    /// before the first line that changed since the last call are not
+    /// parsed again, unless `base_folder` changed too. If nothing changed,
+    /// everything is parsed again.
+    pub fn parse_text(
+        &mut self,
+        inp: &str,
+        base_folder: &Path,
+    ) -> Result<Slideshow, Box<dyn Error + 'static>> {
+        let tokens = tokenizer_from(inp, 0);
+        // The same text is parsed again only when something else changed,
+        // like an imported file: then, everything is parsed again. The
+        // imported files are not followed, so they are always read again.
+        let imports = tokens.iter().any(|t| t.symbol == Structure::Import);
+        let first_changed = if base_folder == self.base_folder
+            && inp != self.text
+            && !imports
+        {
+            first_changed_line(&self.text, inp)
+        } else {
+            0
+        };
+        // Start again from the last slide that did not change.
+        let kept = self
+            .checkpoints
+            .iter()
+            .rposition(|c| c.at.0 < first_changed);
+        let mut lexer = Lexer::new(base_folder);
+        lexer.options = self.options;
+        let tokens = match kept.and_then(|k| self.checkpoints.get(k)) {
+            Some(checkpoint) => {
+                checkpoint.restore(&mut lexer, &self.slides);
+                let at = checkpoint.at;
+                let mut tokens = tokens;
+                tokens.retain(|t| t.span().start() >= at);
+                tokens
+            }
+            None => tokens,
+        };
+        // The checkpoint being resumed is taken again, with the next ones.
+        self.checkpoints.truncate(kept.unwrap_or(0));
+        let result = self.read_slides(&mut lexer, &tokens);
+
+        self.base_folder = base_folder.to_path_buf();
+        self.text = inp.to_string();
+        if let Err(e) = result {
+            // Nothing can be trusted: parse everything next time.
+            self.checkpoints.clear();
+            return Err(e);
+        }
+        if let Some(slide) = &lexer.internals.slide {
+            lexer.slideshow.slides.push(slide.clone());
+        }
+        self.slides.clone_from(&lexer.slideshow.slides);
+        lexer.internals.slide = None;
+        Ok(lexer.take())
+    }
+
+    /// Feed the lexer with the tokens, one slide at a time, keeping its
+    /// state before each one.
+    fn read_slides(
+        &mut self,
+        lexer: &mut Lexer,
+        tokens: &[Token],
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        let mut rest = tokens;
+        while !rest.is_empty() {
+            let len = rest
+                .iter()
+                .skip(1)
+                .position(|t| starts_slide(&t.symbol))
+                .map_or(rest.len(), |idx| idx + 1);
+            let (slide, next) = rest.split_at(len);
+            if starts_slide(&slide[0].symbol) {
+                let checkpoint =
+                    Checkpoint::new(lexer, slide[0].span().start());
+                self.checkpoints.push(checkpoint);
+            }
+            lexer.read_tokens(slide)?;
+            rest = next;
+        }
+        Ok(())
+    }
+
+    /// Parse the input file, like [`super::parse_file`] does, parsing again
+    /// only what changed since the last call.
+    pub fn parse_file(
+        &mut self,
+        path: &Path,
+    ) -> Result<Slideshow, Box<dyn Error + 'static>> {
+        // The data formats are read as a whole.
+        if let Some(slideshow) = super::parse_serialized(path) {
+            return slideshow;
+        }
+        let (content, base_folder, temp_folder) = super::read_file(path)?;
+        let slideshow = self.parse_text(&content, &base_folder)?;
+        Ok(Slideshow {
+            temp_folder,
+            ..slideshow
+        })
+    }
+}
+
+/// If the token is a `:sl`, or a `:sl+`.
+const fn starts_slide(symbol: &Structure) -> bool {
+    matches!(
+        symbol,
+        Structure::Slide | Structure::SlideCopy | Structure::Title
+    )
+}
+
+/// The first line that is not the same in `old` and `new`.
+fn first_changed_line(old: &str, new: &str) -> usize {
+    old.lines()
+        .zip(new.lines())
+        .position(|(o, n)| o != n)
+        .unwrap_or_else(|| old.lines().count().min(new.lines().count()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Parse `texts` one after the other, checking that each result is the
+    /// same as the one of a full parse.
+    fn check(texts: &[&str]) {
+        let base = Path::new("./resources");
+        let mut parser = IncrementalParser::default();
+        for text in texts {
+            let full = super::super::parse_text(text, base).unwrap();
+            let incremental = parser.parse_text(text, base).unwrap();
+            assert_eq!(incremental, full, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_first_changed_line() {
+        assert_eq!(first_changed_line("a\nb\nc", "a\nx\nc"), 1);
+        assert_eq!(first_changed_line("a\nb", "a\nb\nc"), 2);
+        assert_eq!(first_changed_line("a\nb", "a\nb"), 2);
+        assert_eq!(first_changed_line("", "a"), 0);
+    }
+
+    #[test]
+    fn test_incremental_same_as_full() {
+        let deck = ":ge :fc red :tw 2\n:def WHO world\n\
+                    :sl\n:tb\nHello ${WHO}\n\
+                    :sl :bc blue\n:tb :sz 0.1 0.2\n\tTabbed\n:fg star.jpg\n\
+                    :sl :order -1\n:tb\nFirst\n:sl+\n:tb\nBuilt on it";
+        check(&[
+            deck,
+            // The last slide changes.
+            &deck.replace("First", "Really first"),
+            // The middle one.
+            &deck.replace("Tabbed", "Moved"),
+            // The defaults, before any slide.
+            &deck.replace(":fc red", ":fc green"),
+            // A slide is added, then removed.
+            &format!("{deck}\n:sl\n:tb\nNew"),
+            deck,
+            deck,
+        ]);
+    }
+
+    #[test]
+    fn test_incremental_error() {
+        let base = Path::new("");
+        let mut parser = IncrementalParser::default();
+        let ok = ":sl\n:tb\nOne\n:sl\n:tb\nTwo";
+        parser.parse_text(ok, base).unwrap();
+        assert!(parser.parse_text(":sl\n:tb\nOne\n:sl :fc", base).is_err());
+        let slides = parser.parse_text(ok, base).unwrap();
+        assert_eq!(slides, super::super::parse_text(ok, base).unwrap());
+    }
+
+    #[test]
+    fn test_incremental_import() {
+        let dir = std::env::temp_dir().join("slidy_test_incremental_import");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut parser = IncrementalParser::default();
+        let mut parse = |imported: &str, text: &str| {
+            std::fs::write(dir.join("inc.txt"), imported).unwrap();
+            parser.parse_text(text, &dir)
+        };
+        let deck = ":im inc.txt
+:sl
+:tb
+One
+:sl
+:tb
+Two";
+        let first = parse(
+            ":sl
+:tb
+Before
+",
+            deck,
+        );
+        // Only the end of the text changes, but the import did too.
+        let second = parse(
+            ":sl
+:tb
+After
+",
+            &deck.replace("Two", "2"),
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+        let text = |slides: &Slideshow| {
+            slides.slides[0].texts().next().unwrap().text.clone()
+        };
+        assert_eq!(
+            text(&first.unwrap()),
+            "Before
+"
+        );
+        assert_eq!(
+            text(&second.unwrap()),
+            "After
+"
+        );
+    }
+}
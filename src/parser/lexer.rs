@@ -1,5 +1,6 @@
 //! Get out the logic from a stream of tokens.
 
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::path::Path;
 use tracing::{debug, trace};
@@ -11,7 +12,7 @@ use crate::slideshow;
 
 /// Helper to understand in which section we're in.
 /// It is based upon the tag we encountered while parsing.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub(super) enum CurrentState {
     General,
     Slide,
@@ -28,12 +29,31 @@ impl Default for CurrentState {
     }
 }
 
+/// How the whitespace around the text lines is handled, set with `:trim`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TrimMode {
+    /// Keep the lines as they are, useful for ASCII art.
+    #[default]
+    Off,
+    /// Remove the whitespace at the end of the lines.
+    Trailing,
+    /// Remove the whitespace at both ends of the lines.
+    Both,
+}
+
 /// The internals of the `TextParser`.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub(super) struct LexerInternal {
     /// In which section were we?
     pub state: CurrentState,
     pub slide: Option<slideshow::Slide>,
+    /// How many columns a tab in the text lines takes, if not the default.
+    pub tab_width: Option<usize>,
+    /// Which whitespace is removed from the text lines.
+    pub trim: TrimMode,
+    /// The general settings whose value was merged from an import, and not
+    /// set by this file yet: setting them is not an override.
+    pub merged: HashSet<&'static str>,
 }
 
 /// The text parser structure.
@@ -44,6 +64,10 @@ pub(super) struct Lexer<'a> {
     /// The parser's internal status.
     pub internals: LexerInternal,
     pub base_folder: Option<&'a Path>,
+    /// How the input is parsed.
+    pub options: super::ParseOptions,
+    /// The variables defined with `:def`, used in the text lines.
+    pub variables: HashMap<String, String>,
 }
 
 /// Check for the existence of a slide, and apply a closure on that.
@@ -63,6 +87,15 @@ impl<'a> Lexer<'a> {
             debug!("Pushing slide: {:?}", &s);
             slideshow.slides.push(s);
         }
+        if let Some(base_folder) = self.base_folder {
+            slideshow.base_folder = base_folder.to_path_buf();
+        }
+        for slide in &mut slideshow.slides {
+            slide.apply_layout(&slideshow.metadata);
+        }
+        // The sort is stable: the slides with the same order keep the one
+        // they have in the input.
+        slideshow.slides.sort_by_key(|s| s.order);
         slideshow
     }
 
@@ -77,12 +110,27 @@ impl<'a> Lexer<'a> {
         &mut self,
         tokens: &[Token],
     ) -> Result<(), Box<dyn Error + 'static>> {
+        self.read_tokens_traced(tokens, |_, _, _, _| {})
+    }
+
+    /// Like [`Self::read_tokens`], but `on_token` is called after each token
+    /// is consumed, with the token itself, the arguments it consumed, and
+    /// the states the lexer was in before and after it.
+    pub(super) fn read_tokens_traced<F>(
+        &mut self,
+        tokens: &[Token],
+        mut on_token: F,
+    ) -> Result<(), Box<dyn Error + 'static>>
+    where
+        F: FnMut(&Token, &[Token], CurrentState, CurrentState),
+    {
         let Some(base_folder) = self.base_folder else {todo!("base_folder must be set for now.")};
         let mut tokens = tokens;
         while let Some((t, rem)) = tokens.split_first() {
             // t is the token we are checking, rem is the remaining tokens.
             // We need to update rem in case we peek some elements.
             trace!("token: {:?}", t);
+            let previous_state = self.internals.state;
             let skip = match t.symbol {
                 Structure::Generic => {
                     self.internals.state = CurrentState::General;
@@ -94,16 +142,43 @@ impl<'a> Lexer<'a> {
                 Structure::Import => {
                     utils::manage_import(self, rem, base_folder)
                 }
-                Structure::Slide => Ok(utils::manage_slide(self, rem)),
+                Structure::Slide => Ok(utils::manage_slide(self, rem, false)),
+                Structure::SlideCopy => {
+                    Ok(utils::manage_slide(self, rem, true))
+                }
+                Structure::Title => utils::manage_title(self, rem),
                 Structure::TextLine(el) => {
                     utils::manage_textline(self, el, rem, base_folder)
                 }
                 Structure::TextBuffer => utils::manage_textbuffer(self, rem),
+                Structure::Code => utils::manage_code(self, rem),
                 Structure::Position => utils::manage_position(self, rem),
                 Structure::Size => utils::manage_size(self, rem),
                 Structure::Rotation => utils::manage_rotation(self, rem),
+                Structure::Opacity => utils::manage_opacity(self, rem),
+                Structure::Anchor => utils::manage_anchor(self, rem),
+                Structure::Caption => utils::manage_caption(self, rem),
+                Structure::FrameTime => utils::manage_frame_time(self, rem),
+                Structure::BoxWidth => utils::manage_box_width(self, rem),
+                Structure::LineHeight => utils::manage_line_height(self, rem),
+                Structure::VCenter => utils::manage_vcenter(self),
+                Structure::Columns => utils::manage_columns(self),
+                Structure::FitText => utils::manage_fit_text(self, rem),
+                Structure::TabWidth => utils::manage_tab_width(self, rem),
+                Structure::Trim => utils::manage_trim(self, rem),
+                Structure::Define => utils::manage_define(self, rem),
+                Structure::Meta => utils::manage_meta(self, rem),
+                Structure::Order => utils::manage_order(self, rem),
+                Structure::Group => utils::manage_group(self, rem),
+                Structure::Grid => utils::manage_grid(self, rem),
+                Structure::Tag => utils::manage_tag(self, rem),
+                Structure::NoCount => utils::manage_no_count(self),
+                Structure::Only => utils::manage_visibility(self, rem),
                 Structure::Fontcolor => utils::manage_fontcolor(self, rem),
                 Structure::BackGroundColor => utils::manage_bg_color(self, rem),
+                Structure::SectionBgColor => {
+                    utils::manage_section_bg_color(self, rem)
+                }
                 Structure::Comment(_) => {
                     // Ignore comments.
                     Ok(0)
@@ -116,6 +191,7 @@ impl<'a> Lexer<'a> {
             let skip: Result<usize, Box<dyn Error>> =
                 skip.map_err(|e| format!("token {:?}: {}", &t, e).into());
             let skip = skip?;
+            on_token(t, &rem[..skip], previous_state, self.internals.state);
             tokens = &rem[skip..];
         }
         Ok(())
@@ -129,7 +205,9 @@ mod test {
     use crate::parser::tokenizer::TokenSpan;
 
     use super::*;
-    use crate::slideshow::{Color, Section, SectionMain, SectionText};
+    use crate::slideshow::{
+        Color, Section, SectionMain, SectionText, Visibility,
+    };
     use Structure::{Figure, Fontcolor, Import, Slide, String, TextBuffer};
 
     fn resources_path() -> PathBuf {
@@ -177,6 +255,7 @@ mod test {
                 Section {
                     size: None,
                     position: None,
+                    free_axis: None,
                     sec_main: Some(SectionMain::Text(SectionText {
                         text: std::string::String::from(""),
                         color: Some(Color {
@@ -186,11 +265,23 @@ mod test {
                             a: 18,
                         }),
                         font: None,
+                        box_width: None,
+                        line_height: None,
+                        vcenter: false,
+                        is_code: false,
+                        lang: None,
+                        fit: None,
+                        columns: None,
+                        rotation: 0.0,
                     })),
+                    visibility: Visibility::Both,
+                    bg_color: None,
+                    tags: vec![],
                 },
                 Section {
                     size: None,
                     position: None,
+                    free_axis: None,
                     sec_main: Some(SectionMain::Text(SectionText {
                         text: std::string::String::from(""),
                         color: Some(Color {
@@ -200,9 +291,27 @@ mod test {
                             a: 255,
                         }),
                         font: None,
+                        box_width: None,
+                        line_height: None,
+                        vcenter: false,
+                        is_code: false,
+                        lang: None,
+                        fit: None,
+                        columns: None,
+                        rotation: 0.0,
                     })),
+                    visibility: Visibility::Both,
+                    bg_color: None,
+                    tags: vec![],
                 },
             ],
+            order: 0,
+            group: None,
+            counted: true,
+            grid: None,
+            tags: vec![],
+            gradient: None,
+            layout: None,
         };
         assert_eq!(result, &slide);
     }
@@ -0,0 +1,314 @@
+//! Read the slides from Markdown, for the talks that were written for other
+//! tools.
+//!
+//! Only the parts of Markdown that make sense in a slide are understood:
+//! - `---` starts a new slide;
+//! - `# Title` and `## Subtitle` are bigger text sections, the other headings
+//!   are plain text;
+//! - the lines starting with `-`, `*` or `+` make a list, with a bullet in
+//!   front of each item;
+//! - a line with just an image, like `![logo](logo.png)`, is a figure, while
+//!   the images in a line of text are drawn inside the text;
+//! - the fenced blocks (```` ```rust ````) are code sections;
+//! - the other lines are text, and a blank line ends a paragraph.
+//!
+//! Each line is kept as it is: the lines of a paragraph are not joined.
+
+use std::error::Error;
+use std::path::Path;
+
+use super::utils::resolve_inline_images;
+use super::ParseOptions;
+use crate::slideshow::{
+    decode_data_uri, Section, SectionFigure, SectionMain, SectionText, Size,
+    Slide, Slideshow, DEFAULT_FONT_SIZE,
+};
+
+/// What is put in front of the items of a list.
+const BULLET: &str = "\u{2022} ";
+
+/// The kind of text block being read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block {
+    Paragraph,
+    List,
+}
+
+/// Parse the Markdown text, and return the slides as a result, like
+/// [`super::parse_text`] does with the slidy language.
+///
+/// ```
+/// use slidy::parser::parse_markdown;
+/// use std::path::Path;
+///
+/// let text = "# Hello\n- one\n- two\n---\nThe end";
+/// let slides = parse_markdown(text, Path::new("")).unwrap();
+/// assert_eq!(slides.slides.len(), 2);
+/// ```
+pub fn parse_markdown(
+    inp: &str,
+    base_folder: &Path,
+) -> Result<Slideshow, Box<dyn Error + 'static>> {
+    parse_markdown_with_options(inp, base_folder, &ParseOptions::default())
+}
+
+/// Parse the Markdown text like [`parse_markdown`] does, tuned with the
+/// given options: with `strict`, a missing inline image is an error.
+pub fn parse_markdown_with_options(
+    inp: &str,
+    base_folder: &Path,
+    options: &ParseOptions,
+) -> Result<Slideshow, Box<dyn Error + 'static>> {
+    let strict = options.strict;
+    let mut slideshow = Slideshow {
+        base_folder: base_folder.to_path_buf(),
+        ..Slideshow::default()
+    };
+    let mut slide = Slide::default();
+    // The paragraph or the list being read, if any.
+    let mut block: Option<(Block, SectionText)> = None;
+    let mut lines = inp.lines();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.len() >= 3 && trimmed.chars().all(|c| c == '-') {
+            end_block(&mut slide, &mut block);
+            // Many breaks in a row do not make empty slides.
+            if !slide.sections.is_empty() {
+                let done = std::mem::replace(&mut slide, Slide::default());
+                slideshow.slides.push(done);
+            }
+            continue;
+        }
+        if let Some(lang) = trimmed.strip_prefix("```") {
+            end_block(&mut slide, &mut block);
+            let lang = lang.trim();
+            let mut code = SectionText {
+                is_code: true,
+                lang: (!lang.is_empty()).then(|| lang.to_string()),
+                ..SectionText::default()
+            };
+            for line in lines.by_ref() {
+                if line.trim_start().starts_with("```") {
+                    break;
+                }
+                code.push_line(line);
+            }
+            push_text(&mut slide, code, None);
+            continue;
+        }
+        if trimmed.is_empty() {
+            end_block(&mut slide, &mut block);
+        } else if let Some((level, title)) = heading(trimmed) {
+            end_block(&mut slide, &mut block);
+            let title = resolve_inline_images(title, base_folder, strict)?;
+            push_text(
+                &mut slide,
+                SectionText::from_lines([title]),
+                heading_size(level),
+            );
+        } else if let Some(path) = lone_image(trimmed) {
+            end_block(&mut slide, &mut block);
+            slide.sections.push(figure(path, base_folder)?);
+        } else {
+            let (kind, line) = list_item(trimmed).map_or_else(
+                || (Block::Paragraph, trimmed.to_string()),
+                |item| (Block::List, format!("{BULLET}{item}")),
+            );
+            if block.as_ref().is_some_and(|(k, _)| *k != kind) {
+                end_block(&mut slide, &mut block);
+            }
+            let line = resolve_inline_images(&line, base_folder, strict)?;
+            block
+                .get_or_insert_with(|| (kind, SectionText::default()))
+                .1
+                .push_line(&line);
+        }
+    }
+    end_block(&mut slide, &mut block);
+    if !slide.sections.is_empty() {
+        slideshow.slides.push(slide);
+    }
+    Ok(slideshow)
+}
+
+/// Parse the Markdown file, like [`super::parse_file`] does with the slidy
+/// language.
+pub fn parse_markdown_file(
+    path: &Path,
+) -> Result<Slideshow, Box<dyn Error + 'static>> {
+    parse_markdown_file_with_options(path, &ParseOptions::default())
+}
+
+/// Parse the Markdown file like [`parse_markdown_file`] does, tuned with the
+/// given options.
+pub fn parse_markdown_file_with_options(
+    path: &Path,
+    options: &ParseOptions,
+) -> Result<Slideshow, Box<dyn Error + 'static>> {
+    let (content, base_folder, temp_folder) = super::read_file(path)?;
+    let slideshow =
+        parse_markdown_with_options(&content, &base_folder, options)?;
+    Ok(Slideshow {
+        temp_folder,
+        ..slideshow
+    })
+}
+
+/// Put the paragraph or the list being read in the slide.
+fn end_block(slide: &mut Slide, block: &mut Option<(Block, SectionText)>) {
+    if let Some((_, text)) = block.take() {
+        push_text(slide, text, None);
+    }
+}
+
+/// Add a text section to the slide.
+fn push_text(slide: &mut Slide, text: SectionText, size: Option<Size>) {
+    slide.sections.push(Section {
+        size,
+        sec_main: Some(SectionMain::Text(text)),
+        ..Section::default()
+    });
+}
+
+/// Read a heading like `## Title`: its level, and its text.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let title = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then(|| (level, title.trim()))
+}
+
+/// How big the text of a heading is: the first two levels are bigger than
+/// the text, the others are like it.
+fn heading_size(level: usize) -> Option<Size> {
+    let scale = match level {
+        1 => 2.0,
+        2 => 1.5,
+        _ => return None,
+    };
+    Some(Size {
+        w: DEFAULT_FONT_SIZE.w * scale,
+        h: DEFAULT_FONT_SIZE.h * scale,
+    })
+}
+
+/// Read an item of a list, like `- item`.
+fn list_item(line: &str) -> Option<&str> {
+    ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| line.strip_prefix(bullet))
+        .map(str::trim_start)
+}
+
+/// The path of the image, if the line is just an image, like `![](a.png)`.
+fn lone_image(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("![")?.strip_suffix(')')?;
+    let (alt, path) = rest.split_once("](")?;
+    (!alt.contains(']') && !path.contains(')')).then_some(path)
+}
+
+/// A figure section, with the image at `path`.
+fn figure(
+    path: &str,
+    base_folder: &Path,
+) -> Result<Section, Box<dyn Error + 'static>> {
+    // The path is kept as it is written, relative to the slides.
+    if let Some(data) = decode_data_uri(path) {
+        // The figure is embedded in the slides.
+        data?;
+    } else {
+        base_folder
+            .join(path)
+            .canonicalize()
+            .map_err(|e| format!("Unable to find the image {path}: {e}"))?;
+    }
+    Ok(Section {
+        sec_main: Some(SectionMain::Figure(SectionFigure {
+            path: path.to_string(),
+            ..SectionFigure::default()
+        })),
+        ..Section::default()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The text of the sections of the slide, or the path of the figures.
+    fn contents(slide: &Slide) -> Vec<String> {
+        slide
+            .sections
+            .iter()
+            .map(|s| match &s.sec_main {
+                Some(SectionMain::Text(text)) => text.text.clone(),
+                Some(SectionMain::Figure(fig)) => fig.path.clone(),
+                None => String::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_markdown() {
+        let text = "# Title\nSome text\non two lines\n\n- one\n* two\n\
+                    ---\n## Second\n![a star](star.jpg)\n```rust\nfn main() {}\n```\n\
+                    ---\n---\n#### Small\nA ![star](star.jpg) inline\n";
+        let base = Path::new("./resources");
+        let slides = parse_markdown(text, base).unwrap();
+        assert_eq!(slides.slides.len(), 3);
+
+        let first = &slides.slides[0];
+        assert_eq!(
+            contents(first),
+            [
+                "Title\n",
+                "Some text\non two lines\n",
+                "\u{2022} one\n\u{2022} two\n"
+            ]
+        );
+        assert_eq!(first.sections[0].size, heading_size(1));
+        assert_eq!(first.sections[1].size, None);
+
+        let second = &slides.slides[1];
+        assert_eq!(
+            contents(second),
+            ["Second\n", "star.jpg", "fn main() {}\n"]
+        );
+        let Some(SectionMain::Text(code)) = &second.sections[2].sec_main else {
+            panic!("The last section must be a text.");
+        };
+        assert!(code.is_code);
+        assert_eq!(code.lang.as_deref(), Some("rust"));
+
+        // The empty slide is skipped, and the small headings are plain text.
+        let third = &slides.slides[2];
+        assert_eq!(third.sections[0].size, None);
+        assert_eq!(contents(third)[1], "A ![star](star.jpg) inline\n");
+    }
+
+    #[test]
+    fn test_markdown_missing_image() {
+        let text = "# Title\n![](not_there.png)";
+        assert!(parse_markdown(text, Path::new("./resources")).is_err());
+
+        // A missing inline image is kept as text, unless strict.
+        let text = "A ![star](not_there.png) inline";
+        let base = Path::new("./resources");
+        assert!(parse_markdown(text, base).is_ok());
+        let strict = ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        assert!(parse_markdown_with_options(text, base, &strict).is_err());
+    }
+
+    #[test]
+    fn test_markdown_lines() {
+        assert_eq!(heading("## A title"), Some((2, "A title")));
+        assert_eq!(heading("#hashtag"), None);
+        assert_eq!(heading("####### too deep"), None);
+        assert_eq!(list_item("-  item"), Some("item"));
+        assert_eq!(list_item("-5 degrees"), None);
+        assert_eq!(lone_image("![alt](a.png)"), Some("a.png"));
+        assert_eq!(lone_image("![a](a.png) and ![b](b.png)"), None);
+    }
+}
@@ -1,14 +1,52 @@
+#[cfg(feature = "bundle")]
+mod bundle;
+mod incremental;
 pub(crate) mod lexer;
+mod markdown;
+mod serialized;
 pub(crate) mod tokenizer;
+mod tokens;
 mod utils;
 
 use std::error::Error;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use crate::slideshow::Slideshow;
+pub use incremental::IncrementalParser;
+pub use markdown::{
+    parse_markdown, parse_markdown_file, parse_markdown_file_with_options,
+    parse_markdown_with_options,
+};
+pub use tokens::{tokenize, OwnedToken, TokenKind, TokenSpan, TokenValue};
+
+use crate::slideshow::{Slideshow, TempFolder};
+
+/// How the input is parsed.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParseOptions {
+    /// Using a variable that was not defined with `:def` is an error. By
+    /// default, the `${NAME}` is left in the text as it is.
+    pub strict_vars: bool,
+    /// The defaults set with `:ge` in the imported files are used where the
+    /// importing file does not set its own. By default, only the slides are
+    /// imported.
+    pub merge_imported_defaults: bool,
+    /// What is only warned about is an error instead, like a general
+    /// setting set again with another value, or an undefined variable (see
+    /// `strict_vars`). By default, the last value wins and the `${NAME}` is
+    /// left in the text.
+    pub strict: bool,
+}
+
+/// Read a color as it is written after `:fc` or `:bc` in a single word, like
+/// `silver` or `#c0c0c0ff`.
+pub fn parse_color(
+    color: &str,
+) -> Result<crate::slideshow::Color, Box<dyn Error + 'static>> {
+    utils::match_string_color(color)
+}
 
 /// Parse the input text, and return the slides as a result.
 ///
@@ -17,23 +55,114 @@ use crate::slideshow::Slideshow;
 pub fn parse_text(
     inp: &str,
     base_folder: &Path,
+) -> Result<Slideshow, Box<dyn Error + 'static>> {
+    parse_text_with_options(inp, base_folder, &ParseOptions::default())
+}
+
+/// Parse the input text like [`parse_text`] does, tuned with the given
+/// options.
+pub fn parse_text_with_options(
+    inp: &str,
+    base_folder: &Path,
+    options: &ParseOptions,
 ) -> Result<Slideshow, Box<dyn Error + 'static>> {
     // Build the tokens.
     let tokens = tokenizer::tokenizer(inp);
     // Feed the lexer with the tokens.
     let mut tp = lexer::Lexer::new(base_folder);
+    tp.options = *options;
     tp.read_tokens(&tokens)?;
     // Take the slideshow out of the lexer.
     let slideshow = tp.take();
     Ok(slideshow)
 }
 
-/// Parse the input file, and return the slides as a result.
+/// Parse the input text like [`parse_text`] does, and trace it to `out`.
 ///
-/// These slides can be drawn using the appropriate [backend](crate::backends).
-pub fn parse_file(
-    path: &std::path::Path,
+/// Every token is written with its position in the input, and the changes of
+/// the parser's state: this is meant to debug a slide file that does not
+/// behave.
+///
+/// Mind that the imported files are parsed, but not traced.
+pub fn trace_text(
+    inp: &str,
+    base_folder: &Path,
+    out: &mut dyn Write,
 ) -> Result<Slideshow, Box<dyn Error + 'static>> {
+    trace_text_with_options(inp, base_folder, &ParseOptions::default(), out)
+}
+
+/// Trace the input text like [`trace_text`] does, parsing it with the given
+/// options, like [`parse_text_with_options`] does.
+pub fn trace_text_with_options(
+    inp: &str,
+    base_folder: &Path,
+    options: &ParseOptions,
+    out: &mut dyn Write,
+) -> Result<Slideshow, Box<dyn Error + 'static>> {
+    let tokens = tokenizer::tokenizer(inp);
+    let mut tp = lexer::Lexer::new(base_folder);
+    tp.options = *options;
+    let mut write_result = Ok(());
+    let res = tp.read_tokens_traced(&tokens, |token, args, before, after| {
+        if write_result.is_ok() {
+            write_result = write_token(out, token, args, before, after);
+        }
+    });
+    // Errors while writing are less important than the parsing ones.
+    res?;
+    write_result?;
+    Ok(tp.take())
+}
+
+/// Write the tokens found in the input text to `out`, as a JSON array.
+///
+/// Each token has its `kind`, its `span` in the input (the `line`, counted
+/// from 0, and the columns it begins and ends at), and its `value`, if any:
+/// this is meant for the tools, like the editors' syntax highlighting.
+pub fn dump_tokens_text(
+    inp: &str,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error + 'static>> {
+    serde_json::to_writer_pretty(&mut *out, &tokenize(inp))?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Write a single line of the trace.
+fn write_token(
+    out: &mut dyn Write,
+    token: &tokenizer::Token,
+    args: &[tokenizer::Token],
+    before: lexer::CurrentState,
+    after: lexer::CurrentState,
+) -> std::io::Result<()> {
+    write!(out, "{} {:?}", token.span(), token.symbol)?;
+    for arg in args {
+        write!(out, " {:?}", arg.symbol)?;
+    }
+    if before != after {
+        write!(out, " [state: {before:?} -> {after:?}]")?;
+    }
+    writeln!(out)
+}
+
+/// Read the file at `path`, and return its content with the folder the
+/// relative paths are resolved against, and the temporary folder that has
+/// to be kept meanwhile, if any.
+fn read_file(
+    path: &Path,
+) -> Result<(String, PathBuf, TempFolder), Box<dyn Error + 'static>> {
+    if path.extension().is_some_and(|ext| ext == "zip") {
+        #[cfg(feature = "bundle")]
+        {
+            let (deck, folder) = bundle::extract(path)?;
+            let (content, base_folder, _) = read_file(&deck)?;
+            return Ok((content, base_folder, folder));
+        }
+        #[cfg(not(feature = "bundle"))]
+        return Err("Zip bundles need the `bundle` feature.".into());
+    }
     let file = File::open(path)?;
     if !path.is_file() {
         return Err("`{}` is not a file, please provide one.".into());
@@ -42,11 +171,111 @@ pub fn parse_file(
     let base_folder = path
         .parent()
         .ok_or("Unable to find the parent: is this root already?")?;
-    // Read the whole file to a String.
-    let mut file_to_string = String::new();
-    reader.read_to_string(&mut file_to_string)?;
-    let slideshow = parse_text(file_to_string.as_str(), base_folder)?;
-    Ok(slideshow)
+    // Read the whole file, and then check that it is text.
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let file_to_string = decode(bytes)
+        .map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+    Ok((
+        file_to_string,
+        base_folder.to_path_buf(),
+        TempFolder::default(),
+    ))
+}
+
+/// Read the slides of the file, if it is written in one of the data formats,
+/// like JSON, instead of the slidy language.
+fn parse_serialized(
+    path: &Path,
+) -> Option<Result<Slideshow, Box<dyn Error + 'static>>> {
+    let format = serialized::Format::of(path)?;
+    Some(read_file(path).and_then(|(content, base_folder, _)| {
+        let mut slideshow = serialized::deserialize(&content, format)?;
+        slideshow.base_folder = base_folder;
+        Ok(slideshow)
+    }))
+}
+
+/// Turn the content of a file into a String, dropping the byte order mark
+/// that some editors put at its start.
+fn decode(mut bytes: Vec<u8>) -> Result<String, String> {
+    const BOM: &[u8] = b"\xEF\xBB\xBF";
+    let skipped = if bytes.starts_with(BOM) {
+        bytes.drain(..BOM.len());
+        BOM.len()
+    } else {
+        0
+    };
+    String::from_utf8(bytes).map_err(|e| {
+        let at = e.utf8_error().valid_up_to() + skipped;
+        format!("file is not valid UTF-8 at byte {at}")
+    })
+}
+
+/// Parse the input file, and return the slides as a result.
+///
+/// These slides can be drawn using the appropriate [backend](crate::backends).
+/// If the file is a `.zip` bundle (see the `bundle` feature), the slides are
+/// read from the `deck.txt` file inside the archive. The `.json`, `.yaml`
+/// (or `.yml`) and `.toml` files are read as a serialized [`Slideshow`]
+/// instead: the last two need the `yaml` and `toml` features.
+pub fn parse_file(
+    path: &std::path::Path,
+) -> Result<Slideshow, Box<dyn Error + 'static>> {
+    parse_file_with_options(path, &ParseOptions::default())
+}
+
+/// Parse the input file like [`parse_file`] does, tuned with the given
+/// options.
+pub fn parse_file_with_options(
+    path: &Path,
+    options: &ParseOptions,
+) -> Result<Slideshow, Box<dyn Error + 'static>> {
+    if let Some(slideshow) = parse_serialized(path) {
+        return slideshow;
+    }
+    let (content, base_folder, temp_folder) = read_file(path)?;
+    let slideshow =
+        parse_text_with_options(content.as_str(), &base_folder, options)?;
+    Ok(Slideshow {
+        temp_folder,
+        ..slideshow
+    })
+}
+
+/// Parse the input file like [`parse_file`] does, tracing it like
+/// [`trace_text`] does.
+pub fn trace_file(
+    path: &Path,
+    out: &mut dyn Write,
+) -> Result<Slideshow, Box<dyn Error + 'static>> {
+    trace_file_with_options(path, &ParseOptions::default(), out)
+}
+
+/// Trace the input file like [`trace_file`] does, parsing it with the given
+/// options, like [`parse_file_with_options`] does.
+pub fn trace_file_with_options(
+    path: &Path,
+    options: &ParseOptions,
+    out: &mut dyn Write,
+) -> Result<Slideshow, Box<dyn Error + 'static>> {
+    let (content, base_folder, temp_folder) = read_file(path)?;
+    let slideshow =
+        trace_text_with_options(content.as_str(), &base_folder, options, out)?;
+    Ok(Slideshow {
+        temp_folder,
+        ..slideshow
+    })
+}
+
+/// Write the tokens found in the input file to `out`, like
+/// [`dump_tokens_text`] does.
+pub fn dump_tokens_file(
+    path: &Path,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error + 'static>> {
+    let (content, ..) = read_file(path)?;
+    dump_tokens_text(content.as_str(), out)
 }
 
 #[cfg(test)]
@@ -57,7 +286,7 @@ mod test {
     use std::io::BufReader;
     use std::path::PathBuf;
 
-    use crate::slideshow::SectionMain;
+    use crate::slideshow::{Color, SectionMain, TextRun};
 
     /// Load and a file and check its existence.
     macro_rules! load_exists {
@@ -81,6 +310,144 @@ mod test {
         assert_eq!(slideshow.slides.len(), 3);
     }
 
+    #[test]
+    fn test_trace_text() {
+        let example = "# A comment\n:sl\n:tb :ps 0.1 0.2\nSome text\n";
+        let mut out = vec![];
+        let slideshow = trace_text(example, Path::new(""), &mut out).unwrap();
+        assert_eq!(slideshow.slides.len(), 1);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "1:0-11 Comment(\"# A comment\")
+2:0-3 Slide [state: None -> Slide]
+3:0-3 TextBuffer [state: Slide -> Text]
+3:4-7 Position Number(0.1) Number(0.2)
+4:0-9 TextLine(\"Some text\")
+"
+        );
+    }
+
+    #[test]
+    fn test_dump_tokens() {
+        let mut out = vec![];
+        dump_tokens_text(":sl :ps 0.5 _\nsome text", &mut out).unwrap();
+        let tokens: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let expected = serde_json::json!([
+            {"kind": "Slide", "span": {"line": 0, "beg": 0, "end": 3}},
+            {"kind": "Position", "span": {"line": 0, "beg": 4, "end": 7}},
+            {"kind": "Number", "value": 0.5, "span": {"line": 0, "beg": 8, "end": 11}},
+            {"kind": "String", "value": "_", "span": {"line": 0, "beg": 12, "end": 13}},
+            {"kind": "TextLine", "value": "some text", "span": {"line": 1, "beg": 0, "end": 9}},
+        ]);
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_trace_text_with_options() {
+        // The trace fails where the parse does.
+        let example = ":ge :fc red\n:ge :fc blue\n:sl";
+        let strict = ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        let mut out = vec![];
+        let p = Path::new("");
+        assert!(trace_text(example, p, &mut out).is_ok());
+        assert!(parse_text_with_options(example, p, &strict).is_err());
+        assert!(trace_text_with_options(example, p, &strict, &mut out).is_err());
+    }
+
+    #[test]
+    fn test_trace_text_ko() {
+        let mut out = vec![];
+        assert!(trace_text(":sl :ps 0.1 0.2", Path::new(""), &mut out).is_err());
+        // The tokens before the error are traced anyway.
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "1:0-3 Slide [state: None -> Slide]\n"
+        );
+    }
+
+    /// Get the text of the first section of each slide.
+    fn first_texts(slideshow: &Slideshow) -> Vec<&str> {
+        slideshow
+            .slides
+            .iter()
+            .map(|slide| match &slide.sections[0].sec_main {
+                Some(SectionMain::Text(text)) => text.text.as_str(),
+                _ => panic!("The first section must be a text."),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_import_before_any_slide() {
+        let p = load_exists!("resources");
+        let example = ":im to_import.txt\n:sl\n:tb\nAfter\n";
+        let slideshow = parse_text(example, &p).unwrap();
+        assert_eq!(
+            first_texts(&slideshow),
+            vec!["Imported from ./to_import.txt\n\n", "After\n"]
+        );
+    }
+
+    #[test]
+    fn test_import_mid_slide() {
+        let p = load_exists!("resources");
+        // The slide being built is done before the imported ones.
+        let example = ":sl\n:tb\nBefore\n:im to_import.txt\n:sl\n:tb\nAfter\n";
+        let slideshow = parse_text(example, &p).unwrap();
+        assert_eq!(
+            first_texts(&slideshow),
+            vec!["Before\n", "Imported from ./to_import.txt\n\n", "After\n"]
+        );
+        // A text line needs a new slide after the import.
+        let example = ":sl\n:tb\nBefore\n:im to_import.txt\nAfter\n";
+        assert!(parse_text(example, &p).is_err());
+    }
+
+    #[test]
+    fn test_import_defaults() {
+        let p = load_exists!("resources");
+        let example = ":ge :fc red\n:im to_import_with_defaults.txt\n";
+        let red = (0xff, 0x00, 0x00, 0xff).into();
+        let navy = (0x00, 0x00, 0x80, 0xff).into();
+
+        // By default, only the slides are imported.
+        let slideshow = parse_text(example, &p).unwrap();
+        assert_eq!(slideshow.slides.len(), 1);
+        assert_eq!(slideshow.bg_col, None);
+        assert_eq!(slideshow.font_col, Some(red));
+
+        // The importing file's defaults win over the imported ones.
+        let options = ParseOptions {
+            merge_imported_defaults: true,
+            ..ParseOptions::default()
+        };
+        let slideshow = parse_text_with_options(example, &p, &options).unwrap();
+        assert_eq!(slideshow.bg_col, Some(navy));
+        assert_eq!(slideshow.font_col, Some(red));
+
+        // Even when they are set after the import, and strictly: overriding
+        // an imported default is not setting it again.
+        let options = ParseOptions {
+            strict: true,
+            ..options
+        };
+        let example = ":im to_import_with_defaults.txt
+:ge :bc red
+";
+        let slideshow = parse_text_with_options(example, &p, &options).unwrap();
+        assert_eq!(slideshow.bg_col, Some(red));
+        assert_eq!(slideshow.font_col, Some((0xff, 0xff, 0xff, 0xff).into()));
+        // Setting it twice in the file still is.
+        let example = ":im to_import_with_defaults.txt
+:ge :bc red
+:ge :bc blue
+";
+        assert!(parse_text_with_options(example, &p, &options).is_err());
+    }
+
     #[test]
     fn test_import_ko_file_not_there() {
         let example = ":im ./non_existing_file.txt";
@@ -101,6 +468,34 @@ mod test {
             .unwrap();
 
         assert_eq!(slideshow.slides.len(), 1);
+        // The figures of the file are found from its folder.
+        let slideshow = Slideshow {
+            base_folder: d.parent().unwrap().to_path_buf(),
+            ..slideshow
+        };
+        assert_eq!(parse_file(&d).unwrap(), slideshow);
+    }
+
+    #[test]
+    #[cfg(feature = "yaml")]
+    /// Verify the YAML file is read like the JSON one.
+    fn test_load_yaml() {
+        let json =
+            load_exists!("examples/slidy_serde/resources/input_file.json");
+        let yaml =
+            load_exists!("examples/slidy_serde/resources/input_file.yaml");
+        assert_eq!(parse_file(&yaml).unwrap(), parse_file(&json).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    /// Verify the TOML file is read like the JSON one.
+    fn test_load_toml() {
+        let json =
+            load_exists!("examples/slidy_serde/resources/input_file.json");
+        let toml =
+            load_exists!("examples/slidy_serde/resources/input_file.toml");
+        assert_eq!(parse_file(&toml).unwrap(), parse_file(&json).unwrap());
     }
 
     #[test]
@@ -162,4 +557,92 @@ center the text
             &"    4 whitespaces before\n"
         );
     }
+
+    #[test]
+    fn test_inline_image() {
+        let example = r#"
+:sl :tb
+A star ![star](star.jpg) in the text
+"#;
+
+        let p = load_exists!("resources");
+        let slides = parse_text(example, &p)
+            .expect("should be able to create the slides.");
+
+        let Some(SectionMain::Text(sec_text)) =
+            &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("The section must be a text.");
+        };
+        // The path is kept as it is written, like the figures' ones.
+        assert_eq!(
+            TextRun::split(sec_text.text.trim_end()),
+            vec![
+                TextRun::Text("A star "),
+                TextRun::Image {
+                    alt: "star",
+                    path: "star.jpg"
+                },
+                TextRun::Text(" in the text"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_slides() {
+        for text in ["", "\n\n\n", "# just a comment", "  \n# a\n\t\n"] {
+            let slides = parse_text(text, Path::new("")).unwrap();
+            assert_eq!(slides, Slideshow::default(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(b"\xEF\xBB\xBF:ge".to_vec()).unwrap(), ":ge");
+        assert_eq!(
+            decode(b":ge \xEF\xBB\xBF".to_vec()).unwrap(),
+            ":ge \u{feff}"
+        );
+        assert_eq!(
+            decode(b"\xEF\xBB\xBF:sl\xFF".to_vec()).unwrap_err(),
+            "file is not valid UTF-8 at byte 6"
+        );
+    }
+
+    #[test]
+    fn test_parse_file_bom() {
+        let path = std::env::temp_dir().join("slidy_test_bom.txt");
+        std::fs::write(&path, b"\xEF\xBB\xBF:ge :bc red\n:sl\n:tb\nHi\n")
+            .unwrap();
+        let slides = parse_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        let slides = slides.unwrap();
+        assert_eq!(slides.bg_col, Some(Color::from((255, 0, 0, 255))));
+        assert_eq!(slides.slides.len(), 1);
+    }
+
+    #[test]
+    fn test_inline_image_not_there() {
+        let example = ":sl :tb\nA ![missing](not_there.jpg)\n";
+        let p = load_exists!("resources");
+        // The image is kept as text, unless the parsing is strict.
+        let slides = parse_text(example, &p).unwrap();
+        let Some(SectionMain::Text(sec_text)) =
+            &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("The section must be a text.");
+        };
+        assert_eq!(
+            TextRun::split(sec_text.text.trim_end()),
+            vec![
+                TextRun::Text("A "),
+                TextRun::Text("![missing](not_there.jpg)")
+            ]
+        );
+        let strict = ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        assert!(parse_text_with_options(example, &p, &strict).is_err());
+    }
 }
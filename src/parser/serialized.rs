@@ -0,0 +1,78 @@
+//! Read the slides written in a data format, like JSON, YAML or TOML: since
+//! the [`Slideshow`] can be deserialized, the slides can be written in any
+//! format that `serde` reads.
+//!
+//! YAML and TOML are behind the `yaml` and `toml` features. The paths of the
+//! figures and of the fonts are relative to the file, unless they are
+//! absolute. Mind that nothing is imported: what is in the file is what is
+//! shown.
+
+use std::error::Error;
+use std::path::Path;
+
+use crate::slideshow::Slideshow;
+
+/// The data formats the slides can be read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    /// The format of the file at `path`, from its extension, if it is one of
+    /// the data formats.
+    pub(super) fn of(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// Read the slides from `content`, written in `format`.
+pub(super) fn deserialize(
+    content: &str,
+    format: Format,
+) -> Result<Slideshow, Box<dyn Error + 'static>> {
+    match format {
+        Format::Json => Ok(serde_json::from_str(content)?),
+        #[cfg(feature = "yaml")]
+        Format::Yaml => Ok(serde_norway::from_str(content)?),
+        #[cfg(not(feature = "yaml"))]
+        Format::Yaml => Err("YAML slides need the `yaml` feature.".into()),
+        #[cfg(feature = "toml")]
+        Format::Toml => Ok(toml::from_str(content)?),
+        #[cfg(not(feature = "toml"))]
+        Format::Toml => Err("TOML slides need the `toml` feature.".into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format_of() {
+        assert_eq!(Format::of(Path::new("a/talk.json")), Some(Format::Json));
+        assert_eq!(Format::of(Path::new("talk.YML")), Some(Format::Yaml));
+        assert_eq!(Format::of(Path::new("talk.yaml")), Some(Format::Yaml));
+        assert_eq!(Format::of(Path::new("talk.toml")), Some(Format::Toml));
+        assert_eq!(Format::of(Path::new("talk.txt")), None);
+        assert_eq!(Format::of(Path::new("talk")), None);
+    }
+
+    #[test]
+    fn test_deserialize_json() {
+        let slides = deserialize(
+            r#"{"slides": [{"sections": []}], "fonts": {}}"#,
+            Format::Json,
+        );
+        assert_eq!(slides.unwrap().slides.len(), 1);
+        assert!(deserialize("{", Format::Json).is_err());
+    }
+}
@@ -14,35 +14,76 @@ Check the module's tests for more details.
 */
 use tracing::error;
 
-#[derive(Debug, PartialEq)]
-pub(super) struct TokenSpan {
-    line: usize,
-    beg: usize,
-    end: usize,
+/// Where a token is in the input.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TokenSpan {
+    /// The line, counted from 0.
+    pub line: usize,
+    /// The column the token begins at, in bytes.
+    pub beg: usize,
+    /// The column right after the token, in bytes.
+    pub end: usize,
 }
 
 impl TokenSpan {
     pub(super) const fn new(line: usize, beg: usize, end: usize) -> Self {
         Self { line, beg, end }
     }
+
+    /// Where the span starts: its line, and its first column.
+    pub(super) const fn start(&self) -> (usize, usize) {
+        (self.line, self.beg)
+    }
+}
+
+impl std::fmt::Display for TokenSpan {
+    /// Show the span as `line:beg-end`, where lines are counted from 1, like
+    /// in the editors.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}-{}", self.line + 1, self.beg, self.end)
+    }
 }
 
 /// The list of symbols the parser will recognize.
 /// Note that this is not great. Instead of parsing like
 /// letters, symbols, numbers and stuffs, I just try to
 /// recognize the symbol as a whole.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, serde::Serialize)]
+#[serde(tag = "kind", content = "value")]
 pub(super) enum Structure<'a> {
     Generic,
     Fontcolor,
     BackGroundColor,
     Slide,
+    SlideCopy,
+    Title,
     Size,
     TextBuffer,
     Position,
     Figure,
     Rotation,
     Import,
+    BoxWidth,
+    LineHeight,
+    VCenter,
+    Columns,
+    Code,
+    TabWidth,
+    Only,
+    Define,
+    Meta,
+    Order,
+    Group,
+    Grid,
+    Tag,
+    Opacity,
+    Anchor,
+    Caption,
+    FitText,
+    Trim,
+    FrameTime,
+    SectionBgColor,
+    NoCount,
     TextLine(&'a str),
     Comment(&'a str),
     // Generic stuffs, like string, numbers (everything is a f32 internally)
@@ -50,10 +91,11 @@ pub(super) enum Structure<'a> {
     Number(f32),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, serde::Serialize)]
 /// A token is built without knowing about the structure of the thing to be
 /// parsed.
 pub(super) struct Token<'a> {
+    #[serde(flatten)]
     pub symbol: Structure<'a>,
     span: TokenSpan,
 }
@@ -62,12 +104,20 @@ impl<'a> Token<'a> {
     pub(super) const fn new(symbol: Structure<'a>, span: TokenSpan) -> Token {
         Token { symbol, span }
     }
+
+    /// Where the token is in the input.
+    pub(super) const fn span(&self) -> &TokenSpan {
+        &self.span
+    }
 }
 
 fn build_token(val: &str, linenum: usize, beg: usize, end: usize) -> Token {
     use Structure::{
-        BackGroundColor, Figure, Fontcolor, Generic, Import, Number, Position,
-        Rotation, Size, Slide, String, TextBuffer,
+        Anchor, BackGroundColor, BoxWidth, Caption, Code, Columns, Define,
+        Figure, FitText, Fontcolor, FrameTime, Generic, Grid, Group, Import,
+        LineHeight, Meta, NoCount, Number, Only, Opacity, Order, Position,
+        Rotation, SectionBgColor, Size, Slide, SlideCopy, String, TabWidth,
+        Tag, TextBuffer, Title, Trim, VCenter,
     };
 
     let structure = match val {
@@ -75,12 +125,35 @@ fn build_token(val: &str, linenum: usize, beg: usize, end: usize) -> Token {
         ":fc" => Fontcolor,
         ":bc" => BackGroundColor,
         ":sl" => Slide,
+        ":sl+" => SlideCopy,
+        ":title" => Title,
         ":sz" => Size,
         ":tb" => TextBuffer,
         ":ps" => Position,
         ":fg" => Figure,
         ":rt" => Rotation,
         ":im" => Import,
+        ":bw" => BoxWidth,
+        ":lh" => LineHeight,
+        ":vcenter" => VCenter,
+        ":cols" => Columns,
+        ":fit-text" => FitText,
+        ":code" => Code,
+        ":tw" => TabWidth,
+        ":only" => Only,
+        ":def" => Define,
+        ":meta" => Meta,
+        ":order" => Order,
+        ":group" => Group,
+        ":grid" => Grid,
+        ":tag" => Tag,
+        ":op" => Opacity,
+        ":anchor" => Anchor,
+        ":cap" => Caption,
+        ":trim" => Trim,
+        ":frame" => FrameTime,
+        ":sbc" => SectionBgColor,
+        ":nocount" => NoCount,
         _ => val.parse::<f32>().map_or(String(val), Number),
     };
 
@@ -91,66 +164,62 @@ fn build_token(val: &str, linenum: usize, beg: usize, end: usize) -> Token {
 }
 
 /// Parse the line, knowing that we surely don't have `TextLine` and Comments here.
+/// The spans are byte offsets in the line, so that they can slice it.
 fn parse_single_tokens<'a>(
     tokens: &mut Vec<Token<'a>>,
     line: &'a str,
     linenum: usize,
 ) {
-    if line.len() >= isize::MAX as usize {
-        error!(
-            "We don't support lines that are longer than {}: found {}",
-            isize::MAX,
-            line.len()
-        );
-        return;
+    let first_token = tokens.len();
+    let mut push_word = |beg: usize, end: usize| {
+        if let Some(elem) = line.get(beg..end) {
+            tokens.push(build_token(elem, linenum, beg, end));
+        } else {
+            error!("The token at {linenum}:{beg}-{end} is not in the line.");
+        }
+    };
+    // Where the word being read starts, if we are in one.
+    let mut word_start = None;
+    for (pos, ch) in line.char_indices() {
+        if !ch.is_whitespace() {
+            word_start.get_or_insert(pos);
+        } else if let Some(beg) = word_start.take() {
+            push_word(beg, pos);
+        }
+    }
+    if let Some(beg) = word_start {
+        // The line ends with a word, so it has to be considered.
+        push_word(beg, line.len());
     }
-    let mut last_whitespace = -1_isize;
-    let mut whitespace_mode = false;
-    for (pos, ch) in line.chars().enumerate() {
-        assert!((last_whitespace + 1) >= 0);
-        #[allow(clippy::cast_sign_loss)]
-        let last_whitespace_usize = (last_whitespace + 1) as usize;
+    keep_definitions_verbatim(&mut tokens[first_token..], line);
+}
 
-        if ch.is_whitespace() {
-            if pos == 0 {
-                whitespace_mode = true;
-            }
-            if !whitespace_mode {
-                // This whitespace comes after "something".
-                let elem = line
-                    .get(last_whitespace_usize..pos)
-                    .expect("Pos is past the end of the slice.");
-                if !elem.is_empty() {
-                    let tk =
-                        build_token(elem, linenum, last_whitespace_usize, pos);
-                    tokens.push(tk);
+/// The value of a definition is kept as it is written, even when it looks
+/// like a number: think of a version like `1.10`. So are the words of a
+/// metadata's value, like `:meta title "Rust 2.0"`, and of a caption, like
+/// `:cap Figure 1`. The raw words are sliced out of `line` with the spans of
+/// the tokens, which are byte offsets.
+fn keep_definitions_verbatim<'a>(tokens: &mut [Token<'a>], line: &'a str) {
+    for idx in 0..tokens.len() {
+        let values = match tokens[idx].symbol {
+            Structure::Define => idx + 2..idx + 3,
+            Structure::Meta => idx + 2..tokens.len(),
+            Structure::Caption => idx + 1..tokens.len(),
+            _ => continue,
+        };
+        let Some(values) = tokens.get_mut(values) else {
+            continue;
+        };
+        for value in values {
+            match (&value.symbol, line.get(value.span.beg..value.span.end)) {
+                (Structure::Number(_), Some(raw)) => {
+                    value.symbol = Structure::String(raw);
                 }
-
-                whitespace_mode = true;
+                (Structure::String(_), _) => {}
+                _ => break,
             }
-
-            assert!(pos < isize::MAX as usize);
-            #[allow(clippy::cast_possible_wrap)]
-            let pos = pos as isize;
-
-            last_whitespace = pos;
-        } else {
-            // Not whitespace anymore: advance until the end of the line or a whitespace.
-            whitespace_mode = false;
         }
     }
-    assert!((last_whitespace + 1) >= 0);
-    #[allow(clippy::cast_sign_loss)]
-    let last_whitespace_usize = (last_whitespace + 1) as usize;
-
-    if !whitespace_mode {
-        // The last char was not a whitespace, so it has to be considered.
-        let elem = line
-            .get(last_whitespace_usize..)
-            .expect("last_whitespace is out of the array");
-        let tk = build_token(elem, linenum, last_whitespace_usize, line.len());
-        tokens.push(tk);
-    }
 }
 
 /// Parse a line, and detect all the `TextLine` and Comments that are there.
@@ -199,8 +268,13 @@ fn parse_line<'a>(tokens: &mut Vec<Token<'a>>, line: &'a str, linenum: usize) {
 }
 
 pub(super) fn tokenizer(inp: &str) -> Vec<Token> {
+    tokenizer_from(inp, 0)
+}
+
+/// Like [`tokenizer`], but the lines before `first_line` are skipped.
+pub(super) fn tokenizer_from(inp: &str, first_line: usize) -> Vec<Token<'_>> {
     let mut tokens: Vec<Token> = vec![];
-    for (linenum, line) in inp.lines().enumerate() {
+    for (linenum, line) in inp.lines().enumerate().skip(first_line) {
         parse_line(&mut tokens, line, linenum);
     }
     tokens
@@ -315,6 +389,18 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_non_ascii_spans() {
+        // The spans are in bytes, even after letters that take more than one.
+        let inp = ":cap Café 1.0";
+        let tokens = tokenizer(inp);
+        let spans: Vec<_> =
+            tokens.iter().map(|t| (t.span.beg, t.span.end)).collect();
+        assert_eq!(spans, [(0, 4), (5, 10), (11, 14)]);
+        assert_eq!(tokens[1].symbol, String("Café"));
+        assert_eq!(tokens[2].symbol, String("1.0"));
+    }
+
     #[test]
     fn test_parse_single_line() {
         let inp = " line no :ge escaped ";
@@ -0,0 +1,221 @@
+//! The tokens of the slidy language, for the tools that need them, like the
+//! editors' syntax highlighting.
+//!
+//! The parser works on tokens borrowed from the input: here they are copied,
+//! so that they can be kept and sent around.
+
+use super::tokenizer::{self, Structure, Token};
+
+pub use super::tokenizer::TokenSpan;
+
+/// What a token is. New directives add new kinds, so the matches on it
+/// need a wildcard arm.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize,
+)]
+#[non_exhaustive]
+pub enum TokenKind {
+    /// `:ge`, the general section.
+    Generic,
+    /// `:fc`, the font color.
+    Fontcolor,
+    /// `:bc`, the background color.
+    BackGroundColor,
+    /// `:sl`, a new slide.
+    Slide,
+    /// `:sl+`, a new slide, copied from the previous one.
+    SlideCopy,
+    /// `:title`, a new title slide.
+    Title,
+    /// `:sz`, the size.
+    Size,
+    /// `:tb`, a text section.
+    TextBuffer,
+    /// `:ps`, the position.
+    Position,
+    /// `:fg`, a figure section.
+    Figure,
+    /// `:rt`, the rotation of a figure or of a text.
+    Rotation,
+    /// `:im`, an import.
+    Import,
+    /// `:bw`, the width of a text box.
+    BoxWidth,
+    /// `:lh`, the height of the lines.
+    LineHeight,
+    /// `:vcenter`, to center a text from top to bottom.
+    VCenter,
+    /// `:cols`, to lay a text out in columns.
+    Columns,
+    /// `:fit-text`, the box a text is shrunk to fit in.
+    FitText,
+    /// `:code`, a code section.
+    Code,
+    /// `:tw`, the width of the tabs.
+    TabWidth,
+    /// `:only`, who can see a section.
+    Only,
+    /// `:def`, a variable.
+    Define,
+    /// `:meta`, something about the slideshow.
+    Meta,
+    /// `:order`, where a slide goes.
+    Order,
+    /// `:group`, the chapter of a slide.
+    Group,
+    /// `:grid`, the rows and the columns the figures are placed on.
+    Grid,
+    /// `:tag`, the profiles a slide or a section is shown in.
+    Tag,
+    /// `:op`, the opacity of a figure.
+    Opacity,
+    /// `:anchor`, the point a figure rotates around.
+    Anchor,
+    /// `:cap`, the caption of a figure.
+    Caption,
+    /// `:trim`, how the text lines are trimmed.
+    Trim,
+    /// `:frame`, how long each image of a sequence is shown.
+    FrameTime,
+    /// `:sbc`, the background color of a section.
+    SectionBgColor,
+    /// `:nocount`, to leave a slide out of the counter.
+    NoCount,
+    /// A line of text.
+    TextLine,
+    /// A comment, starting with `#`.
+    Comment,
+    /// A word after a token, like a color's name or a path.
+    String,
+    /// A number after a token.
+    Number,
+}
+
+/// The value of a token, for the text lines, the comments, the words and the
+/// numbers.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum TokenValue {
+    /// Some text, as it is in the input.
+    Text(String),
+    /// A number.
+    Number(f32),
+}
+
+/// A token, that owns its value.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct OwnedToken {
+    /// What the token is.
+    pub kind: TokenKind,
+    /// Its value, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<TokenValue>,
+    /// Where the token is in the input.
+    pub span: TokenSpan,
+}
+
+impl From<&Token<'_>> for OwnedToken {
+    fn from(token: &Token<'_>) -> Self {
+        let text = |s: &str| Some(TokenValue::Text(s.to_string()));
+        let (kind, value) = match token.symbol {
+            Structure::Generic => (TokenKind::Generic, None),
+            Structure::Fontcolor => (TokenKind::Fontcolor, None),
+            Structure::BackGroundColor => (TokenKind::BackGroundColor, None),
+            Structure::Slide => (TokenKind::Slide, None),
+            Structure::SlideCopy => (TokenKind::SlideCopy, None),
+            Structure::Title => (TokenKind::Title, None),
+            Structure::Size => (TokenKind::Size, None),
+            Structure::TextBuffer => (TokenKind::TextBuffer, None),
+            Structure::Position => (TokenKind::Position, None),
+            Structure::Figure => (TokenKind::Figure, None),
+            Structure::Rotation => (TokenKind::Rotation, None),
+            Structure::Import => (TokenKind::Import, None),
+            Structure::BoxWidth => (TokenKind::BoxWidth, None),
+            Structure::LineHeight => (TokenKind::LineHeight, None),
+            Structure::VCenter => (TokenKind::VCenter, None),
+            Structure::Columns => (TokenKind::Columns, None),
+            Structure::FitText => (TokenKind::FitText, None),
+            Structure::Code => (TokenKind::Code, None),
+            Structure::TabWidth => (TokenKind::TabWidth, None),
+            Structure::Only => (TokenKind::Only, None),
+            Structure::Define => (TokenKind::Define, None),
+            Structure::Meta => (TokenKind::Meta, None),
+            Structure::Order => (TokenKind::Order, None),
+            Structure::Group => (TokenKind::Group, None),
+            Structure::Grid => (TokenKind::Grid, None),
+            Structure::Tag => (TokenKind::Tag, None),
+            Structure::Opacity => (TokenKind::Opacity, None),
+            Structure::Anchor => (TokenKind::Anchor, None),
+            Structure::Caption => (TokenKind::Caption, None),
+            Structure::Trim => (TokenKind::Trim, None),
+            Structure::FrameTime => (TokenKind::FrameTime, None),
+            Structure::SectionBgColor => (TokenKind::SectionBgColor, None),
+            Structure::NoCount => (TokenKind::NoCount, None),
+            Structure::TextLine(s) => (TokenKind::TextLine, text(s)),
+            Structure::Comment(s) => (TokenKind::Comment, text(s)),
+            Structure::String(s) => (TokenKind::String, text(s)),
+            Structure::Number(n) => {
+                (TokenKind::Number, Some(TokenValue::Number(n)))
+            }
+        };
+        Self {
+            kind,
+            value,
+            span: token.span().clone(),
+        }
+    }
+}
+
+/// Split the input text in tokens, as the parser does.
+///
+/// ```
+/// use slidy::parser::{tokenize, TokenKind};
+///
+/// let tokens = tokenize(":sl\n:tb :fc red\nHello");
+/// let kinds: Vec<_> = tokens.iter().map(|t| t.kind).collect();
+/// assert_eq!(
+///     kinds,
+///     [
+///         TokenKind::Slide,
+///         TokenKind::TextBuffer,
+///         TokenKind::Fontcolor,
+///         TokenKind::String,
+///         TokenKind::TextLine,
+///     ]
+/// );
+/// ```
+#[must_use]
+pub fn tokenize(inp: &str) -> Vec<OwnedToken> {
+    tokenizer::tokenizer(inp)
+        .iter()
+        .map(OwnedToken::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize("# Hi\n:ge :sz 0.1 0.2\nText");
+        assert_eq!(tokens.len(), 6);
+        assert_eq!(tokens[0].value, Some(TokenValue::Text("# Hi".into())));
+        assert_eq!(tokens[3].kind, TokenKind::Number);
+        assert_eq!(tokens[3].value, Some(TokenValue::Number(0.1)));
+        assert_eq!(tokens[5].span, TokenSpan::new(2, 0, 4));
+    }
+
+    #[test]
+    fn test_same_json() {
+        // The owned tokens are written the same as the borrowed ones.
+        let text = ":ge :def V 1.10\n:sl :order -1\n:tb\nHello\n# done";
+        let borrowed = serde_json::to_string(&tokenizer::tokenizer(text));
+        let owned = serde_json::to_string(&tokenize(text));
+        assert_eq!(owned.unwrap(), borrowed.unwrap());
+
+        let json = serde_json::to_string(&tokenize(text)).unwrap();
+        let back: Vec<OwnedToken> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, tokenize(text));
+    }
+}
@@ -1,14 +1,17 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
 use std::error::Error;
 use std::path::Path;
 
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 
-use super::lexer::{CurrentState, Lexer};
+use super::lexer::{CurrentState, Lexer, TrimMode};
 use super::tokenizer::{Structure, Token};
 
 use crate::slideshow::{
-    Color, Position, Section, SectionFigure, SectionMain, SectionText, Size,
-    Slide,
+    decode_data_uri, Color, Gradient, GradientDirection, Layout, Position,
+    Section, SectionFigure, SectionMain, SectionText, Size, Slide, Slideshow,
+    TextRun, Visibility, DEFAULT_FIGURE_SIZE, DEFAULT_FONT_SIZE,
 };
 
 fn apply_slide<T, U>(
@@ -45,50 +48,271 @@ pub(super) fn manage_import(
     }
     let mut path = std::path::PathBuf::new();
     path.push(format!("{}/{}", base_folder.display(), el).as_str());
-    let mut imported_slides = super::parse_file(&path)?;
+    let mut imported_slides =
+        super::parse_file_with_options(&path, &lexer.options)?;
+    // The images of the imported slides are relative to their own file.
+    if let Some(folder) = Path::new(el).parent() {
+        rebase_images(&mut imported_slides.slides, folder);
+    }
     lexer.slideshow.slides.append(&mut imported_slides.slides);
+    // The importing file's defaults always win, even when they are set after
+    // the import.
+    if lexer.options.merge_imported_defaults {
+        let slideshow = &mut lexer.slideshow;
+        let merged = &mut lexer.internals.merged;
+        if slideshow.bg_col.is_none()
+            && slideshow.gradient.is_none()
+            && general_background(&imported_slides).is_some()
+        {
+            slideshow.bg_col = imported_slides.bg_col;
+            slideshow.gradient = imported_slides.gradient;
+            merged.insert(BACKGROUND);
+        }
+        if slideshow.font_col.is_none() && imported_slides.font_col.is_some() {
+            slideshow.font_col = imported_slides.font_col;
+            merged.insert(FONT_COLOR);
+        }
+        if slideshow.font_size.is_none() && imported_slides.font_size.is_some()
+        {
+            slideshow.font_size = imported_slides.font_size;
+            merged.insert(FONT_SIZE);
+        }
+        // The fonts are relative to the imported file, like its images.
+        let folder = Path::new(el).parent().unwrap_or_else(|| Path::new(""));
+        for (name, path) in imported_slides.fonts {
+            let path = folder.join(path).to_string_lossy().into_owned();
+            slideshow.fonts.entry(name).or_insert(path);
+        }
+        for (key, value) in imported_slides.metadata {
+            slideshow.metadata.entry(key).or_insert(value);
+        }
+    }
     // If everything went ok, we can ignore the next token.
     Ok(1)
 }
 
-pub(super) fn manage_slide(lexer: &mut Lexer, _tokens: &[Token]) -> usize {
-    match &mut lexer.internals.slide {
-        None => lexer.internals.slide = Some(Slide::default()),
-        Some(s) => {
-            let slide = std::mem::replace(s, Slide::default());
-            debug!("Pushing slide: {:?}", &slide);
-            lexer.slideshow.slides.push(slide);
+/// Make the relative paths of the images of `slides`, the figures and the
+/// inline images, imported from a file in `folder`, relative to the file
+/// that imports them instead.
+fn rebase_images(slides: &mut [Slide], folder: &Path) {
+    let rebased = |path: &str| {
+        decode_data_uri(path).map_or_else(
+            || folder.join(path).to_string_lossy().into_owned(),
+            |_| path.to_string(),
+        )
+    };
+    for section in slides.iter_mut().flat_map(|s| s.sections.iter_mut()) {
+        match &mut section.sec_main {
+            Some(SectionMain::Figure(fig)) => {
+                fig.path = rebased(&fig.path);
+                for path in &mut fig.paths {
+                    *path = rebased(path);
+                }
+            }
+            Some(SectionMain::Text(text)) => {
+                let Ok(rewritten) =
+                    rewrite_inline_images::<Infallible>(&text.text, |path| {
+                        Ok(Some(rebased(path)))
+                    });
+                text.text = rewritten;
+            }
+            None => {}
         }
     }
+}
+
+/// Start a new slide. With `copy` (`:sl+`), the new slide starts as a copy
+/// of the previous one, so that it can be built on top of it.
+pub(super) fn manage_slide(
+    lexer: &mut Lexer,
+    _tokens: &[Token],
+    copy: bool,
+) -> usize {
+    let previous = lexer
+        .internals
+        .slide
+        .as_ref()
+        .or_else(|| lexer.slideshow.slides.last());
+    let next = match previous {
+        Some(previous) if copy => previous.clone(),
+        _ => Slide::default(),
+    };
+    if let Some(slide) = lexer.internals.slide.replace(next) {
+        debug!("Pushing slide: {:?}", &slide);
+        lexer.slideshow.slides.push(slide);
+    }
     lexer.internals.state = CurrentState::Slide;
     0
 }
 
+/// A new title slide, whose text is the title: see [`Layout::Title`].
+pub(super) fn manage_title(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    manage_slide(lexer, tokens, false);
+    apply_slide(&mut lexer.internals.slide, |slide| {
+        slide.layout = Some(Layout::Title);
+        Ok(())
+    })?;
+    manage_textbuffer(lexer, tokens)
+}
+
+/// Check that the inline images (`![alt](path)`) of the line can be found:
+/// their paths are kept as they are written, like the ones of the figures.
+/// An image that can't be found is kept as plain text, escaped, unless
+/// `strict` makes it an error.
+pub(super) fn resolve_inline_images(
+    line: &str,
+    base_folder: &Path,
+    strict: bool,
+) -> Result<String, Box<dyn Error + 'static>> {
+    rewrite_inline_images(line, |path| {
+        if let Some(data) = decode_data_uri(path) {
+            data?;
+            return Ok(Some(path.to_string()));
+        }
+        if base_folder.join(path).is_file() {
+            return Ok(Some(path.to_string()));
+        }
+        let message = format!("Unable to find the inline image {path}");
+        if strict {
+            return Err(message.into());
+        }
+        warn!("{message}: it is kept as text.");
+        Ok(None)
+    })
+}
+
+/// Write `text` again, with the path of each inline image given by `image`,
+/// or with the image as plain text when it gives `None`. The `![` that are
+/// plain text are escaped, so that they stay so.
+fn rewrite_inline_images<E>(
+    text: &str,
+    mut image: impl FnMut(&str) -> Result<Option<String>, E>,
+) -> Result<String, E> {
+    let mut rewritten = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        for run in TextRun::split(line) {
+            match run {
+                TextRun::Text(text) => {
+                    rewritten.push_str(&text.replace("![", "\\!["));
+                }
+                TextRun::Image { alt, path } => {
+                    let (open, path) = image(path)?.map_or_else(
+                        || ("\\![", path.to_string()),
+                        |path| ("![", path),
+                    );
+                    for part in [open, alt, "](", &path, ")"] {
+                        rewritten.push_str(part);
+                    }
+                }
+            }
+        }
+    }
+    Ok(rewritten)
+}
+
+/// How many columns a tab takes in the text lines, unless `:ge :tw` says
+/// otherwise.
+pub(super) const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// Replace the tabs in `line` with spaces, up to the next multiple of
+/// `width` columns, so that all the backends align the text the same way.
+fn expand_tabs(line: &str, width: usize) -> String {
+    let mut expanded = String::with_capacity(line.len());
+    let mut column = 0;
+    for ch in line.chars() {
+        if ch == '\t' {
+            let spaces = width - column % width;
+            expanded.extend(std::iter::repeat_n(' ', spaces));
+            column += spaces;
+        } else {
+            expanded.push(ch);
+            column += 1;
+        }
+    }
+    expanded
+}
+
+/// Replace the `${NAME}` in `line` with the value of the `NAME` variable.
+/// Undefined variables are kept as they are, or are an error if `strict`.
+fn substitute_variables(
+    line: &str,
+    variables: &HashMap<String, String>,
+    strict: bool,
+) -> Result<String, Box<dyn Error + 'static>> {
+    let mut substituted = String::with_capacity(line.len());
+    let mut rem = line;
+    while let Some(start) = rem.find("${") {
+        let Some(len) = rem[start..].find('}') else {
+            break;
+        };
+        let (name, reference) =
+            (&rem[start + 2..start + len], &rem[start..=start + len]);
+        substituted.push_str(&rem[..start]);
+        match variables.get(name) {
+            Some(value) => substituted.push_str(value),
+            Option::None if strict => {
+                return Err(format!("Undefined variable `{name}`").into());
+            }
+            Option::None => substituted.push_str(reference),
+        }
+        rem = &rem[start + len + 1..];
+    }
+    substituted.push_str(rem);
+    Ok(substituted)
+}
+
 pub(super) fn manage_textline(
     lexer: &mut Lexer,
     el: &str,
     _tokens: &[Token],
-    _base_folder: &Path,
+    base_folder: &Path,
 ) -> Result<usize, Box<dyn Error + 'static>> {
     use CurrentState::{Figure, General, Import, None, Slide, Text};
 
     match lexer.internals.state {
         Import | Figure | Slide | General | None => {
-            if el.is_empty() {
+            // Blank lines are fine anywhere, even with some spaces in them.
+            if el.trim().is_empty() {
                 Ok(0)
             } else {
                 Err("A textline does make sense only in a text section.".into())
             }
         }
         Text => {
+            let el = match lexer.internals.trim {
+                TrimMode::Off => el,
+                TrimMode::Trailing => el.trim_end(),
+                TrimMode::Both => el.trim(),
+            };
+            let tab_width =
+                lexer.internals.tab_width.unwrap_or(DEFAULT_TAB_WIDTH);
+            // The code is kept as it is, backslashes included.
+            let line = if in_code(lexer) {
+                el
+            } else {
+                strip_line_break(el)
+            };
+            let line = expand_tabs(&line.replace("\\:", ":"), tab_width);
+            let line = substitute_variables(
+                &line,
+                &lexer.variables,
+                lexer.options.strict_vars || lexer.options.strict,
+            )?;
+            let line = resolve_inline_images(
+                &line,
+                base_folder,
+                lexer.options.strict,
+            )?;
             apply_slide(&mut lexer.internals.slide, |slide| {
                 let last_section = slide.sections.len() - 1;
 
                 slide.sections[last_section].sec_main.as_mut().map_or_else(
                     || Err("No section is built yet.".into()),
                     |sec_main| if let SectionMain::Text(ref mut text) = sec_main {
-                        text.text.push_str(&el.replace("\\:", ":"));
-                        text.text.push('\n');
+                        text.push_line(&line);
                         Ok(())
                     } else {
                         Err("In a Text section but the last section is not a figure... How?".into())})
@@ -98,6 +322,35 @@ pub(super) fn manage_textline(
     }
 }
 
+/// If the text section being written is made of code.
+fn in_code(lexer: &Lexer) -> bool {
+    lexer
+        .internals
+        .slide
+        .as_ref()
+        .and_then(|slide| slide.sections.last())
+        .is_some_and(|section| {
+            matches!(
+                &section.sec_main,
+                Some(SectionMain::Text(text)) if text.is_code
+            )
+        })
+}
+
+/// Drop the `\` that asks for a line break at the end of a text line, like
+/// in `one \`: the line ends there anyway. A `\` alone makes a blank line.
+/// The other backslashes are kept, like in `one \ two`.
+fn strip_line_break(line: &str) -> &str {
+    match line.trim_end().strip_suffix('\\') {
+        Some(rest)
+            if rest.is_empty() || rest.ends_with(char::is_whitespace) =>
+        {
+            rest.trim_end()
+        }
+        _ => line,
+    }
+}
+
 pub(super) fn manage_textbuffer(
     lexer: &mut Lexer,
     _tokens: &[Token],
@@ -115,6 +368,32 @@ pub(super) fn manage_textbuffer(
     Ok(0)
 }
 
+/// A text section made of code, maybe followed by its language, like `:code
+/// rust`.
+pub(super) fn manage_code(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    manage_textbuffer(lexer, tokens)?;
+    let mut lang = match tokens.first().map(|t| &t.symbol) {
+        Some(Structure::String(lang)) => Some((*lang).to_string()),
+        _ => None,
+    };
+    let skip = usize::from(lang.is_some());
+    apply_slide(&mut lexer.internals.slide, |slide| {
+        if let Some(Section {
+            sec_main: Some(SectionMain::Text(text)),
+            ..
+        }) = slide.sections.last_mut()
+        {
+            text.is_code = true;
+            text.lang = lang.take();
+        }
+        Ok(())
+    })?;
+    Ok(skip)
+}
+
 pub(super) fn manage_figure(
     lexer: &mut Lexer,
     tokens: &[Token],
@@ -122,26 +401,39 @@ pub(super) fn manage_figure(
 ) -> Result<usize, Box<dyn Error + 'static>> {
     lexer.internals.state = CurrentState::Figure;
 
-    let Some(el) = tokens.first().and_then(|t| match t.symbol {
-        Structure::String(el) => Some(el),
-        _ => None,
-    }) else {
+    // Many paths make a sequence of images.
+    let names: Vec<&str> = tokens
+        .iter()
+        .map_while(|t| match t.symbol {
+            Structure::String(el) => Some(el),
+            _ => None,
+        })
+        .collect();
+    if names.is_empty() {
         return Err("In an figure, we must have a path.".into());
-    };
+    }
 
-    let figure_path = String::from(
-        base_folder
-            .join(el)
-            .canonicalize()
-            .unwrap()
-            .to_str()
-            .unwrap(),
-    );
+    // The paths are kept as they are written, relative to the slides, so
+    // that they can be moved with their images.
+    for el in &names {
+        if let Some(data) = decode_data_uri(el) {
+            // The figure is embedded in the slides.
+            data?;
+        } else if !base_folder.join(el).is_file() {
+            return Err(format!("Unable to find the figure {el}.").into());
+        }
+    }
+    let paths: Vec<String> = names.iter().map(ToString::to_string).collect();
 
     apply_slide(&mut lexer.internals.slide, |slide| {
         let figure_sec = Section {
             sec_main: Some(SectionMain::Figure(SectionFigure {
-                path: figure_path.clone(),
+                path: paths[0].clone(),
+                paths: if paths.len() > 1 {
+                    paths.clone()
+                } else {
+                    Vec::new()
+                },
                 ..SectionFigure::default()
             })),
             ..Section::default()
@@ -150,7 +442,71 @@ pub(super) fn manage_figure(
         Ok(())
     })?;
 
-    Ok(1)
+    Ok(names.len())
+}
+
+pub(super) fn manage_frame_time(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | Text | General | None => {
+            Err("Frame time does make sense only in a figure section.".into())
+        }
+        Figure => {
+            let Some(t) = tokens.first() else {
+                return Err("Frame time must have 1 token after it".into());
+            };
+            let ms = extract_f32(t)?;
+            if ms.fract() != 0.0 || !(1.0..=1e6).contains(&ms) {
+                let e = format!(
+                    "Frame time must be a positive integer, found {t:?}"
+                );
+                return Err(e.into());
+            }
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                let last_section = slide.sections.len() - 1;
+                if let Some(SectionMain::Figure(figure)) =
+                    &mut slide.sections[last_section].sec_main
+                {
+                    #[allow(clippy::cast_possible_truncation)]
+                    #[allow(clippy::cast_sign_loss)]
+                    let ms = ms as u32;
+                    figure.frame_ms = ms;
+                    Ok(())
+                } else {
+                    Err("In a Figure section but the last section is not a figure... How?".into())
+                }
+            })?;
+            Ok(1)
+        }
+    }
+}
+
+/// As a position, we accept 2 floats, where any of them can be `_` to keep
+/// the `current` value, or a single float after an axis, like `x 0.3`. The
+/// coordinates that are still not known are left to the backend.
+fn get_position(
+    tokens: &[Token],
+    (cur_x, cur_y): (Option<f32>, Option<f32>),
+) -> Result<(Option<f32>, Option<f32>), Box<dyn Error + 'static>> {
+    let Some([t1, t2]) = tokens.get(0..2) else {
+        return Err("Position must have 2 tokens after it".into());
+    };
+    match (&t1.symbol, &t2.symbol) {
+        (Structure::String("x"), Structure::Number(x)) => Ok((Some(*x), cur_y)),
+        (Structure::String("y"), Structure::Number(y)) => Ok((cur_x, Some(*y))),
+        _ => {
+            let coordinate = |t: &Token, cur: Option<f32>| match t.symbol {
+                Structure::Number(v) => Ok(Some(v)),
+                Structure::String("_") => Ok(cur),
+                _ => Err(format!("Expect a float or `_`, found {t:?}")),
+            };
+            Ok((coordinate(t1, cur_x)?, coordinate(t2, cur_y)?))
+        }
+    }
 }
 
 pub(super) fn manage_position(
@@ -164,28 +520,13 @@ pub(super) fn manage_position(
             Err("Position does make sense only for text and figures.".into())
         }
         Text | Figure => {
+            let strict = lexer.options.strict;
             apply_slide(&mut lexer.internals.slide, |slide| {
-                // Get 2 numbers
-                let v = if let Some([t1, t2]) = tokens.get(0..2) {
-                    let Structure::Number(v1) = t1.symbol else {
-                            return Err(format!(
-                                "Expect a float, found {t1:?}"
-                            )
-                            .into())
-                    };
-                    let Structure::Number(v2) = t2.symbol else {
-                            return Err(format!(
-                                "Expect a float, found {t2:?}"
-                            )
-                            .into())
-                    };
-                    Position { x: v1, y: v2 }
-                } else {
-                    return Err("Position must have 2 tokens after it".into());
-                };
-
                 let last_section = slide.sections.len() - 1;
-                slide.sections[last_section].position = Some(v);
+                let section = &mut slide.sections[last_section];
+                let (x, y) = get_position(tokens, section.coordinates())?;
+                check_position((x, y), tokens, strict)?;
+                section.set_coordinates(x, y);
                 Ok(())
             })?;
             Ok(2)
@@ -193,12 +534,157 @@ pub(super) fn manage_position(
     }
 }
 
+/// Warn when a position is outside the slide: the section starts at the edge
+/// of the slide instead. With `strict`, it is an error.
+fn check_position(
+    (x, y): (Option<f32>, Option<f32>),
+    tokens: &[Token],
+    strict: bool,
+) -> Result<(), Box<dyn Error + 'static>> {
+    let inside = |v: Option<f32>| v.is_none_or(|v| (0.0..=1.0).contains(&v));
+    if inside(x) && inside(y) {
+        return Ok(());
+    }
+    let at = tokens
+        .first()
+        .map_or_else(String::new, |t| format!(" at {}", t.span()));
+    let show =
+        |v: Option<f32>| v.map_or_else(|| "_".to_string(), |v| v.to_string());
+    let message = format!(
+        "The position ({}, {}){at} is outside the slide",
+        show(x),
+        show(y)
+    );
+    if strict {
+        return Err(message.into());
+    }
+    warn!("{message}: the section starts at its edge.");
+    Ok(())
+}
+
+/// The names of the general settings, as [`warn_override`] reports them.
+const BACKGROUND: &str = "background";
+const FONT_COLOR: &str = "font color";
+const FONT_SIZE: &str = "font size";
+
+/// Like [`warn_override`], but a value merged from an import is not an
+/// override: the importing file's own defaults always win.
+fn check_override<T: PartialEq + std::fmt::Debug>(
+    merged: &mut HashSet<&'static str>,
+    what: &'static str,
+    old: Option<&T>,
+    new: &T,
+    tokens: &[Token],
+    strict: bool,
+) -> Result<(), Box<dyn Error + 'static>> {
+    if merged.remove(what) {
+        return Ok(());
+    }
+    warn_override(what, old, new, tokens, strict)
+}
+
+/// Warn when a general setting that was already set gets another value: the
+/// last one wins, which is easy to miss in a large or imported deck. With
+/// `strict`, it is an error instead.
+fn warn_override<T: PartialEq + std::fmt::Debug>(
+    what: &str,
+    old: Option<&T>,
+    new: &T,
+    tokens: &[Token],
+    strict: bool,
+) -> Result<(), Box<dyn Error + 'static>> {
+    let Some(old) = old.filter(|old| *old != new) else {
+        return Ok(());
+    };
+    let at = tokens
+        .first()
+        .map_or_else(String::new, |t| format!(" at {}", t.span()));
+    if strict {
+        return Err(format!(
+            "The {what} is set again{at}, from {old:?} to {new:?}."
+        )
+        .into());
+    }
+    warn!(
+        "The {what} is set again{at}, from {old:?} to {new:?}: the last one \
+         wins."
+    );
+    Ok(())
+}
+
+/// The background of the slides, as a color or as a gradient: setting one
+/// replaces the other.
+#[derive(Debug, PartialEq)]
+enum Background {
+    Color(Color),
+    Gradient(Gradient),
+}
+
+/// The background the general section has set so far, if any.
+fn general_background(slideshow: &Slideshow) -> Option<Background> {
+    slideshow
+        .gradient
+        .map(Background::Gradient)
+        .or_else(|| slideshow.bg_col.map(Background::Color))
+}
+
+/// Check if the next token asks to go back to the built-in default, with
+/// either `default` or `none`.
+fn is_reset(tokens: &[Token]) -> bool {
+    matches!(
+        tokens.first().map(|t| &t.symbol),
+        Some(Structure::String(s))
+            if s.eq_ignore_ascii_case("default") || s.eq_ignore_ascii_case("none")
+    )
+}
+
+/// Read a size multiplier, like `x1.5`.
+fn get_multiplier(token: &Token) -> Option<f32> {
+    match token.symbol {
+        Structure::String(s) => s.strip_prefix('x')?.parse().ok(),
+        _ => Option::None,
+    }
+}
+
+/// Read a number of letters per line, like `fit40`.
+fn get_letters_per_line(token: &Token) -> Option<f32> {
+    match token.symbol {
+        Structure::String(s) => {
+            let n: f32 = s.strip_prefix("fit")?.parse().ok()?;
+            (n >= 1.0 && n.is_finite()).then_some(n)
+        }
+        _ => Option::None,
+    }
+}
+
 /// As a size, we both accept a single integer or 2 floats.
 /// In case we find a single float, we re-interpret that as a "single size" and
 /// we change both x and y value based on that.
+/// A multiplier, like `x1.5`, scales the `inherited` size instead.
+/// A number of letters per line, like `fit40`, makes the letters as wide as
+/// that many fit across the window, and keeps the `inherited` height to
+/// width ratio.
 fn get_size(
     tokens: &[Token],
+    inherited: &Size,
 ) -> Result<(Size, usize), Box<dyn Error + 'static>> {
+    if let Some(m) = tokens.first().and_then(get_multiplier) {
+        let size = Size {
+            w: inherited.w * m,
+            h: inherited.h * m,
+        };
+        return Ok((size, 1));
+    }
+    if let Some(n) = tokens.first().and_then(get_letters_per_line) {
+        let ratio = inherited.h / inherited.w;
+        let ratio = if ratio.is_finite() && ratio > 0.0 {
+            ratio
+        } else {
+            DEFAULT_FONT_SIZE.h / DEFAULT_FONT_SIZE.w
+        };
+        let w = 1.0 / n;
+        return Ok((Size { w, h: w * ratio }, 1));
+    }
     if let Some([t1, t2]) = tokens.get(0..2) {
         let skip;
         let Structure::Number(mut v1) = t1.symbol else { return Err(format!("Expect a float, found {t1:?}").into()) };
@@ -238,16 +724,46 @@ pub(super) fn manage_size(
             "Size does make sense only in general, text and figure sections."
                 .into(),
         ),
+        General if is_reset(tokens) => {
+            lexer.slideshow.font_size = Option::None;
+            Ok(1)
+        }
         General => {
-            let r = get_size(tokens)?;
+            let inherited = lexer
+                .slideshow
+                .font_size
+                .as_ref()
+                .unwrap_or(&DEFAULT_FONT_SIZE);
+            let r = get_size(tokens, inherited)?;
+            check_override(
+                &mut lexer.internals.merged,
+                FONT_SIZE,
+                lexer.slideshow.font_size.as_ref(),
+                &r.0,
+                tokens,
+                lexer.options.strict,
+            )?;
             lexer.slideshow.font_size = Some(r.0);
             Ok(r.1)
         }
-        Text | Figure => {
+        state @ (Text | Figure) => {
+            // The size a multiplier applies to: the section's one, or the
+            // default one for this kind of section.
+            let default = if state == Text {
+                lexer
+                    .slideshow
+                    .font_size
+                    .as_ref()
+                    .unwrap_or(&DEFAULT_FONT_SIZE)
+            } else {
+                &DEFAULT_FIGURE_SIZE
+            };
             let skip = apply_slide(&mut lexer.internals.slide, |slide| {
                 let last_section = slide.sections.len() - 1;
-                let r = get_size(tokens)?;
-                slide.sections[last_section].size = Some(r.0);
+                let section = &mut slide.sections[last_section];
+                let r =
+                    get_size(tokens, section.size.as_ref().unwrap_or(default))?;
+                section.size = Some(r.0);
                 Ok(r.1)
             })?;
             Ok(skip)
@@ -286,9 +802,20 @@ pub(super) fn manage_fontcolor(
             "FontColor color does make sense only in general and slide sections."
                 .into(),
         ),
+        General if is_reset(tokens) => {
+            lexer.slideshow.font_col = Option::None;
+            Ok(1)
+        }
         General => {
-                        let (c, skip) = get_color(tokens)?;
-
+            let (c, skip) = get_color(tokens)?;
+            check_override(
+                &mut lexer.internals.merged,
+                FONT_COLOR,
+                lexer.slideshow.font_col.as_ref(),
+                &c,
+                tokens,
+                lexer.options.strict,
+            )?;
             lexer.slideshow.font_col = Some(c);
             Ok(skip)
         }
@@ -317,7 +844,7 @@ pub(super) fn manage_fontcolor(
 }
 
 /// Color's names are taken from <https://encycolorpedia.com/websafe>
-fn match_string_color(
+pub(super) fn match_string_color(
     color_str: &str,
 ) -> Result<Color, Box<dyn Error + 'static>> {
     // Try to match the exa values
@@ -344,6 +871,9 @@ fn match_string_color(
         }
         return Err("Exa format must be 0xrrggbbaa".into());
     }
+    if let Some(hsl) = parse_hsl(color_str) {
+        return hsl;
+    }
     // Try to match the string names
     match color_str.to_lowercase().as_str() {
         "acqua" => return Ok((0x00, 0xff, 0xff, 0xff).into()),
@@ -367,9 +897,182 @@ fn match_string_color(
     Err(format!("Unable to parse {color_str} into a known color.").into())
 }
 
+/// Convert a hue (in degrees), a saturation and a lightness (in `[0, 1]`) to
+/// red, green and blue, in `[0, 1]`.
+fn hsl_to_rgb(hue: f32, sat: f32, light: f32) -> (f32, f32, f32) {
+    let chroma = (1.0 - 2.0f32.mul_add(light, -1.0).abs()) * sat;
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let second = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector {
+        s if s < 1.0 => (chroma, second, 0.0),
+        s if s < 2.0 => (second, chroma, 0.0),
+        s if s < 3.0 => (0.0, chroma, second),
+        s if s < 4.0 => (0.0, second, chroma),
+        s if s < 5.0 => (second, 0.0, chroma),
+        _ => (chroma, 0.0, second),
+    };
+    let base = light - chroma / 2.0;
+    (r + base, g + base, b + base)
+}
+
+/// Convert a value in `[0, 1]` to a color channel.
+fn unit_to_u8(v: f32) -> u8 {
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    let v = (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    v
+}
+
+/// Parse a color like `hsl(200, 50%, 40%)` or `hsla(200, 50%, 40%, 0.5)`.
+/// It is `None` if the string is not in this form at all.
+fn parse_hsl(
+    color_str: &str,
+) -> Option<Result<Color, Box<dyn Error + 'static>>> {
+    let lower = color_str.to_lowercase();
+    let (args, with_alpha) = if let Some(args) = lower.strip_prefix("hsla(") {
+        (args, true)
+    } else {
+        (lower.strip_prefix("hsl(")?, false)
+    };
+    let parse = || -> Result<Color, Box<dyn Error + 'static>> {
+        let args = args
+            .strip_suffix(')')
+            .ok_or_else(|| format!("Missing ')' in {color_str}"))?;
+        let values: Vec<&str> = args.split(',').map(str::trim).collect();
+        let (hue, sat, light, alpha) = match (values.as_slice(), with_alpha) {
+            ([h, s, l], false) => (*h, *s, *l, Option::None),
+            ([h, s, l, a], true) => (*h, *s, *l, Some(*a)),
+            _ => {
+                let e = format!("Expected hsl(h, s%, l%) or hsla(h, s%, l%, a), found {color_str}");
+                return Err(e.into());
+            }
+        };
+        let hue: f32 = hue.parse()?;
+        let percent = |v: &str| -> Result<f32, Box<dyn Error + 'static>> {
+            let p: f32 = v.trim_end_matches('%').parse()?;
+            if !(0.0..=100.0).contains(&p) {
+                return Err(format!("{v} must be between 0% and 100%").into());
+            }
+            Ok(p / 100.0)
+        };
+        let (r, g, b) = hsl_to_rgb(hue, percent(sat)?, percent(light)?);
+        let alpha = match alpha {
+            Some(a) if a.ends_with('%') => percent(a)?,
+            Some(a) => get_alpha(a.parse()?)?,
+            Option::None => 1.0,
+        };
+        Ok((
+            unit_to_u8(r),
+            unit_to_u8(g),
+            unit_to_u8(b),
+            unit_to_u8(alpha),
+        )
+            .into())
+    };
+    Some(parse())
+}
+
+/// Check that an alpha is in `[0, 1]`.
+fn get_alpha(alpha: f32) -> Result<f32, Box<dyn Error + 'static>> {
+    if (0.0..=1.0).contains(&alpha) {
+        Ok(alpha)
+    } else {
+        Err(format!("The alpha must be between 0 and 1, found {alpha}").into())
+    }
+}
+
+/// Get a color, followed by an optional `alpha 0.5` (or `alpha(0.5)`) that
+/// changes its alpha only, like in `red alpha 0.5`.
 fn get_color(
     tokens: &[Token],
 ) -> Result<(Color, usize), Box<dyn Error + 'static>> {
+    let (mut color, skip) = get_base_color(tokens)?;
+    let rest = &tokens[skip..];
+    let (alpha, more) = match rest.first().map(|t| &t.symbol) {
+        Some(Structure::String(s)) if s.eq_ignore_ascii_case("alpha") => {
+            let Some(t) = rest.get(1) else {
+                return Err("Missing the value after alpha".into());
+            };
+            (extract_f32(t)?, 2)
+        }
+        Some(Structure::String(s))
+            if s.to_lowercase().starts_with("alpha(") && s.ends_with(')') =>
+        {
+            (s[6..s.len() - 1].trim().parse()?, 1)
+        }
+        _ => return Ok((color, skip)),
+    };
+    color.a = unit_to_u8(get_alpha(alpha)?);
+    Ok((color, skip + more))
+}
+
+/// If `token` is the given `word`, whatever its case.
+fn is_word(token: Option<&Token>, word: &str) -> bool {
+    matches!(
+        token.map(|t| &t.symbol),
+        Some(Structure::String(s)) if s.eq_ignore_ascii_case(word)
+    )
+}
+
+/// Read a gradient, like `gradient red blue horizontal`, where the direction
+/// is vertical when not given: get it, and how many tokens it took.
+fn get_gradient(
+    tokens: &[Token],
+) -> Result<(Gradient, usize), Box<dyn Error + 'static>> {
+    if !is_word(tokens.first(), "gradient") {
+        return Err("A gradient must start with gradient".into());
+    }
+    let (from, skip_from) = get_color(&tokens[1..])
+        .map_err(|e| format!("Invalid first color of the gradient: {e}"))?;
+    let rest = &tokens[1 + skip_from..];
+    let (to, skip_to) = get_color(rest)
+        .map_err(|e| format!("Invalid second color of the gradient: {e}"))?;
+    let skip = 1 + skip_from + skip_to;
+    let direction = if is_word(tokens.get(skip), "horizontal") {
+        Some(GradientDirection::Horizontal)
+    } else if is_word(tokens.get(skip), "vertical") {
+        Some(GradientDirection::Vertical)
+    } else {
+        Option::None
+    };
+    let gradient = Gradient {
+        from,
+        to,
+        direction: direction.unwrap_or_default(),
+    };
+    Ok((gradient, skip + usize::from(direction.is_some())))
+}
+
+/// The text of the tokens of an `hsl(...)` color, that may be split in many
+/// tokens by the spaces in it, and how many tokens it takes.
+fn join_hsl_tokens(tokens: &[Token]) -> Option<(String, usize)> {
+    let Some(Structure::String(first)) = tokens.first().map(|t| &t.symbol)
+    else {
+        return Option::None;
+    };
+    if !first.to_lowercase().starts_with("hsl") {
+        return Option::None;
+    }
+    let mut joined = String::new();
+    for (idx, t) in tokens.iter().enumerate() {
+        match &t.symbol {
+            Structure::String(s) => joined.push_str(s),
+            Structure::Number(n) => joined.push_str(&n.to_string()),
+            _ => return Option::None,
+        }
+        if joined.ends_with(')') {
+            return Some((joined, idx + 1));
+        }
+    }
+    Option::None
+}
+
+fn get_base_color(
+    tokens: &[Token],
+) -> Result<(Color, usize), Box<dyn Error + 'static>> {
+    if let Some((hsl, skip)) = join_hsl_tokens(tokens) {
+        return Ok((match_string_color(&hsl)?, skip));
+    }
     // Get 4 numbers
     let mut res = None;
     let mut err_msg = String::with_capacity(1024);
@@ -417,32 +1120,100 @@ pub(super) fn manage_bg_color(
             "Background color does make sense only in general and slide sections."
                 .into(),
         ),
+        General if is_reset(tokens) => {
+            lexer.slideshow.bg_col = Option::None;
+            lexer.slideshow.gradient = Option::None;
+            Ok(1)
+        }
+        // A gradient replaces the color, and the other way around.
+        General if is_word(tokens.first(), "gradient") => {
+            let (g, skip) = get_gradient(tokens)?;
+            check_override(
+                &mut lexer.internals.merged,
+                BACKGROUND,
+                general_background(&lexer.slideshow).as_ref(),
+                &Background::Gradient(g),
+                tokens,
+                lexer.options.strict,
+            )?;
+            lexer.slideshow.bg_col = Option::None;
+            lexer.slideshow.gradient = Some(g);
+            Ok(skip)
+        }
         General => {
             let (c, skip) = get_color(tokens)?;
+            check_override(
+                &mut lexer.internals.merged,
+                BACKGROUND,
+                general_background(&lexer.slideshow).as_ref(),
+                &Background::Color(c),
+                tokens,
+                lexer.options.strict,
+            )?;
             lexer.slideshow.bg_col = Some(c);
+            lexer.slideshow.gradient = Option::None;
+            Ok(skip)
+        }
+        Slide if is_word(tokens.first(), "gradient") => {
+            let (g, skip) = get_gradient(tokens)?;
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                slide.bg_color = Option::None;
+                slide.gradient = Some(g);
+                Ok(())
+            })?;
             Ok(skip)
         }
         Slide => {
             let (c, skip) = get_color(tokens)?;
             apply_slide(&mut lexer.internals.slide, |slide| {
                 slide.bg_color = Some(c);
+                slide.gradient = Option::None;
                 Ok(())
             })?;
             Ok(skip)
         }}
 }
 
-pub(super) fn manage_rotation(
+pub(super) fn manage_section_bg_color(
     lexer: &mut Lexer,
     tokens: &[Token],
 ) -> Result<usize, Box<dyn Error + 'static>> {
     use CurrentState::{Figure, General, Import, None, Slide, Text};
 
     match lexer.internals.state {
-        Import | Slide | Text | General | None => {
-            Err("Rotation does make sense only in a figure section.".into())
+        Import | General | Slide | None => Err(
+            "Section background color does make sense only in text and figure sections."
+                .into(),
+        ),
+        Text | Figure => {
+            let (c, skip) = if is_reset(tokens) {
+                (Option::None, 1)
+            } else {
+                let (c, skip) = get_color(tokens)?;
+                (Some(c), skip)
+            };
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                let last_section = slide.sections.len() - 1;
+                slide.sections[last_section].bg_color = c;
+                Ok(())
+            })?;
+            Ok(skip)
         }
-        Figure => {
+    }
+}
+
+pub(super) fn manage_rotation(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | General | None => Err(
+            "Rotation does make sense only in a figure or a text section."
+                .into(),
+        ),
+        Figure | Text => {
             apply_slide(&mut lexer.internals.slide, |slide| {
                 let v = if let Some(t) = tokens.first() {
                     match t.symbol {
@@ -457,26 +1228,623 @@ pub(super) fn manage_rotation(
                     return Err("Position must have 1 tokens after it".into());
                 };
                 let last_section = slide.sections.len() - 1;
-                if let Some(SectionMain::Figure(figure)) =
-                    &mut slide.sections[last_section].sec_main
-                {
-                    figure.rotation = v;
-                    Ok(())
-                } else {
-                    Err("In a Figure section but the last section is not a figure... How?".into())
+                match &mut slide.sections[last_section].sec_main {
+                    Some(SectionMain::Figure(figure)) => figure.rotation = v,
+                    Some(SectionMain::Text(text)) => text.rotation = v,
+                    Option::None => {
+                        return Err("In a Figure or a Text section but the \
+                                    last section is empty... How?"
+                            .into())
+                    }
                 }
+                Ok(())
             })?;
             Ok(1)
         }
     }
 }
 
-#[cfg(test)]
-mod test {
-    use crate::parser::tokenizer::tokenizer;
-
-    use super::*;
-
+pub(super) fn manage_opacity(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | Text | General | None => {
+            Err("Opacity does make sense only in a figure section.".into())
+        }
+        Figure => {
+            let Some(t) = tokens.first() else {
+                return Err("Opacity must have 1 token after it".into());
+            };
+            let opacity = extract_f32(t)?;
+            if !(0.0..=1.0).contains(&opacity) {
+                let e = format!("Opacity must be between 0 and 1, found {t:?}");
+                return Err(e.into());
+            }
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                let last_section = slide.sections.len() - 1;
+                if let Some(SectionMain::Figure(figure)) =
+                    &mut slide.sections[last_section].sec_main
+                {
+                    figure.opacity = opacity;
+                    Ok(())
+                } else {
+                    Err("In a Figure section but the last section is not a figure... How?".into())
+                }
+            })?;
+            Ok(1)
+        }
+    }
+}
+
+pub(super) fn manage_anchor(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | Text | General | None => {
+            Err("Anchor does make sense only in a figure section.".into())
+        }
+        Figure => {
+            let (Some(x), Some(y)) = (tokens.first(), tokens.get(1)) else {
+                return Err("Anchor must have 2 tokens after it".into());
+            };
+            let anchor = Position {
+                x: extract_f32(x)?,
+                y: extract_f32(y)?,
+            };
+            if !(0.0..=1.0).contains(&anchor.x)
+                || !(0.0..=1.0).contains(&anchor.y)
+            {
+                let e =
+                    format!("Anchor must be between 0 and 1, found {anchor:?}");
+                return Err(e.into());
+            }
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                let last_section = slide.sections.len() - 1;
+                if let Some(SectionMain::Figure(figure)) =
+                    &mut slide.sections[last_section].sec_main
+                {
+                    figure.anchor = Some(anchor.clone());
+                    Ok(())
+                } else {
+                    Err("In a Figure section but the last section is not a figure... How?".into())
+                }
+            })?;
+            Ok(2)
+        }
+    }
+}
+
+pub(super) fn manage_box_width(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | Figure | General | None => {
+            Err("Box width does make sense only in a text section.".into())
+        }
+        Text => {
+            let Some(t) = tokens.first() else {
+                return Err("Box width must have 1 token after it".into());
+            };
+            let width = extract_f32(t)?;
+            if width <= 0.0 {
+                return Err(
+                    format!("Box width must be positive, found {t:?}").into()
+                );
+            }
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                let last_section = slide.sections.len() - 1;
+                if let Some(SectionMain::Text(text)) =
+                    &mut slide.sections[last_section].sec_main
+                {
+                    text.box_width = Some(width);
+                    Ok(())
+                } else {
+                    Err("In a Text section but the last section is not a text... How?".into())
+                }
+            })?;
+            Ok(1)
+        }
+    }
+}
+
+pub(super) fn manage_fit_text(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | Figure | General | None => {
+            Err("Fit text does make sense only in a text section.".into())
+        }
+        Text => {
+            let (Some(w), Some(h)) = (tokens.first(), tokens.get(1)) else {
+                return Err("Fit text must have 2 tokens after it".into());
+            };
+            let fit = Size {
+                w: extract_f32(w)?,
+                h: extract_f32(h)?,
+            };
+            if fit.w <= 0.0 || fit.h <= 0.0 {
+                let e = format!("Fit text must be positive, found {fit:?}");
+                return Err(e.into());
+            }
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                let last_section = slide.sections.len() - 1;
+                if let Some(SectionMain::Text(text)) =
+                    &mut slide.sections[last_section].sec_main
+                {
+                    text.fit = Some(fit.clone());
+                    Ok(())
+                } else {
+                    Err("In a Text section but the last section is not a text... How?".into())
+                }
+            })?;
+            Ok(2)
+        }
+    }
+}
+
+pub(super) fn manage_vcenter(
+    lexer: &mut Lexer,
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | Figure | General | None => {
+            Err("Vcenter does make sense only in a text section.".into())
+        }
+        Text => {
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                let last_section = slide.sections.len() - 1;
+                if let Some(SectionMain::Text(text)) =
+                    &mut slide.sections[last_section].sec_main
+                {
+                    text.vcenter = true;
+                    Ok(())
+                } else {
+                    Err("In a Text section but the last section is not a text... How?".into())
+                }
+            })?;
+            Ok(0)
+        }
+    }
+}
+
+pub(super) fn manage_columns(
+    lexer: &mut Lexer,
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | Figure | General | None => {
+            Err("Columns do make sense only in a text section.".into())
+        }
+        Text => {
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                let last_section = slide.sections.len() - 1;
+                match &mut slide.sections[last_section].sec_main {
+                    Some(SectionMain::Text(text)) if text.is_code => {
+                        Err("Columns can't be used in a code section.".into())
+                    }
+                    Some(SectionMain::Text(text)) => {
+                        text.set_columns();
+                        Ok(())
+                    }
+                    _ => Err("In a Text section but the last section is not a text... How?".into()),
+                }
+            })?;
+            Ok(0)
+        }
+    }
+}
+
+pub(super) fn manage_line_height(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | Figure | General | None => {
+            Err("Line height does make sense only in a text section.".into())
+        }
+        Text => {
+            let Some(t) = tokens.first() else {
+                return Err("Line height must have 1 token after it".into());
+            };
+            let height = extract_f32(t)?;
+            if height <= 0.0 {
+                let e = format!("Line height must be positive, found {t:?}");
+                return Err(e.into());
+            }
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                let last_section = slide.sections.len() - 1;
+                if let Some(SectionMain::Text(text)) =
+                    &mut slide.sections[last_section].sec_main
+                {
+                    text.line_height = Some(height);
+                    Ok(())
+                } else {
+                    Err("In a Text section but the last section is not a text... How?".into())
+                }
+            })?;
+            Ok(1)
+        }
+    }
+}
+
+pub(super) fn manage_tab_width(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | Figure | Text | None => {
+            Err("Tab width does make sense only in the general section.".into())
+        }
+        General => {
+            let Some(t) = tokens.first() else {
+                return Err("Tab width must have 1 token after it".into());
+            };
+            let width = extract_u8(t)?;
+            if width == 0 {
+                return Err("Tab width must be at least 1".into());
+            }
+            lexer.internals.tab_width = Some(width.into());
+            Ok(1)
+        }
+    }
+}
+
+pub(super) fn manage_trim(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | Figure | Text | None => {
+            Err("Trimming does make sense only in the general section.".into())
+        }
+        General => {
+            let mode = match tokens.first().map(|t| &t.symbol) {
+                Some(Structure::String("off")) => TrimMode::Off,
+                Some(Structure::String("on")) => TrimMode::Trailing,
+                Some(Structure::String("all")) => TrimMode::Both,
+                t => {
+                    let e = format!("Trim must be on, all or off, found {t:?}");
+                    return Err(e.into());
+                }
+            };
+            lexer.internals.trim = mode;
+            Ok(1)
+        }
+    }
+}
+
+pub(super) fn manage_define(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | Figure | Text | None => {
+            Err("Definitions do make sense only in the general section.".into())
+        }
+        General => {
+            let (Some(name), Some(value)) = (tokens.first(), tokens.get(1))
+            else {
+                return Err(
+                    "A definition must be like `:def NAME value`".into()
+                );
+            };
+            let Structure::String(name) = name.symbol else {
+                let e =
+                    format!("A variable's name must be a word, found {name:?}");
+                return Err(e.into());
+            };
+            let value = match value.symbol {
+                Structure::String(value) => value.to_string(),
+                Structure::Number(value) => value.to_string(),
+                _ => {
+                    let e =
+                        format!("Expect the variable's value, found {value:?}");
+                    return Err(e.into());
+                }
+            };
+            lexer.variables.insert(name.to_string(), value);
+            Ok(2)
+        }
+    }
+}
+
+/// Read a value made of all the words up to the next token, that can be
+/// quoted, like `"Rust 2.0"`: get it, and how many tokens it took.
+fn read_words(tokens: &[Token]) -> Option<(String, usize)> {
+    let words: Vec<&str> = tokens
+        .iter()
+        .map_while(|t| match t.symbol {
+            Structure::String(word) => Some(word),
+            _ => Option::None,
+        })
+        .collect();
+    if words.is_empty() {
+        return Option::None;
+    }
+    let value = words.join(" ");
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(&value)
+        .to_string();
+    Some((value, words.len()))
+}
+
+pub(super) fn manage_caption(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | Text | General | None => {
+            Err("Caption does make sense only in a figure section.".into())
+        }
+        Figure => {
+            let Some((caption, words)) = read_words(tokens) else {
+                return Err("Caption must have some text after it".into());
+            };
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                let last_section = slide.sections.len() - 1;
+                if let Some(SectionMain::Figure(figure)) =
+                    &mut slide.sections[last_section].sec_main
+                {
+                    figure.caption = Some(caption.clone());
+                    Ok(())
+                } else {
+                    Err("In a Figure section but the last section is not a figure... How?".into())
+                }
+            })?;
+            Ok(words)
+        }
+    }
+}
+
+pub(super) fn manage_meta(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | Figure | Text | None => {
+            Err("Metadata do make sense only in the general section.".into())
+        }
+        General => {
+            let Some(Structure::String(key)) =
+                tokens.first().map(|t| &t.symbol)
+            else {
+                return Err("Metadata must be like `:meta key value`".into());
+            };
+            let Some((value, words)) = read_words(&tokens[1..]) else {
+                return Err(format!("The metadata {key} has no value").into());
+            };
+            lexer.slideshow.metadata.insert((*key).to_string(), value);
+            Ok(words + 1)
+        }
+    }
+}
+
+pub(super) fn manage_order(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | General | None => {
+            Err("Order does make sense only in a slide.".into())
+        }
+        Slide | Figure | Text => {
+            let Some(t) = tokens.first() else {
+                return Err("Order must have 1 token after it".into());
+            };
+            let order = extract_f32(t)?;
+            if order.fract() != 0.0 || order.abs() > 1e6 {
+                let e = format!("Order must be an integer, found {t:?}");
+                return Err(e.into());
+            }
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                #[allow(clippy::cast_possible_truncation)]
+                let order = order as i32;
+                slide.order = order;
+                Ok(())
+            })?;
+            Ok(1)
+        }
+    }
+}
+
+pub(super) fn manage_group(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | General | None => {
+            Err("Group does make sense only in a slide.".into())
+        }
+        Slide | Figure | Text => {
+            let group = match tokens.first().map(|t| &t.symbol) {
+                Some(Structure::String(group)) => (*group).to_string(),
+                Some(Structure::Number(group)) => group.to_string(),
+                _ => return Err("Group must have a name after it".into()),
+            };
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                slide.group = Some(group.clone());
+                Ok(())
+            })?;
+            Ok(1)
+        }
+    }
+}
+
+pub(super) fn manage_grid(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | General | None => {
+            Err("Grid does make sense only in a slide.".into())
+        }
+        Slide | Figure | Text => {
+            let (Some(rows), Some(cols)) = (tokens.first(), tokens.get(1))
+            else {
+                return Err("Grid must have 2 tokens after it".into());
+            };
+            let count = |t: &Token| -> Result<u32, Box<dyn Error>> {
+                let n = extract_f32(t)?;
+                if n.fract() != 0.0 || !(1.0..=MAX_GRID).contains(&n) {
+                    let e = format!(
+                        "Grid must have from 1 to {MAX_GRID} rows and \
+                         columns, found {t:?}"
+                    );
+                    return Err(e.into());
+                }
+                #[allow(clippy::cast_possible_truncation)]
+                #[allow(clippy::cast_sign_loss)]
+                Ok(n as u32)
+            };
+            let grid = (count(rows)?, count(cols)?);
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                slide.grid = Some(grid);
+                Ok(())
+            })?;
+            Ok(2)
+        }
+    }
+}
+
+pub(super) fn manage_tag(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    let tags: Vec<String> = tokens
+        .iter()
+        .map_while(|t| match t.symbol {
+            Structure::String(tag) => Some(tag.to_string()),
+            _ => Option::None,
+        })
+        .collect();
+    match lexer.internals.state {
+        Import | General | None => {
+            Err("Tag does make sense only in a slide.".into())
+        }
+        _ if tags.is_empty() => Err("Tag must have a profile after it".into()),
+        // Right after `:sl`, the whole slide is tagged.
+        Slide => {
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                slide.tags.extend(tags.iter().cloned());
+                Ok(())
+            })?;
+            Ok(tags.len())
+        }
+        Figure | Text => {
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                let Some(section) = slide.sections.last_mut() else {
+                    return Err(
+                        "In a section but there are no sections... How?".into(),
+                    );
+                };
+                section.tags.extend(tags.iter().cloned());
+                Ok(())
+            })?;
+            Ok(tags.len())
+        }
+    }
+}
+
+/// The most rows, or columns, a grid can have.
+const MAX_GRID: f32 = 64.0;
+
+pub(super) fn manage_no_count(
+    lexer: &mut Lexer,
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | General | None => {
+            Err("Nocount does make sense only in a slide.".into())
+        }
+        Slide | Figure | Text => {
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                slide.counted = false;
+                Ok(())
+            })?;
+            Ok(0)
+        }
+    }
+}
+
+pub(super) fn manage_visibility(
+    lexer: &mut Lexer,
+    tokens: &[Token],
+) -> Result<usize, Box<dyn Error + 'static>> {
+    use CurrentState::{Figure, General, Import, None, Slide, Text};
+
+    match lexer.internals.state {
+        Import | Slide | General | None => {
+            Err("Visibility does make sense only for text and figures.".into())
+        }
+        Text | Figure => {
+            let Some(Structure::String(v)) = tokens.first().map(|t| &t.symbol)
+            else {
+                return Err("Visibility must be presenter, main or both".into());
+            };
+            let visibility = match v.to_lowercase().as_str() {
+                "both" => Visibility::Both,
+                "main" => Visibility::Main,
+                "presenter" => Visibility::Presenter,
+                _ => {
+                    let e = format!(
+                        "Visibility must be presenter, main or both, found {v}"
+                    );
+                    return Err(e.into());
+                }
+            };
+            apply_slide(&mut lexer.internals.slide, |slide| {
+                let last_section = slide.sections.len() - 1;
+                slide.sections[last_section].visibility = visibility;
+                Ok(())
+            })?;
+            Ok(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::tokenizer::tokenizer;
+
+    use super::*;
+
     #[test]
     fn get_color_ok() {
         let tokens = tokenizer(":cl 0 23 2 42");
@@ -513,6 +1881,95 @@ mod test {
         assert_eq!(c.a, 255, "{c:?}");
     }
 
+    #[test]
+    fn get_color_hsl() {
+        let teal = Color::from((0x33, 0x77, 0x99, 0xff));
+        for text in [
+            ":cl hsl(200, 50%, 40%)",
+            ":cl hsl(200,50%,40%)",
+            ":cl HSL( 200 , 50% , 40% )",
+        ] {
+            let tokens = tokenizer(text);
+            let c = get_color(&tokens[1..]);
+            assert_eq!(c.unwrap(), (teal, tokens.len() - 1), "{text}");
+        }
+        let tokens = tokenizer(":cl hsla(0, 100%, 50%, 0.5) :sz 2");
+        let c = get_color(&tokens[1..]).unwrap();
+        assert_eq!(c, (Color::from((0xff, 0x00, 0x00, 0x80)), 4));
+
+        for text in [
+            ":cl hsl(200, 50%)",
+            ":cl hsl(200, 150%, 40%)",
+            ":cl hsla(0, 1%, 1%, 2)",
+        ] {
+            let tokens = tokenizer(text);
+            assert!(get_color(&tokens[1..]).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn get_color_alpha() {
+        let tokens = tokenizer(":cl red alpha 0.5");
+        let c = get_color(&tokens[1..]).unwrap();
+        assert_eq!(c, (Color::from((0xff, 0x00, 0x00, 128)), 3));
+        let tokens = tokenizer(":cl 1 2 3 4 alpha(1)");
+        let c = get_color(&tokens[1..]).unwrap();
+        assert_eq!(c, (Color::from((1, 2, 3, 255)), 5));
+        let tokens = tokenizer(":cl red alpha 2");
+        assert!(get_color(&tokens[1..]).is_err());
+    }
+
+    #[test]
+    fn slide_copy() {
+        let p = Path::new("");
+        let texts = |inp| {
+            let slides = crate::parser::parse_text(inp, p).unwrap();
+            let texts: Vec<Vec<_>> = slides
+                .slides
+                .iter()
+                .map(|s| {
+                    s.sections
+                        .iter()
+                        .filter_map(|s| match &s.sec_main {
+                            Some(SectionMain::Text(t)) => Some(t.text.clone()),
+                            _ => None,
+                        })
+                        .collect()
+                })
+                .collect();
+            texts
+        };
+        assert_eq!(
+            texts(":sl :bc red\n:tb\nOne\n:sl+\n:tb\nTwo\n:sl+\n:tb\nThree"),
+            [
+                vec!["One\n"],
+                vec!["One\n", "Two\n"],
+                vec!["One\n", "Two\n", "Three\n"]
+            ]
+        );
+        // Only the new slide changes.
+        let slides =
+            crate::parser::parse_text(":sl\n:tb\nOne\n:sl+ :bc red", p)
+                .unwrap();
+        assert_eq!(slides.slides[0].bg_color, None);
+        assert!(slides.slides[1].bg_color.is_some());
+        // A copy of nothing is an empty slide.
+        assert_eq!(texts(":sl+\n:sl\n:sl+"), vec![Vec::<String>::new(); 3]);
+    }
+
+    #[test]
+    fn no_count() {
+        let p = Path::new("");
+        let slides =
+            crate::parser::parse_text(":sl :nocount\n:sl\n:tb :nocount", p)
+                .unwrap();
+        let counted: Vec<_> = slides.slides.iter().map(|s| s.counted).collect();
+        assert_eq!(counted, [false, false]);
+        let slides = crate::parser::parse_text(":sl\n:sl", p).unwrap();
+        assert!(slides.slides.iter().all(|s| s.counted));
+        assert!(crate::parser::parse_text(":ge :nocount", p).is_err());
+    }
+
     #[test]
     fn get_color_ko() {
         let tokens = tokenizer(":cl pinka");
@@ -532,4 +1989,897 @@ mod test {
         let c = get_color(&tokens[1..]);
         assert!(c.is_err(), "{c:?}");
     }
+
+    #[test]
+    fn general_reset_to_default() {
+        let p = Path::new("");
+        let slides = crate::parser::parse_text(
+            ":ge :fc red :bc blue :sz 20\n:ge :fc default :bc none :sz default",
+            p,
+        )
+        .unwrap();
+        assert_eq!(slides.font_col, None);
+        assert_eq!(slides.bg_col, None);
+        assert_eq!(slides.font_size, None);
+    }
+
+    #[test]
+    fn general_reset_only_the_given_one() {
+        let p = Path::new("");
+        let slides =
+            crate::parser::parse_text(":ge :fc red :bc blue\n:ge :bc NONE", p)
+                .unwrap();
+        assert_eq!(slides.font_col, Some((0xff, 0x00, 0x00, 0xff).into()));
+        assert_eq!(slides.bg_col, None);
+    }
+
+    #[test]
+    fn reset_is_only_for_general() {
+        let p = Path::new("");
+        let slides = crate::parser::parse_text(":sl :tb :fc default", p);
+        assert!(slides.is_err(), "{slides:?}");
+    }
+
+    /// Get the position of the first section of the first slide.
+    fn first_position(text: &str) -> Option<Position> {
+        let p = Path::new("");
+        let mut slides = crate::parser::parse_text(text, p).unwrap();
+        slides.slides.remove(0).sections.remove(0).position
+    }
+
+    /// Get the coordinates given to the first section of the first slide.
+    fn first_coordinates(text: &str) -> (Option<f32>, Option<f32>) {
+        let p = Path::new("");
+        let mut slides = crate::parser::parse_text(text, p).unwrap();
+        slides.slides.remove(0).sections.remove(0).coordinates()
+    }
+
+    #[test]
+    fn position_both_coordinates() {
+        let pos = first_position(":sl :tb :ps 0.3 0.4");
+        assert_eq!(pos, Some(Position { x: 0.3, y: 0.4 }));
+    }
+
+    #[test]
+    fn position_single_axis() {
+        let pos = first_coordinates(":sl :tb :ps x 0.3");
+        assert_eq!(pos, (Some(0.3), None));
+        let pos = first_coordinates(":sl :tb :ps y 0.5");
+        assert_eq!(pos, (None, Some(0.5)));
+        let pos = first_coordinates(":sl :tb :ps _ 0.5");
+        assert_eq!(pos, (None, Some(0.5)));
+        let pos = first_coordinates(":sl :tb :ps x 0.3 :ps y 0.5");
+        assert_eq!(pos, (Some(0.3), Some(0.5)));
+    }
+
+    #[test]
+    fn position_keeps_the_current_value() {
+        let pos = first_position(":sl :tb :ps 0.3 0.4 :ps _ 0.7");
+        assert_eq!(pos, Some(Position { x: 0.3, y: 0.7 }));
+        let pos = first_position(":sl :tb :ps 0.3 0.4 :ps y 0.1 :ps x 0.2");
+        assert_eq!(pos, Some(Position { x: 0.2, y: 0.1 }));
+    }
+
+    #[test]
+    fn position_ko() {
+        let p = Path::new("");
+        assert!(crate::parser::parse_text(":sl :tb :ps z 0.3", p).is_err());
+        assert!(crate::parser::parse_text(":sl :tb :ps 0.3", p).is_err());
+    }
+
+    #[test]
+    fn box_width() {
+        let p = Path::new("");
+        let mut slides =
+            crate::parser::parse_text(":sl :tb :bw 0.4\nsome text", p).unwrap();
+        let section = slides.slides.remove(0).sections.remove(0);
+        let Some(SectionMain::Text(text)) = section.sec_main else {
+            panic!("The section must be a text.");
+        };
+        assert_eq!(text.box_width, Some(0.4));
+        assert_eq!(text.text, "some text\n");
+    }
+
+    #[test]
+    fn box_width_ko() {
+        let p = Path::new("");
+        assert!(crate::parser::parse_text(":sl :tb :bw -1", p).is_err());
+        assert!(crate::parser::parse_text(":sl :tb :bw wide", p).is_err());
+        assert!(crate::parser::parse_text(":ge :bw 0.4", p).is_err());
+    }
+
+    #[test]
+    fn fit_text() {
+        let p = Path::new("");
+        let text = ":sl :tb :ps 0.1 0.1 :fit-text 0.8 0.2 :sz 0.1\nA title";
+        let mut slides = crate::parser::parse_text(text, p).unwrap();
+        let section = slides.slides.remove(0).sections.remove(0);
+        let Some(SectionMain::Text(text)) = section.sec_main else {
+            panic!("The section must be a text.");
+        };
+        assert_eq!(text.fit, Some(Size { w: 0.8, h: 0.2 }));
+        assert_eq!(text.text, "A title\n");
+
+        assert!(crate::parser::parse_text(":sl :tb :fit-text 0.8", p).is_err());
+        assert!(crate::parser::parse_text(":sl :tb :fit-text 0 1", p).is_err());
+        assert!(crate::parser::parse_text(
+            ":sl :fg resources/star.jpg :fit-text 1 1",
+            p
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn caption() {
+        let p = Path::new("");
+        let text = ":sl :fg resources/star.jpg :cap \"Fig 1\" :rt 10\n\
+                    :fg resources/star.jpg :cap The star, 2.0\n\
+                    :fg resources/star.jpg";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let captions: Vec<_> = slides.slides[0]
+            .figures()
+            .map(|fig| fig.caption.as_deref())
+            .collect();
+        assert_eq!(captions, [Some("Fig 1"), Some("The star, 2.0"), None]);
+        let fig = slides.slides[0].figures().next().unwrap();
+        assert!((fig.rotation - 10.0).abs() < f32::EPSILON);
+
+        // The words of the caption need not be ASCII.
+        let text = ":sl\n:fg resources/star.jpg :cap Café au lait :rt 5";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let fig = slides.slides[0].figures().next().unwrap();
+        assert_eq!(fig.caption.as_deref(), Some("Café au lait"));
+        assert!((fig.rotation - 5.0).abs() < f32::EPSILON);
+
+        assert!(crate::parser::parse_text(":sl :tb :cap Hi", p).is_err());
+        let no_text = ":sl :fg resources/star.jpg :cap :rt 10";
+        assert!(crate::parser::parse_text(no_text, p).is_err());
+    }
+
+    #[test]
+    fn code() {
+        let p = Path::new("");
+        let inp =
+            ":sl\n:code rust :ps 0.1 0.1\nfn main() {}\n:code\n  x\n:tb\ny";
+        let mut slides = crate::parser::parse_text(inp, p).unwrap();
+        let texts: Vec<_> = slides
+            .slides
+            .remove(0)
+            .sections
+            .into_iter()
+            .map(|s| match s.sec_main {
+                Some(SectionMain::Text(text)) => {
+                    (text.is_code, text.lang, text.text)
+                }
+                _ => panic!("The section must be a text."),
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                (true, Some("rust".to_string()), "fn main() {}\n".to_string()),
+                (true, None, "  x\n".to_string()),
+                (false, None, "y\n".to_string()),
+            ]
+        );
+        assert!(crate::parser::parse_text(":code rust", p).is_err());
+    }
+
+    #[test]
+    fn vcenter() {
+        let p = Path::new("");
+        let mut slides =
+            crate::parser::parse_text(":sl :tb :vcenter\nTitle\n:tb\nNot", p)
+                .unwrap();
+        let vcenter: Vec<_> = slides
+            .slides
+            .remove(0)
+            .sections
+            .into_iter()
+            .map(|s| match s.sec_main {
+                Some(SectionMain::Text(text)) => text.vcenter,
+                _ => panic!("The section must be a text."),
+            })
+            .collect();
+        assert_eq!(vcenter, [true, false]);
+        assert!(crate::parser::parse_text(":sl :vcenter", p).is_err());
+        let resources = Path::new("./resources");
+        let figure = ":sl :fg star.jpg :vcenter";
+        assert!(crate::parser::parse_text(figure, resources).is_err());
+    }
+
+    #[test]
+    fn line_height() {
+        let p = Path::new("");
+        let mut slides =
+            crate::parser::parse_text(":sl :tb :lh 1.5\nsome text", p).unwrap();
+        let section = slides.slides.remove(0).sections.remove(0);
+        let Some(SectionMain::Text(text)) = section.sec_main else {
+            panic!("The section must be a text.");
+        };
+        assert_eq!(text.line_height, Some(1.5));
+        assert!(crate::parser::parse_text(":sl :tb :lh 0", p).is_err());
+        assert!(crate::parser::parse_text(
+            ":sl :fg resources/star.jpg :lh 2",
+            p
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn line_breaks() {
+        let p = Path::new("");
+        let text = ":sl\n:tb\nA line \\\nTwo \\ lines\n\\\nC\\:\\temp\n\
+                    :code\nlet s = \"a \\ b\"; \\";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let texts: Vec<&str> = slides.slides[0]
+            .sections
+            .iter()
+            .filter_map(|s| match &s.sec_main {
+                Some(SectionMain::Text(text)) => Some(text.text.as_str()),
+                _ => Option::None,
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                "A line\nTwo \\ lines\n\nC:\\temp\n",
+                "let s = \"a \\ b\"; \\\n"
+            ]
+        );
+    }
+
+    #[test]
+    fn opacity() {
+        let p = Path::new("");
+        let text = ":sl :fg resources/star.jpg :op 0.3\n:fg resources/star.jpg";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let opacities: Vec<f32> = slides.slides[0]
+            .sections
+            .iter()
+            .filter_map(|s| match &s.sec_main {
+                Some(SectionMain::Figure(fig)) => Some(fig.opacity),
+                _ => Option::None,
+            })
+            .collect();
+        assert_eq!(opacities, vec![0.3, 1.0]);
+        assert!(crate::parser::parse_text(
+            ":sl :fg resources/star.jpg :op 1.5",
+            p
+        )
+        .is_err());
+        assert!(crate::parser::parse_text(":sl :tb :op 0.5", p).is_err());
+    }
+
+    #[test]
+    fn relative_figure() {
+        let base = Path::new("resources");
+        let slides =
+            crate::parser::parse_text(":sl :fg star.jpg", base).unwrap();
+        let Some(SectionMain::Figure(fig)) =
+            &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("The section must be a figure.");
+        };
+        // The path is kept as it is written, and found from the slides.
+        assert_eq!(fig.path, "star.jpg");
+        let star = base.join("star.jpg").canonicalize().unwrap();
+        assert_eq!(slides.resolve_path(&fig.path), star.to_string_lossy());
+        assert!(crate::parser::parse_text(":sl :fg missing.jpg", base).is_err());
+
+        // The figures of an imported file are relative to that file.
+        let dir = std::env::temp_dir().join("slidy_test_relative_figure");
+        let sub = dir.join("sub");
+        std::fs::create_dir_all(&sub).unwrap();
+        std::fs::copy(base.join("star.jpg"), sub.join("star.jpg")).unwrap();
+        std::fs::write(
+            sub.join("more.txt"),
+            ":sl\n:fg star.jpg\n:tb\nA ![star](star.jpg) \\![x](y)\n",
+        )
+        .unwrap();
+        let slides = crate::parser::parse_text(":im sub/more.txt", &dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let slides = slides.unwrap();
+        let Some(SectionMain::Figure(fig)) =
+            &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("The section must be a figure.");
+        };
+        assert_eq!(Path::new(&fig.path), Path::new("sub/star.jpg"));
+        let text: Vec<_> = slides.slides[0].texts().collect();
+        let star = Path::new("sub").join("star.jpg");
+        assert_eq!(
+            text[0].text,
+            format!("A ![star]({}) \\![x](y)\n", star.display())
+        );
+    }
+
+    #[test]
+    fn imported_title_slide() {
+        let dir = std::env::temp_dir().join("slidy_test_imported_title");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("inc.txt"),
+            ":ge :meta author Jane Doe\n:title\nPart Two\n",
+        )
+        .unwrap();
+        let text = ":ge :meta author Jane Doe\n:im inc.txt";
+        let slides = crate::parser::parse_text(text, &dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let slides = slides.unwrap();
+        // The title and the author, once.
+        let texts: Vec<_> =
+            slides.slides[0].texts().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["Part Two\n", "Jane Doe\n"]);
+    }
+
+    #[test]
+    fn imported_fonts() {
+        let dir = std::env::temp_dir().join("slidy_test_imported_fonts");
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(
+            dir.join("sub").join("inc.json"),
+            r#"{"slides": [], "fonts": {"mono": "mono.ttf"}}"#,
+        )
+        .unwrap();
+        let options = crate::parser::ParseOptions {
+            merge_imported_defaults: true,
+            ..crate::parser::ParseOptions::default()
+        };
+        let slides = crate::parser::parse_text_with_options(
+            ":im sub/inc.json",
+            &dir,
+            &options,
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+        // The font is found from the importing file's folder.
+        let font = Path::new("sub").join("mono.ttf");
+        let fonts = slides.unwrap().fonts;
+        assert_eq!(fonts.get("mono"), Some(&font.display().to_string()));
+    }
+
+    #[test]
+    fn title_slide() {
+        let p = Path::new("");
+        let slides = crate::parser::parse_text(":title\nMy Talk\n", p).unwrap();
+        let slide = &slides.slides[0];
+        // The layout is applied, and done.
+        assert_eq!(slide.layout, None);
+        assert_eq!(slide.sections.len(), 1);
+        let title = &slide.sections[0];
+        assert_eq!(title.size, Some(crate::slideshow::TITLE_SIZE));
+        // 7 letters, 0.04 wide, in the middle of the slide.
+        let position = title.position.as_ref().unwrap();
+        assert!((position.x - 0.36).abs() < 1e-6, "{position:?}");
+        assert!((position.y - 0.42).abs() < 1e-6, "{position:?}");
+
+        let slides = crate::parser::parse_text(
+            ":ge :meta author Jane Doe\n:title\nMy Talk\n:sl\n:tb\nNext",
+            p,
+        )
+        .unwrap();
+        let slide = &slides.slides[0];
+        assert_eq!(slide.sections.len(), 2);
+        let Some(SectionMain::Text(author)) = &slide.sections[1].sec_main
+        else {
+            panic!("Not a text section");
+        };
+        assert_eq!(author.text, "Jane Doe\n");
+        let (title, author) = (&slide.sections[0], &slide.sections[1]);
+        let below = |s: &Section| s.position.as_ref().unwrap().y;
+        assert!(below(author) > below(title));
+        // The other slides are left alone.
+        assert!(slides.slides[1].sections[0].position.is_none());
+
+        // Laying it out again changes nothing.
+        let mut again = slide.clone();
+        again.apply_layout(&slides.metadata);
+        assert_eq!(&again, slide);
+    }
+
+    #[test]
+    fn text_rotation() {
+        let p = Path::new("");
+        let slides = crate::parser::parse_text(
+            ":sl :tb :rt 45\nTurned\n:tb\nStraight",
+            p,
+        )
+        .unwrap();
+        let rotations: Vec<_> = slides.slides[0]
+            .sections
+            .iter()
+            .filter_map(|s| match &s.sec_main {
+                Some(SectionMain::Text(text)) => Some(text.rotation),
+                _ => Option::None,
+            })
+            .collect();
+        assert_eq!(rotations, vec![45.0, 0.0]);
+        assert!(crate::parser::parse_text(":sl :rt 45", p).is_err());
+    }
+
+    #[test]
+    fn anchor() {
+        let p = Path::new("");
+        let text = ":sl :fg resources/star.jpg :anchor 0 1 :rt 45\n\
+                    :fg resources/star.jpg";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let anchors: Vec<_> = slides.slides[0]
+            .sections
+            .iter()
+            .filter_map(|s| match &s.sec_main {
+                Some(SectionMain::Figure(fig)) => Some(fig.anchor.clone()),
+                _ => Option::None,
+            })
+            .collect();
+        assert_eq!(anchors, vec![Some(Position { x: 0.0, y: 1.0 }), None]);
+        assert!(crate::parser::parse_text(
+            ":sl :fg resources/star.jpg :anchor 0.5 1.5",
+            p
+        )
+        .is_err());
+        assert!(crate::parser::parse_text(
+            ":sl :fg resources/star.jpg :anchor 0.5",
+            p
+        )
+        .is_err());
+        assert!(crate::parser::parse_text(":sl :tb :anchor 0 0", p).is_err());
+    }
+
+    #[test]
+    fn sequence() {
+        let p = Path::new("");
+        let star = "resources/star.jpg";
+        let text = ":sl :fg resources/star.jpg resources/star.jpg :frame 200";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let Some(SectionMain::Figure(fig)) =
+            &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("The section must be a figure.");
+        };
+        assert_eq!(fig.path, star);
+        assert_eq!(fig.paths, vec![star, star]);
+        assert_eq!(fig.frame_ms, 200);
+
+        // A single image is not a sequence.
+        let text = ":sl :fg resources/star.jpg :ps 0.1 0.1";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let Some(SectionMain::Figure(fig)) =
+            &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("The section must be a figure.");
+        };
+        assert!(!fig.is_sequence());
+
+        let text = ":sl :fg resources/star.jpg :frame 0";
+        assert!(crate::parser::parse_text(text, p).is_err());
+        assert!(crate::parser::parse_text(":sl :tb :frame 10", p).is_err());
+    }
+
+    #[test]
+    fn section_bg_color() {
+        let p = Path::new("");
+        let text = ":sl :tb :sbc yellow\nA callout\n:fg resources/star.jpg :sbc 0 0 255 128\n:tb\nplain";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let colors: Vec<_> = slides.slides[0]
+            .sections
+            .iter()
+            .map(|s| s.bg_color)
+            .collect();
+        assert_eq!(
+            colors,
+            vec![
+                Some((0xff, 0xff, 0x00, 0xff).into()),
+                Some((0x00, 0x00, 0xff, 0x80).into()),
+                Option::None
+            ]
+        );
+        assert!(crate::parser::parse_text(":sl :sbc yellow", p).is_err());
+        assert!(crate::parser::parse_text(":ge :sbc yellow", p).is_err());
+    }
+
+    #[test]
+    fn trim() {
+        let p = Path::new("");
+        let text_of = |input: &str| {
+            let mut slides = crate::parser::parse_text(input, p).unwrap();
+            let section = slides.slides.remove(0).sections.remove(0);
+            let Some(SectionMain::Text(text)) = section.sec_main else {
+                panic!("The section must be a text.");
+            };
+            text.text
+        };
+        let lines = ":sl :tb\n  some text  \n";
+        assert_eq!(text_of(lines), "  some text  \n");
+        assert_eq!(
+            text_of(&format!(":ge :trim off\n{lines}")),
+            "  some text  \n"
+        );
+        assert_eq!(text_of(&format!(":ge :trim on\n{lines}")), "  some text\n");
+        assert_eq!(text_of(&format!(":ge :trim all\n{lines}")), "some text\n");
+        assert!(crate::parser::parse_text(":ge :trim maybe", p).is_err());
+        assert!(crate::parser::parse_text(":sl :tb :trim on", p).is_err());
+    }
+
+    #[test]
+    fn tabs() {
+        assert_eq!(expand_tabs("\tab", 4), "    ab");
+        assert_eq!(expand_tabs("ab\tc", 4), "ab  c");
+        assert_eq!(expand_tabs("abcd\te\t", 4), "abcd    e   ");
+        assert_eq!(expand_tabs("a\tb", 8), "a       b");
+
+        let p = Path::new("");
+        let text = ":sl :tb\nkey\tvalue\n\tindented";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let Some(SectionMain::Text(t)) = &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("The section must be a text.");
+        };
+        assert_eq!(t.text, "key value\n    indented\n");
+
+        let text = ":ge :tw 2\n:sl :tb\na\tb";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let Some(SectionMain::Text(t)) = &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("The section must be a text.");
+        };
+        assert_eq!(t.text, "a b\n");
+
+        assert!(crate::parser::parse_text(":ge :tw 0", p).is_err());
+        assert!(crate::parser::parse_text(":sl :tw 2", p).is_err());
+    }
+
+    #[test]
+    fn define() {
+        let p = Path::new("");
+        let text = ":ge :def VER 1.2 :def DATE 2024-05\n:sl :tb\nVersion ${VER}\n${DATE}, v${VER}";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let Some(SectionMain::Text(t)) = &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("The section must be a text.");
+        };
+        assert_eq!(t.text, "Version 1.2\n2024-05, v1.2\n");
+
+        // Values that look like numbers are kept as they are written.
+        let text = ":ge :def VER 1.10\n:sl :tb\n${VER}";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let Some(SectionMain::Text(t)) = &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("The section must be a text.");
+        };
+        assert_eq!(t.text, "1.10\n");
+
+        // So are the values after a name that is not ASCII.
+        let text = ":ge :def ÉÉ 1.50 :def Año 2024\n:sl :tb\n${ÉÉ} ${Año}";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let Some(SectionMain::Text(t)) = &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("The section must be a text.");
+        };
+        assert_eq!(t.text, "1.50 2024\n");
+
+        assert!(crate::parser::parse_text(":ge :def VER", p).is_err());
+        assert!(crate::parser::parse_text(":sl :def VER 1", p).is_err());
+    }
+
+    #[test]
+    fn meta() {
+        let p = Path::new("");
+        let text = ":ge :meta title \"Rust 2.0, at last\"\n\
+                    :ge :meta author Jane Doe\n\
+                    :ge :meta date 2024-05-01 :bc red\n\
+                    :sl :tb\nHi";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let meta = |key: &str| slides.metadata.get(key).map(String::as_str);
+        assert_eq!(meta("title"), Some("Rust 2.0, at last"));
+        assert_eq!(meta("author"), Some("Jane Doe"));
+        assert_eq!(meta("date"), Some("2024-05-01"));
+        assert!(slides.bg_col.is_some());
+
+        // Neither the keys nor the values need be ASCII.
+        let text = ":ge :meta author José Pérez\n\
+                    :ge :meta titleé 2024\n\
+                    :ge :meta ÉÉ 2024 :fc red\n\
+                    :sl :tb\nHi";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let meta = |key: &str| slides.metadata.get(key).map(String::as_str);
+        assert_eq!(meta("author"), Some("José Pérez"));
+        assert_eq!(meta("titleé"), Some("2024"));
+        assert_eq!(meta("ÉÉ"), Some("2024"));
+        assert!(slides.font_col.is_some());
+
+        assert!(crate::parser::parse_text(":ge :meta title", p).is_err());
+        assert!(crate::parser::parse_text(":ge :meta", p).is_err());
+        assert!(crate::parser::parse_text(":sl :meta title Hi", p).is_err());
+    }
+
+    #[test]
+    fn undefined_variables() {
+        let vars = HashMap::from([("A".to_string(), "1".to_string())]);
+        let line = "${A} ${B} ${A";
+        assert_eq!(
+            substitute_variables(line, &vars, false).unwrap(),
+            "1 ${B} ${A"
+        );
+        assert!(substitute_variables(line, &vars, true).is_err());
+
+        let p = Path::new("");
+        let text = ":sl :tb\n${NOPE}";
+        let strict = crate::parser::ParseOptions {
+            strict_vars: true,
+            ..crate::parser::ParseOptions::default()
+        };
+        assert!(crate::parser::parse_text(text, p).is_ok());
+        assert!(
+            crate::parser::parse_text_with_options(text, p, &strict).is_err()
+        );
+    }
+
+    #[test]
+    fn embedded_figure() {
+        let p = Path::new("");
+        let uri = "data:image/png;base64,aGVsbG8=";
+        let slides = crate::parser::parse_text(&format!(":sl :fg {uri}"), p);
+        let Some(SectionMain::Figure(fig)) =
+            &slides.unwrap().slides[0].sections[0].sec_main
+        else {
+            panic!("The section must be a figure.");
+        };
+        assert_eq!(fig.path, uri);
+
+        let text = ":sl :fg data:image/png;base64,not*base64";
+        assert!(crate::parser::parse_text(text, p).is_err());
+    }
+
+    #[test]
+    fn order() {
+        let p = Path::new("");
+        let text =
+            ":sl :order 2 :tb\nB\n:sl :order 1 :tb\nA\n:sl :order 3 :tb\nC";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let titles: Vec<_> = slides.slides.iter().map(|s| s.title()).collect();
+        assert_eq!(titles, [Some("A"), Some("B"), Some("C")]);
+
+        // Without an order, the slides keep the one in the input.
+        let text = ":sl :tb\nA\n:sl :order -1 :tb\nFirst\n:sl :tb\nB";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let titles: Vec<_> = slides.slides.iter().map(|s| s.title()).collect();
+        assert_eq!(titles, [Some("First"), Some("A"), Some("B")]);
+
+        assert!(crate::parser::parse_text(":sl :order 1.5", p).is_err());
+        assert!(crate::parser::parse_text(":ge :order 1", p).is_err());
+    }
+
+    #[test]
+    fn group() {
+        let p = Path::new("");
+        let text = ":sl :group intro :tb\nA\n:sl :tb\nB\n:sl :group intro";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let groups: Vec<_> =
+            slides.slides.iter().map(|s| s.group.as_deref()).collect();
+        assert_eq!(groups, [Some("intro"), None, Some("intro")]);
+        assert!(crate::parser::parse_text(":sl :group", p).is_err());
+        assert!(crate::parser::parse_text(":ge :group intro", p).is_err());
+    }
+
+    #[test]
+    fn background_gradient() {
+        let p = Path::new("");
+        let (red, blue) =
+            (Color::from((255, 0, 0, 255)), Color::from((0, 0, 255, 255)));
+        let text = ":ge :bc gradient red blue\n:sl :bc gradient #ff0000ff \
+                    blue horizontal :tb\nA\n:sl :bc red";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let vertical = Gradient {
+            from: red,
+            to: blue,
+            direction: GradientDirection::Vertical,
+        };
+        assert_eq!(slides.gradient, Some(vertical));
+        assert_eq!(slides.bg_col, Option::None);
+        let horizontal = Gradient {
+            direction: GradientDirection::Horizontal,
+            ..vertical
+        };
+        assert_eq!(slides.slides[0].gradient, Some(horizontal));
+        assert_eq!(slides.slides[0].title(), Some("A"));
+        // The last background set wins.
+        assert_eq!(slides.slides[1].gradient, Option::None);
+        assert_eq!(slides.slides[1].bg_color, Some(red));
+        let text = ":ge :bc gradient red blue :bc red";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        assert_eq!((slides.bg_col, slides.gradient), (Some(red), Option::None));
+
+        assert!(crate::parser::parse_text(":sl :bc gradient red", p).is_err());
+        assert!(crate::parser::parse_text(":sl :bc gradient", p).is_err());
+    }
+
+    #[test]
+    fn grid() {
+        let p = Path::new("");
+        let slides = crate::parser::parse_text(":sl :grid 2 3", p).unwrap();
+        assert_eq!(slides.slides[0].grid, Some((2, 3)));
+        let slides = crate::parser::parse_text(":sl :tb\nA", p).unwrap();
+        assert_eq!(slides.slides[0].grid, None);
+        assert!(crate::parser::parse_text(":sl :grid 2", p).is_err());
+        assert!(crate::parser::parse_text(":sl :grid 0 2", p).is_err());
+        assert!(crate::parser::parse_text(":sl :grid 2 1.5", p).is_err());
+        assert!(crate::parser::parse_text(":ge :grid 2 2", p).is_err());
+    }
+
+    #[test]
+    fn tag() {
+        let p = Path::new("");
+        let text = ":sl :tag a b\n:tb :tag c\nHi\n:tb\nThere";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let slide = &slides.slides[0];
+        assert_eq!(slide.tags, ["a", "b"]);
+        let tags: Vec<_> =
+            slide.sections.iter().map(|s| s.tags.len()).collect();
+        assert_eq!(tags, [1, 0]);
+        assert_eq!(slide.sections[0].tags, ["c"]);
+        assert!(crate::parser::parse_text(":sl :tag", p).is_err());
+        assert!(crate::parser::parse_text(":ge :tag a", p).is_err());
+    }
+
+    /// Check the size of the first section of the first slide.
+    fn assert_first_size(text: &str, w: f32, h: f32) {
+        let p = Path::new("");
+        let mut slides = crate::parser::parse_text(text, p).unwrap();
+        let size = slides.slides.remove(0).sections.remove(0).size.unwrap();
+        assert!((size.w - w).abs() < 1e-6, "{text}: {size:?}");
+        assert!((size.h - h).abs() < 1e-6, "{text}: {size:?}");
+    }
+
+    #[test]
+    fn size_multiplier() {
+        // Nothing is set: the built-in default is used.
+        assert_first_size(":sl :tb :sz x2", 0.036, 0.16);
+        // The slideshow's default.
+        assert_first_size(":ge :sz 0.1 0.2 :sl :tb :sz x1.5", 0.15, 0.3);
+        // The section's own size.
+        assert_first_size(":sl :tb :sz 0.2 0.4 :sz x0.5", 0.1, 0.2);
+        // Figures do not use the font's size.
+        let text = ":ge :sz 0.3 0.3 :sl :fg resources/star.jpg :sz x2";
+        assert_first_size(text, 0.2, 0.2);
+    }
+
+    #[test]
+    fn size_letters_per_line() {
+        // The built-in default's letters are 0.08 / 0.018 times as high as
+        // they are wide.
+        let h = 0.08 / 0.018 / 40.0;
+        assert_first_size(":sl :tb :sz fit40", 1.0 / 40.0, h);
+        assert_first_size(":ge :sz 0.1 0.2 :sl :tb :sz fit20", 0.05, 0.1);
+        let p = Path::new("");
+        for size in ["fit0", "fit", "fitx"] {
+            let text = format!(":sl :tb :sz {size}");
+            let slides = crate::parser::parse_text(&text, p);
+            assert!(slides.is_err(), "{size}: {slides:?}");
+        }
+    }
+
+    #[test]
+    fn size_multiplier_in_general() {
+        let p = Path::new("");
+        let slides = crate::parser::parse_text(":ge :sz 0.1 0.2 :sz x3", p);
+        let size = slides.unwrap().font_size.unwrap();
+        assert!((size.w - 0.3).abs() < 1e-6, "{size:?}");
+        assert!((size.h - 0.6).abs() < 1e-6, "{size:?}");
+        let slides = crate::parser::parse_text(":ge :sz xx3", p);
+        assert!(slides.is_err(), "{slides:?}");
+    }
+
+    #[test]
+    fn visibility() {
+        let p = Path::new("");
+        let text = ":sl :tb\nfor all\n:tb :only presenter\nreminder\n:fg resources/star.jpg :only Main";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let visibility: Vec<_> = slides.slides[0]
+            .sections
+            .iter()
+            .map(|s| s.visibility)
+            .collect();
+        assert_eq!(
+            visibility,
+            vec![Visibility::Both, Visibility::Presenter, Visibility::Main]
+        );
+    }
+
+    #[test]
+    fn visibility_ko() {
+        let p = Path::new("");
+        assert!(crate::parser::parse_text(":sl :tb :only me", p).is_err());
+        assert!(crate::parser::parse_text(":sl :only main", p).is_err());
+        assert!(crate::parser::parse_text(":sl :tb :only", p).is_err());
+    }
+
+    #[test]
+    fn duplicate_general() {
+        let tokens = tokenizer(":bc 0 0 255 255");
+        let tokens = &tokens[1..];
+        let red = Color {
+            r: 255,
+            g: 0,
+            b: 0,
+            a: 255,
+        };
+        let blue = Color {
+            r: 0,
+            g: 0,
+            b: 255,
+            a: 255,
+        };
+        assert!(warn_override("color", None, &red, tokens, true).is_ok());
+        assert!(warn_override("color", Some(&red), &red, tokens, true).is_ok());
+        assert!(
+            warn_override("color", Some(&red), &blue, tokens, false).is_ok()
+        );
+        assert!(
+            warn_override("color", Some(&red), &blue, tokens, true).is_err()
+        );
+
+        // The last one wins.
+        let p = Path::new("");
+        let text = ":ge :bc 255 0 0 255\n:ge :bc 0 0 255 255\n:sl";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        assert_eq!(slides.bg_col, Some(blue));
+    }
+
+    #[test]
+    fn strict_mode() {
+        let p = Path::new("");
+        let strict = crate::parser::ParseOptions {
+            strict: true,
+            ..crate::parser::ParseOptions::default()
+        };
+        let parse = |text: &str| {
+            crate::parser::parse_text_with_options(text, p, &strict)
+        };
+        // The warnings are errors.
+        let text = ":ge :fc red\n:ge :fc blue\n:sl";
+        assert!(crate::parser::parse_text(text, p).is_ok());
+        assert!(parse(text).is_err());
+        let text = ":sl :tb\n${NOPE}";
+        assert!(crate::parser::parse_text(text, p).is_ok());
+        assert!(parse(text).is_err());
+
+        // A position outside the slide is only warned about, by default.
+        let text = ":sl :tb :ps 1.5 0.2\nHello";
+        assert!(crate::parser::parse_text(text, p).is_ok());
+        assert!(parse(text).is_err());
+        assert!(parse(":sl :tb :ps 1 0\nHello").is_ok());
+
+        // Setting the same value again is fine.
+        assert!(parse(":ge :fc red\n:ge :fc red\n:sl :tb\nHello").is_ok());
+        // A gradient replaces the background color, and the other way around.
+        let text = ":ge :bc gradient red blue\n:ge :bc red\n:sl";
+        assert!(parse(text).is_err());
+        let text = ":ge :bc red\n:ge :bc gradient red blue\n:sl";
+        assert!(parse(text).is_err());
+    }
+
+    #[test]
+    fn columns() {
+        let p = Path::new("");
+        let text = ":sl :tb :cols\nName | Age\nAlexandra | 7\n";
+        let slides = crate::parser::parse_text(text, p).unwrap();
+        let Some(SectionMain::Text(text)) =
+            &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("Not a text: {slides:?}");
+        };
+        assert_eq!(
+            text.columns,
+            Some(vec![
+                vec!["Name".to_string(), "Age".to_string()],
+                vec!["Alexandra".to_string(), "7".to_string()],
+            ])
+        );
+        assert_eq!(text.column_widths(), vec![9, 3]);
+        assert_eq!(text.cells(1), Some(vec![(0, "Alexandra"), (11, "7")]));
+        assert_eq!(text.text, "Name       Age\nAlexandra  7\n");
+
+        assert!(crate::parser::parse_text(":sl :cols", p).is_err());
+        assert!(crate::parser::parse_text(":sl :code rust :cols", p).is_err());
+    }
 }
@@ -0,0 +1,854 @@
+/*!
+Show the slides until the user quits, the way the `slidy` executable does.
+
+[`run_file`] reads a file, shows it with a backend, and reads it again each
+time it changes; [`run_slideshow`] shows slides that are already built. Both
+return once the user quits.
+
+```no_run
+let options = slidy::run::RunOptions {
+    start_slide: 2,
+    ..slidy::run::RunOptions::default()
+};
+slidy::run::run_file(std::path::Path::new("talk.txt"), options).unwrap();
+```
+
+The pieces they are made of are available too, to build another loop: the
+slides are parsed on their own thread, and sent to the [`event_loop`], which
+asks for them again when the file changes or when the user wants to reload
+them.
+*/
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::sleep;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::{debug, error, info, warn};
+
+use crate::backends::{
+    get_backend_with_options, BackendError, Backends, InputOutcome, Options,
+    SlidyBackend, SlidyContext,
+};
+use crate::parser::{IncrementalParser, ParseOptions};
+use crate::slideshow::Slideshow;
+
+/// How many frames are drawn each second, at most, when nothing else is
+/// asked.
+pub const DEFAULT_FPS: u32 = 10;
+
+/// The polling interval used when the file system notifications can't be
+/// used, and none was given.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Changes the slides read by [`run_file`] each time they are parsed,
+/// before they are shown.
+pub type PostParse = Arc<dyn Fn(&mut Slideshow) + Send + Sync>;
+
+/// How the slides are shown by [`run_file`] and [`run_slideshow`].
+#[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct RunOptions {
+    /// The backend showing the slides. Without one, SDL is preferred, but
+    /// if it can't start (like when there is no display) the terminal is
+    /// used instead.
+    pub backend: Option<Backends>,
+    /// The options of the backend.
+    pub backend_options: Options,
+    /// How many frames are drawn each second, at most.
+    pub fps: u32,
+    /// The slide shown first, from 0.
+    pub start_slide: usize,
+    /// Read the file again each time it changes.
+    pub watch: bool,
+    /// Check the file for changes this often, instead of using the file
+    /// system notifications.
+    pub poll_interval: Option<Duration>,
+    /// Read the file as Markdown, whatever its extension.
+    pub markdown: bool,
+    /// When the file can't be read again, show the error instead of the
+    /// last good slides.
+    pub show_errors: bool,
+    /// How the file is parsed.
+    pub parse_options: ParseOptions,
+    /// Called on the slides each time the file is parsed, like to apply a
+    /// theme or to keep only some of them.
+    pub post_parse: Option<PostParse>,
+    /// Read the file again when the images shown in the slides change too.
+    pub watch_assets: bool,
+    /// Serve the current slide on this address, for other slideshows to
+    /// follow it. Only used by [`run_file`].
+    pub serve: Option<String>,
+    /// Follow the slideshow served on this address. Only used by
+    /// [`run_file`].
+    pub follow: Option<String>,
+}
+
+impl std::fmt::Debug for RunOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RunOptions")
+            .field("backend", &self.backend)
+            .field("backend_options", &self.backend_options)
+            .field("fps", &self.fps)
+            .field("start_slide", &self.start_slide)
+            .field("watch", &self.watch)
+            .field("poll_interval", &self.poll_interval)
+            .field("markdown", &self.markdown)
+            .field("show_errors", &self.show_errors)
+            .field("parse_options", &self.parse_options)
+            .field("post_parse", &self.post_parse.is_some())
+            .field("watch_assets", &self.watch_assets)
+            .field("serve", &self.serve)
+            .field("follow", &self.follow)
+            .finish()
+    }
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            backend: None,
+            backend_options: Options::default(),
+            fps: DEFAULT_FPS,
+            start_slide: 0,
+            watch: true,
+            poll_interval: None,
+            markdown: false,
+            show_errors: false,
+            parse_options: ParseOptions::default(),
+            post_parse: None,
+            watch_assets: false,
+            serve: None,
+            follow: None,
+        }
+    }
+}
+
+/// Show the slides at `path` until the user quits. When `options` ask for
+/// it, the slides are read again each time the file changes.
+///
+/// # Errors
+///
+/// If the file can't be parsed at first (unless
+/// [`RunOptions::show_errors`] is set), if the slideshow can't be served
+/// or followed, or if the backend can't start. The later errors are
+/// logged, and the last good slides are kept, unless
+/// [`RunOptions::show_errors`] is set.
+///
+/// # Panics
+///
+/// If the thread parsing the slides panicked.
+pub fn run_file(
+    path: &Path,
+    options: RunOptions,
+) -> Result<(), Box<dyn Error>> {
+    let path = path.to_path_buf();
+    let markdown = is_markdown(&path, options.markdown);
+    let show_errors = options.show_errors;
+    let (server, followed) = sync_slides(&options)?;
+
+    let (send_slides_tx, send_slides_rx) = channel();
+    let (request_update_tx, request_update_rx) = channel();
+    // The images are watched on their own, and their watcher is dropped
+    // at the end, for the parser to stop.
+    let assets = Arc::new(Mutex::new(if options.watch_assets {
+        AssetWatcher::new(request_update_tx.clone())
+            .map_err(|e| warn!("Unable to watch the images: {}", e))
+            .ok()
+    } else {
+        None
+    }));
+    let mut parser = IncrementalParser::new(options.parse_options);
+    let mut parse = {
+        let path = path.clone();
+        let assets = Arc::clone(&assets);
+        let post_parse = options.post_parse.clone();
+        move || -> Result<Slideshow, Box<dyn Error>> {
+            let mut slides = if markdown {
+                crate::parser::parse_markdown_file_with_options(
+                    &path,
+                    &options.parse_options,
+                )?
+            } else {
+                parser.parse_file(&path)?
+            };
+            if let Some(post_parse) = &post_parse {
+                post_parse(&mut slides);
+            }
+            debug!("Parsed {}", slides);
+            if let Ok(mut assets) = assets.lock() {
+                if let Some(assets) = assets.as_mut() {
+                    assets.update(asset_paths(&slides));
+                }
+            }
+            Ok(slides)
+        }
+    };
+    // Log the error, and tell what to show instead of the slides.
+    let report = {
+        let path = path.clone();
+        move |e: &dyn Error| {
+            error!("Error when parsing {:?}: {}", path, e);
+            format!("Error when parsing {}: {e}", path.display())
+        }
+    };
+    let slides = match parse() {
+        Ok(slides) => slides,
+        Err(e) if show_errors => Slideshow::from_error(&report(e.as_ref())),
+        Err(e) => return Err(e),
+    };
+
+    let watcher = options
+        .watch
+        .then(|| watch_file(&path, options.poll_interval, &request_update_tx));
+    let parser = thread::spawn(move || {
+        while request_update_rx.recv().is_ok() {
+            let slides = match parse() {
+                Ok(slides) => slides,
+                Err(e) => {
+                    let message = report(e.as_ref());
+                    if !show_errors {
+                        continue;
+                    }
+                    Slideshow::from_error(&message)
+                }
+            };
+            if send_slides_tx.send(slides).is_err() {
+                // Nobody shows the slides anymore.
+                break;
+            }
+        }
+    });
+
+    let shown = with_context(
+        options.backend.as_ref(),
+        options.backend_options,
+        |context| {
+            context.set_slides(slides);
+            context.set_slide_index(options.start_slide);
+            event_loop(
+                context,
+                Some(&path),
+                &send_slides_rx,
+                &request_update_tx,
+                server.as_ref(),
+                followed.as_ref(),
+                options.fps,
+            );
+        },
+    );
+
+    // Without any request left, the parser stops.
+    drop(watcher);
+    if let Ok(mut assets) = assets.lock() {
+        assets.take();
+    }
+    drop(request_update_tx);
+    parser
+        .join()
+        .expect("The thread parsing the slides panicked");
+    shown?;
+    Ok(())
+}
+
+/// Serve the current slide, and follow the one served by another
+/// slideshow, as `options` ask.
+fn sync_slides(
+    options: &RunOptions,
+) -> Result<(Option<crate::sync::Server>, Option<Receiver<usize>>), String> {
+    let server = match &options.serve {
+        Some(addr) => Some(
+            crate::sync::Server::bind(addr.as_str())
+                .map_err(|e| format!("Unable to serve on {addr}: {e}"))?,
+        ),
+        None => None,
+    };
+    let followed = match &options.follow {
+        Some(addr) => Some(
+            crate::sync::follow(addr.as_str())
+                .map_err(|e| format!("Unable to follow {addr}: {e}"))?,
+        ),
+        None => None,
+    };
+    Ok((server, followed))
+}
+
+/// Show `slideshow` until the user quits. There is no file behind it: the
+/// slides can't be reloaded nor edited, and [`RunOptions::watch`] is
+/// ignored.
+///
+/// # Errors
+///
+/// If the backend can't start.
+pub fn run_slideshow(
+    slideshow: Slideshow,
+    options: RunOptions,
+) -> Result<(), BackendError> {
+    let (_send_slides_tx, send_slides_rx) = channel();
+    // Nobody reads the requests, but they can still be sent.
+    let (request_update_tx, _request_update_rx) = channel();
+    with_context(
+        options.backend.as_ref(),
+        options.backend_options,
+        |context| {
+            context.set_slides(slideshow);
+            context.set_slide_index(options.start_slide);
+            event_loop(
+                context,
+                None,
+                &send_slides_rx,
+                &request_update_tx,
+                None,
+                None,
+                options.fps,
+            );
+        },
+    )
+}
+
+/// If the slides at `path` are written in Markdown: either the caller says
+/// so, or the file ends in `.md`.
+#[must_use]
+pub fn is_markdown(path: &Path, from_markdown: bool) -> bool {
+    from_markdown
+        || path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+}
+
+/// Start the `which` backend. When nothing was asked, SDL is preferred, but
+/// if it can't start (like when there is no display) the crossterm one is
+/// used instead.
+///
+/// # Errors
+///
+/// If the backend can't start.
+pub fn start_backend(
+    which: Option<&Backends>,
+    options: Options,
+) -> Result<Box<dyn SlidyBackend>, BackendError> {
+    #[cfg(feature = "sdl")]
+    let preferred_backend = Backends::Sdl;
+    #[cfg(all(not(feature = "sdl"), feature = "cterm"))]
+    let preferred_backend = Backends::Crossterm;
+    #[cfg(not(any(feature = "sdl", feature = "cterm")))]
+    let preferred_backend = Backends::Null;
+
+    match get_backend_with_options(
+        which.unwrap_or(&preferred_backend),
+        options.clone(),
+    ) {
+        Ok(backend) => Ok(backend),
+        #[cfg(feature = "cterm")]
+        Err(e) if which.is_none() => fall_back_to_terminal(
+            &format!("the {preferred_backend:?} backend"),
+            &e,
+            options,
+        ),
+        Err(e) => Err(e),
+    }
+}
+
+/// Say why `what` can't start, and start the crossterm backend instead.
+#[cfg(feature = "cterm")]
+fn fall_back_to_terminal(
+    what: &str,
+    e: &BackendError,
+    options: Options,
+) -> Result<Box<dyn SlidyBackend>, BackendError> {
+    warn!("Unable to start {what}: {e}, falling back to the terminal.");
+    get_backend_with_options(&Backends::Crossterm, options)
+}
+
+/// Start the `which` backend like [`start_backend`], and `run` its context.
+/// When nothing was asked and the context of the preferred backend can't
+/// start either, the crossterm one is used instead.
+///
+/// # Errors
+///
+/// If the backend or its context can't start.
+pub fn with_context<R>(
+    which: Option<&Backends>,
+    options: Options,
+    run: impl FnOnce(&mut dyn SlidyContext) -> R,
+) -> Result<R, BackendError> {
+    let mut backend = start_backend(which, options.clone())?;
+    let context = backend.get_context();
+    match context {
+        Ok(mut context) => Ok(run(context.as_mut())),
+        #[cfg(feature = "cterm")]
+        Err(e) if which.is_none() => {
+            let mut backend =
+                fall_back_to_terminal("the backend's context", &e, options)?;
+            let mut context = backend.get_context()?;
+            Ok(run(context.as_mut()))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Watches a file for [`watch_file`], until it is dropped.
+pub struct FileWatcher {
+    /// The file system notifications, when they are used.
+    _watcher: Option<RecommendedWatcher>,
+    /// Stops the polling thread, if any, once dropped.
+    stop_tx: Option<Sender<()>>,
+    /// The thread polling the file, if any.
+    poller: Option<JoinHandle<()>>,
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        self.stop_tx.take();
+        if let Some(poller) = self.poller.take() {
+            if poller.join().is_err() {
+                error!("The thread polling the file panicked.");
+            }
+        }
+    }
+}
+
+/// Request an update of the slides on `request_update_tx` each time the
+/// file at `path` changes.
+///
+/// The file system notifications are used, unless a `poll_interval` is
+/// given, or they are not available (like on some network file systems):
+/// then, the file is polled on its own thread. The notifications stop, and
+/// the thread ends, when the returned watcher is dropped.
+#[must_use]
+pub fn watch_file(
+    path: &Path,
+    poll_interval: Option<Duration>,
+    request_update_tx: &Sender<()>,
+) -> FileWatcher {
+    let watcher = match poll_interval {
+        Some(_) => None,
+        None => start_watcher(path, request_update_tx.clone())
+            .map_err(|e| {
+                warn!("Unable to watch {:?}, polling it instead: {}", path, e);
+            })
+            .ok(),
+    };
+    let (stop_tx, poller) = if watcher.is_none() {
+        let interval = poll_interval.unwrap_or(DEFAULT_POLL_INTERVAL);
+        let path = path.to_path_buf();
+        let request_update_tx = request_update_tx.clone();
+        let (stop_tx, stop_rx) = channel();
+        let poller = thread::spawn(move || {
+            poll_file(&path, interval, &request_update_tx, &stop_rx);
+        });
+        (Some(stop_tx), Some(poller))
+    } else {
+        (None, None)
+    };
+    FileWatcher {
+        _watcher: watcher,
+        stop_tx,
+        poller,
+    }
+}
+
+/// Watch the file using the file system notifications.
+fn start_watcher(
+    path: &Path,
+    request_update_tx: Sender<()>,
+) -> notify::Result<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(
+        move |_: notify::Result<notify::Event>| {
+            // Nobody may be listening anymore, and that's fine.
+            let _ = request_update_tx.send(());
+        },
+    )?;
+    watcher.watch(path, RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
+
+/// Check the file's modification time every `interval`, and request a slide's
+/// update whenever it changes, until `stop_rx` is closed.
+fn poll_file(
+    path: &Path,
+    interval: Duration,
+    request_update_tx: &Sender<()>,
+    stop_rx: &Receiver<()>,
+) {
+    let modified = |p: &Path| std::fs::metadata(p).and_then(|m| m.modified());
+    let mut last_seen = modified(path).ok();
+    while stop_rx.recv_timeout(interval) == Err(RecvTimeoutError::Timeout) {
+        match modified(path) {
+            Ok(now) if Some(now) != last_seen => {
+                last_seen = Some(now);
+                if request_update_tx.send(()).is_err() {
+                    // Nobody is listening anymore.
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(e) => debug!("Unable to check {:?} for changes: {}", path, e),
+        }
+    }
+}
+
+/// Watches the images shown in the slides, to read the slides again when
+/// they change.
+struct AssetWatcher {
+    watcher: RecommendedWatcher,
+    watched: BTreeSet<PathBuf>,
+}
+
+impl AssetWatcher {
+    fn new(request_update_tx: Sender<()>) -> notify::Result<Self> {
+        let watcher =
+            notify::recommended_watcher(move |_: notify::Result<_>| {
+                // Nobody may be listening anymore, and that's fine.
+                let _ = request_update_tx.send(());
+            })?;
+        Ok(Self {
+            watcher,
+            watched: BTreeSet::new(),
+        })
+    }
+
+    /// Watch `paths`, and only them. All of them are watched again, since
+    /// the editors that save a file by renaming another one over it leave
+    /// the old watch on a file that is gone.
+    fn update(&mut self, paths: BTreeSet<PathBuf>) {
+        for path in &self.watched {
+            // The file may be gone already, and so is its watch.
+            let _ = self.watcher.unwatch(path);
+        }
+        for path in &paths {
+            if let Err(e) =
+                self.watcher.watch(path, RecursiveMode::NonRecursive)
+            {
+                debug!("Unable to watch {:?}: {}", path, e);
+            }
+        }
+        self.watched = paths;
+    }
+}
+
+/// The image files shown in the slides, leaving out the embedded ones.
+fn asset_paths(slides: &Slideshow) -> BTreeSet<PathBuf> {
+    slides
+        .slides
+        .iter()
+        .flat_map(crate::slideshow::Slide::image_paths)
+        .filter(|path| !path.starts_with("data:"))
+        .map(|path| PathBuf::from(slides.resolve_path(path)))
+        .collect()
+}
+
+/// The command that opens `path` in the `editor`, like the one in `$EDITOR`:
+/// it can have arguments too, like `code --wait`. Without an editor, `vi` is
+/// used.
+fn editor_command(editor: Option<&str>, path: &Path) -> Command {
+    let mut words = editor.unwrap_or_default().split_whitespace();
+    let mut command = Command::new(words.next().unwrap_or("vi"));
+    command.args(words).arg(path);
+    command
+}
+
+/// Let the user change the file at `path` in their editor, and ask the
+/// slides again on `request_update_tx` once they are done.
+///
+/// In the terminal, the editor takes it over: nothing is shown until it
+/// exits. Otherwise, the editor runs next to the slides, and only the one
+/// set in `$EDITOR` is opened.
+fn edit_file(
+    context: &mut dyn SlidyContext,
+    path: &Path,
+    request_update_tx: &Sender<()>,
+) {
+    let editor = std::env::var("EDITOR").ok();
+    if context.owns_terminal() {
+        info!("Opening {:?} in {:?}", path, editor);
+        context.suspend();
+        wait_editor(editor_command(editor.as_deref(), path).status());
+        context.resume();
+        request_update_tx
+            .send(())
+            .expect("Unable to request slide update");
+        return;
+    }
+    let Some(editor) = editor else {
+        warn!("$EDITOR is not set: there is no editor to open {:?}", path);
+        return;
+    };
+    info!("Opening {:?} in {:?}", path, editor);
+    match editor_command(Some(&editor), path).spawn() {
+        Ok(mut child) => {
+            let request_update_tx = request_update_tx.clone();
+            thread::spawn(move || {
+                wait_editor(child.wait());
+                // The slides may be gone already.
+                let _ = request_update_tx.send(());
+            });
+        }
+        Err(e) => error!("Unable to start the editor: {}", e),
+    }
+}
+
+/// Report how the editor exited.
+fn wait_editor(status: std::io::Result<std::process::ExitStatus>) {
+    match status {
+        Ok(status) if !status.success() => {
+            warn!("The editor exited with {}", status);
+        }
+        Ok(_) => {}
+        Err(e) => error!("Unable to start the editor: {}", e),
+    }
+}
+
+/// Show the slides received on `slides_rx` until the user quits, at `fps`
+/// frames per second at most.
+///
+/// The slides are asked again on
+/// `request_update_tx` when the user wants to reload them. The current slide
+/// is sent to the `server`, and the slides shown follow the `followed` one.
+/// The user can change the slides at `path`, if any, in an editor.
+pub fn event_loop(
+    context: &mut dyn SlidyContext,
+    path: Option<&Path>,
+    slides_rx: &Receiver<Slideshow>,
+    request_update_tx: &Sender<()>,
+    server: Option<&crate::sync::Server>,
+    followed: Option<&Receiver<usize>>,
+    fps: u32,
+) {
+    // Fix the max fps.
+    let fixed_fps = Duration::from_nanos(1_000_000_000 / u64::from(fps.max(1)));
+    // The slide shown before an error replaced the slides, to go back to it
+    // once the error is fixed.
+    let mut before_error = None;
+
+    loop {
+        let timer = std::time::SystemTime::now();
+        // Check if we have new slides
+        if let Ok(slides) = slides_rx.try_recv() {
+            if slides.is_error() {
+                before_error.get_or_insert_with(|| context.current_index());
+                context.set_slides(slides);
+            } else {
+                context.set_slides(slides);
+                if let Some(idx) = before_error.take() {
+                    context.set_slide_index(idx);
+                }
+            }
+        }
+
+        match context.manage_inputs() {
+            InputOutcome::Continue => {}
+            InputOutcome::Quit => break,
+            InputOutcome::Reload => {
+                info!("Reloading the slides on request.");
+                request_update_tx
+                    .send(())
+                    .expect("Unable to request slide update");
+            }
+            InputOutcome::Edit => {
+                let Some(path) = path else {
+                    info!("There is no file to edit.");
+                    continue;
+                };
+                edit_file(context, path, request_update_tx);
+            }
+        }
+        if let Some(rx) = followed {
+            if let Some(idx) = rx.try_iter().last() {
+                context.set_slide_index(idx);
+            }
+        }
+        if let Some(server) = server {
+            server.send(context.current_index());
+        }
+        context.render();
+
+        match timer.elapsed() {
+            Ok(elapsed) => {
+                if elapsed < fixed_fps {
+                    sleep(fixed_fps.saturating_sub(elapsed));
+                } else {
+                    warn!(
+                        "Unable to have {} fps, needed {:?} to show the slide",
+                        fps, elapsed
+                    );
+                }
+            }
+            Err(e) => {
+                error!(
+                    "Previous measured time is later than actual one: {}",
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backends::null;
+
+    #[test]
+    fn test_is_markdown() {
+        assert!(is_markdown(Path::new("talk.md"), false));
+        assert!(is_markdown(Path::new("talk.MD"), false));
+        assert!(!is_markdown(Path::new("talk.txt"), false));
+        assert!(is_markdown(Path::new("talk.txt"), true));
+    }
+
+    #[test]
+    fn test_editor_command() {
+        let path = Path::new("/tmp/slides.txt");
+        let args = |c: &Command| {
+            let mut all = vec![c.get_program().to_owned()];
+            all.extend(c.get_args().map(ToOwned::to_owned));
+            all
+        };
+        assert_eq!(
+            args(&editor_command(Some("code --wait"), path)),
+            ["code", "--wait", "/tmp/slides.txt"]
+        );
+        assert_eq!(
+            args(&editor_command(None, path)),
+            ["vi", "/tmp/slides.txt"]
+        );
+        assert_eq!(
+            args(&editor_command(Some("  "), path)),
+            ["vi", "/tmp/slides.txt"]
+        );
+    }
+
+    #[test]
+    fn test_event_loop() {
+        let (slides_tx, slides_rx) = channel();
+        let (request_update_tx, request_update_rx) = channel();
+        let slides = crate::parser::parse_text(
+            ":sl\n:tb\nOne\n:sl\n:tb\nTwo",
+            Path::new(""),
+        )
+        .unwrap();
+        slides_tx.send(slides).unwrap();
+
+        let (follow_tx, follow_rx) = channel();
+        follow_tx.send(1).unwrap();
+        let mut backend = null::Backend::with_script([
+            InputOutcome::Continue,
+            InputOutcome::Reload,
+        ]);
+        {
+            let mut context = backend.get_context().unwrap();
+            event_loop(
+                context.as_mut(),
+                None,
+                &slides_rx,
+                &request_update_tx,
+                None,
+                Some(&follow_rx),
+                DEFAULT_FPS,
+            );
+        }
+        let record = backend.record();
+        assert_eq!(record.slides.len(), 1);
+        assert_eq!(record.slides[0].slides.len(), 2);
+        // The third input is the end of the script: quit, without rendering.
+        assert_eq!((record.inputs, record.renders), (3, 2));
+        assert_eq!(record.index, 1);
+        assert_eq!(request_update_rx.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_run_file() {
+        let options = RunOptions {
+            backend: Some(Backends::Null),
+            start_slide: 1,
+            watch: false,
+            ..RunOptions::default()
+        };
+        let path = Path::new("resources/simple_slide.txt");
+        assert!(run_file(path, options.clone()).is_ok());
+        assert!(run_file(Path::new("resources/none.txt"), options).is_err());
+    }
+
+    #[test]
+    fn test_run_file_post_parse() {
+        let parsed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let options = RunOptions {
+            backend: Some(Backends::Null),
+            watch: false,
+            watch_assets: true,
+            post_parse: Some({
+                let parsed = Arc::clone(&parsed);
+                Arc::new(move |slides: &mut Slideshow| {
+                    assert!(!slides.slides.is_empty());
+                    parsed.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                })
+            }),
+            ..RunOptions::default()
+        };
+        let path = Path::new("resources/simple_slide.txt");
+        assert!(run_file(path, options).is_ok());
+        assert_eq!(parsed.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_run_file_show_errors() {
+        let options = RunOptions {
+            backend: Some(Backends::Null),
+            watch: false,
+            show_errors: true,
+            ..RunOptions::default()
+        };
+        assert!(run_file(Path::new("resources/none.txt"), options).is_ok());
+    }
+
+    #[test]
+    fn test_asset_paths() {
+        let slides = crate::parser::parse_text(
+            ":sl\n:fg star.jpg\n:sl\n:fg data:image/png;base64,aGVsbG8=\n\
+             :sl\n:fg star.jpg",
+            Path::new("./resources"),
+        )
+        .unwrap();
+        let paths = asset_paths(&slides);
+        assert_eq!(paths.len(), 1);
+        assert!(paths.iter().all(|p| p.ends_with("star.jpg")));
+    }
+
+    #[test]
+    fn test_run_slideshow() {
+        let slides =
+            crate::parser::parse_text(":sl\n:tb\nOne\n", Path::new(""))
+                .unwrap();
+        let options = RunOptions {
+            backend: Some(Backends::Null),
+            ..RunOptions::default()
+        };
+        assert!(run_slideshow(slides, options).is_ok());
+    }
+
+    #[test]
+    fn test_watch_file_stops() {
+        let path = Path::new("resources/simple_slide.txt");
+        let (request_update_tx, request_update_rx) = channel();
+        let watcher = watch_file(
+            path,
+            Some(Duration::from_millis(10)),
+            &request_update_tx,
+        );
+        drop(watcher);
+        drop(request_update_tx);
+        // The polling thread is gone, with its sender.
+        assert_eq!(
+            request_update_rx.recv_timeout(Duration::from_secs(5)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+}
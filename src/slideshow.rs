@@ -1,4 +1,9 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 /// The position data.
 /// Note that this contains float between 0 and 1, and our coordinates are
@@ -13,7 +18,7 @@ use std::collections::HashMap;
 ///   |                     |
 /// (0,1)-----------------(1,1)
 /// ```
-#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
 pub struct Position {
     /// The `x` coordinate.
     pub x: f32,
@@ -21,8 +26,25 @@ pub struct Position {
     pub y: f32,
 }
 
+impl From<(f32, f32)> for Position {
+    fn from((x, y): (f32, f32)) -> Self {
+        Self { x, y }
+    }
+}
+
+/// An axis of the slide.
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq,
+)]
+pub enum Axis {
+    /// The horizontal one, from left to right.
+    X,
+    /// The vertical one, from top to bottom.
+    Y,
+}
+
 /// The size of the object to be represented.
-#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
 pub struct Size {
     /// The `width`.
     pub w: f32,
@@ -30,6 +52,39 @@ pub struct Size {
     pub h: f32,
 }
 
+impl From<&Size> for (f32, f32) {
+    fn from(s: &Size) -> Self {
+        (s.w, s.h)
+    }
+}
+
+/// The size of the letters, when neither the section nor the slideshow set
+/// one.
+pub const DEFAULT_FONT_SIZE: Size = Size { w: 0.018, h: 0.08 };
+
+/// The size of the letters of the title, on a title slide.
+pub const TITLE_SIZE: Size = Size { w: 0.04, h: 0.16 };
+
+/// The size of the letters of the other texts, like the author, on a title
+/// slide.
+pub const TITLE_DETAILS_SIZE: Size = Size { w: 0.02, h: 0.08 };
+
+/// The metadata written below the title, on a title slide, in order.
+pub const TITLE_METADATA: [&str; 2] = ["subtitle", "author"];
+
+/// The room between the texts of a title slide.
+const TITLE_GAP: f32 = 0.04;
+
+/// The size of the figures, when the section does not set one.
+pub const DEFAULT_FIGURE_SIZE: Size = Size { w: 0.1, h: 0.1 };
+
+/// Where the first line of text goes, when the section does not set a
+/// position. The next sections without a position go below it.
+pub const DEFAULT_TEXT_POSITION: Position = Position { x: 0.01, y: 0.01 };
+
+/// Where the figures go, when the section does not set a position.
+pub const DEFAULT_FIGURE_POSITION: Position = Position { x: 0.01, y: 0.01 };
+
 #[derive(
     serde::Serialize, serde::Deserialize, Debug, Copy, Clone, PartialEq, Eq,
 )]
@@ -45,6 +100,131 @@ pub struct Color {
     pub a: u8,
 }
 
+/// The direction the colors of a [`Gradient`] change along.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+)]
+pub enum GradientDirection {
+    /// From the top to the bottom.
+    #[default]
+    Vertical,
+    /// From the left to the right.
+    Horizontal,
+}
+
+/// A background that fades from a color to another.
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Copy, Clone, PartialEq, Eq,
+)]
+pub struct Gradient {
+    /// The color at the top, or at the left.
+    pub from: Color,
+    /// The color at the bottom, or at the right.
+    pub to: Color,
+    /// The direction the colors change along.
+    #[serde(default)]
+    pub direction: GradientDirection,
+}
+
+impl Gradient {
+    /// The color at `t`, from 0 (the `from` color) to 1 (the `to` one).
+    #[must_use]
+    pub fn color_at(&self, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |from: u8, to: u8| {
+            let (from, to) = (f32::from(from), f32::from(to));
+            #[allow(clippy::cast_possible_truncation)]
+            #[allow(clippy::cast_sign_loss)]
+            let c = t.mul_add(to - from, from).round() as u8;
+            c
+        };
+        Color {
+            r: mix(self.from.r, self.to.r),
+            g: mix(self.from.g, self.to.g),
+            b: mix(self.from.b, self.to.b),
+            a: mix(self.from.a, self.to.a),
+        }
+    }
+}
+
+/// The background color, when neither the slide nor the slideshow set one.
+pub const DEFAULT_BG_COLOR: Color = Color {
+    r: 0xff,
+    g: 0xff,
+    b: 0xff,
+    a: 0xff,
+};
+
+/// The color of the errors shown instead of the slides, see
+/// [`Slideshow::from_error`].
+pub const ERROR_COLOR: Color = Color {
+    r: 0xff,
+    g: 0x00,
+    b: 0x00,
+    a: 0xff,
+};
+
+/// The color of the text, when neither the section nor the slideshow set
+/// one.
+pub const DEFAULT_FONT_COLOR: Color = Color {
+    r: 0x00,
+    g: 0x00,
+    b: 0x00,
+    a: 0xff,
+};
+
+impl std::fmt::Display for Color {
+    /// The color in hex, like `#rrggbbaa`, as it can be written in the
+    /// slides.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#{:02x}{:02x}{:02x}{:02x}",
+            self.r, self.g, self.b, self.a
+        )
+    }
+}
+
+impl Color {
+    /// The relative luminance of the color, from 0 (black) to 1 (white), as
+    /// defined by the WCAG. The alpha is ignored.
+    #[must_use]
+    pub fn luminance(&self) -> f32 {
+        let linear = |c: u8| {
+            let c = f32::from(c) / 255.0;
+            if c <= 0.039_28 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.0722f32.mul_add(
+            linear(self.b),
+            0.2126f32.mul_add(linear(self.r), 0.7152 * linear(self.g)),
+        )
+    }
+
+    /// Black or white, whichever is easier to read on `bg`.
+    #[must_use]
+    pub fn readable_on(bg: Self) -> Self {
+        // The contrast ratios with black and with white are (L + 0.05) /
+        // 0.05 and 1.05 / (L + 0.05): black wins when the first is higher.
+        let lum = bg.luminance() + 0.05;
+        if lum * lum > 0.05 * 1.05 {
+            DEFAULT_FONT_COLOR
+        } else {
+            Self::from((0xff, 0xff, 0xff, 0xff))
+        }
+    }
+}
+
 impl From<(u8, u8, u8, u8)> for Color {
     fn from(c: (u8, u8, u8, u8)) -> Self {
         Self {
@@ -56,7 +236,7 @@ impl From<(u8, u8, u8, u8)> for Color {
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
 /// Define a section that contains a text.
 #[derive(Default)]
 pub struct SectionText {
@@ -67,15 +247,375 @@ pub struct SectionText {
     // The font name, must be aligned with the global one in the Slide struct
     /// Unused at the moment
     pub font: Option<String>,
+    /// The width of the box the text lives in: lines longer than this are
+    /// wrapped, whatever the size of the letters is.
+    pub box_width: Option<f32>,
+    /// The space between the lines, as a multiple of the letters' height.
+    pub line_height: Option<f32>,
+    /// If the lines are moved up or down, so that they are in the middle of
+    /// the slide.
+    #[serde(default)]
+    pub vcenter: bool,
+    /// If the text is code: it is never wrapped, and it is drawn with the
+    /// built-in (monospace) font, in a box.
+    #[serde(default)]
+    pub is_code: bool,
+    /// The language of the code, if any, shown next to it.
+    #[serde(default)]
+    pub lang: Option<String>,
+    /// The box the text must fit in, from its position: the letters are
+    /// made smaller than the section's size until it does.
+    #[serde(default)]
+    pub fit: Option<Size>,
+    /// The cells of each line, when the text is laid out in columns: the
+    /// lines are split on a `|`. The `text` has the same lines, with the
+    /// cells padded so that the columns are aligned.
+    #[serde(default)]
+    pub columns: Option<Vec<Vec<String>>>,
+    /// The rotation, in degrees, around the middle of the lines.
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+/// What separates the cells of a line, in a text laid out in columns.
+pub const COLUMN_SEPARATOR: char = '|';
+
+/// How many spaces there are between two columns.
+pub const COLUMN_GAP: usize = 2;
+
+impl SectionText {
+    /// Build a text out of its lines, like the parser does.
+    #[must_use]
+    pub fn from_lines<I, S>(lines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut text = Self::default();
+        for line in lines {
+            text.push_line(line.as_ref());
+        }
+        text
+    }
+
+    /// Add a line at the end of the text. Each line is ended by a `\n`, which
+    /// is where the backends split the text.
+    pub fn push_line(&mut self, line: &str) {
+        if let Some(rows) = &mut self.columns {
+            rows.push(split_cells(line));
+            self.text = align_columns(rows);
+            return;
+        }
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+
+    /// Lay the text out in columns: its lines, and the ones added after,
+    /// are split into cells, see [`SectionText::columns`].
+    pub fn set_columns(&mut self) {
+        if self.columns.is_some() {
+            return;
+        }
+        let rows: Vec<_> = self.text.lines().map(split_cells).collect();
+        self.text = align_columns(&rows);
+        self.columns = Some(rows);
+    }
+
+    /// How wide each column is, in letters: as its widest cell. Empty when
+    /// the text is not laid out in columns.
+    #[must_use]
+    pub fn column_widths(&self) -> Vec<usize> {
+        self.columns.as_deref().map_or_else(Vec::new, column_widths)
+    }
+
+    /// The cells of the line at `row`, with the letter each one starts at,
+    /// when the text is laid out in columns. The backends that don't draw
+    /// each letter as wide as the others draw each cell on its own, there.
+    #[must_use]
+    pub fn cells(&self, row: usize) -> Option<Vec<(usize, &str)>> {
+        let cells = self.columns.as_ref()?.get(row)?;
+        let starts =
+            self.column_widths().into_iter().scan(0, |start, width| {
+                let this = *start;
+                *start += width + COLUMN_GAP;
+                Some(this)
+            });
+        Some(starts.zip(cells.iter().map(String::as_str)).collect())
+    }
+}
+
+/// Helper: the trimmed cells of `line`.
+fn split_cells(line: &str) -> Vec<String> {
+    line.split(COLUMN_SEPARATOR)
+        .map(|cell| cell.trim().to_string())
+        .collect()
+}
+
+/// Helper: the width of each column of `rows`, in letters.
+fn column_widths(rows: &[Vec<String>]) -> Vec<usize> {
+    let mut widths = vec![];
+    for row in rows {
+        for (idx, cell) in row.iter().enumerate() {
+            let len = cell.chars().count();
+            match widths.get_mut(idx) {
+                Some(width) => *width = len.max(*width),
+                None => widths.push(len),
+            }
+        }
+    }
+    widths
+}
+
+/// Helper: the lines of `rows`, with the cells padded to the width of their
+/// column. The backends draw each letter as wide as the others, so the
+/// columns start at the same place on each line.
+fn align_columns(rows: &[Vec<String>]) -> String {
+    let widths = column_widths(rows);
+    let gap = " ".repeat(COLUMN_GAP);
+    let mut text = String::new();
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        text.push_str(cells.join(&gap).trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// A chunk of a text line: either some plain text, or an inline image.
+///
+/// Inline images are written as `![alt](path)` in the text, and are drawn as
+/// high as the line they are in.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TextRun<'a> {
+    /// Some plain text.
+    Text(&'a str),
+    /// An image in the text flow.
+    Image {
+        /// The alternative text.
+        alt: &'a str,
+        /// The path of the image.
+        path: &'a str,
+    },
+}
+
+impl<'a> TextRun<'a> {
+    /// Split a single line of text into its runs. An escaped `\![` is plain
+    /// text, without the backslash.
+    #[must_use]
+    pub fn split(line: &'a str) -> Vec<Self> {
+        let mut runs = vec![];
+        // Where the text not in a run yet starts, and where to look for the
+        // next image.
+        let (mut start, mut from) = (0, 0);
+        while let Some(found) = line[from..].find("![") {
+            let beg = from + found;
+            if line[start..beg].ends_with('\\') {
+                push_text(&mut runs, &line[start..beg - 1]);
+                (start, from) = (beg, beg + 2);
+                continue;
+            }
+            let Some((alt, path, len)) = inline_image(&line[beg + 2..]) else {
+                from = beg + 2;
+                continue;
+            };
+            push_text(&mut runs, &line[start..beg]);
+            runs.push(Self::Image { alt, path });
+            start = beg + 2 + len;
+            from = start;
+        }
+        push_text(&mut runs, &line[start..]);
+        runs
+    }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+/// Add `text` to the `runs`, unless it is empty.
+fn push_text<'a>(runs: &mut Vec<TextRun<'a>>, text: &'a str) {
+    if !text.is_empty() {
+        runs.push(TextRun::Text(text));
+    }
+}
+
+/// Read the `alt](path)` that follows a `![`: the alt text has no `]`, and
+/// the path, that can't be empty, comes right after it. Return the alt text,
+/// the path, and how long all of it is.
+fn inline_image(rest: &str) -> Option<(&str, &str, usize)> {
+    let mid = rest.find(']')?;
+    let tail = rest[mid + 1..].strip_prefix('(')?;
+    let end = tail.find(')').filter(|&end| end > 0)?;
+    Some((&rest[..mid], &tail[..end], mid + end + 3))
+}
+
+/// Read a range of slides, numbered from 1 like the slide counter does, and
+/// get their indexes (from 0).
+///
+/// `10-20` and `10..=20` are the slides from 10
+/// to 20, both included, while `10..20` leaves the 20th out. Either end of
+/// `..` can be left out, like in `10..`, to go to the last slide.
+///
+/// # Errors
+///
+/// If the range is not in one of these forms, if it starts from 0, or if it
+/// starts after its end.
+pub fn parse_range(s: &str) -> Result<Range<usize>, String> {
+    let number = |n: &str| {
+        n.trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| format!("Expected a slide number, found {n:?}"))
+    };
+    let (start, end) = if let Some((start, end)) = s.split_once("..=") {
+        (start, Some(number(end)?))
+    } else if let Some((start, end)) = s.split_once("..") {
+        let end = if end.trim().is_empty() {
+            None
+        } else {
+            Some(number(end)? - 1)
+        };
+        (start, end)
+    } else if let Some((start, end)) = s.split_once('-') {
+        (start, Some(number(end)?))
+    } else {
+        return Err(format!("The range must be like 10-20 or 10..20: {s}"));
+    };
+    let start = if start.trim().is_empty() {
+        0
+    } else {
+        number(start)? - 1
+    };
+    if end.is_some_and(|end| end < start) {
+        return Err(format!("The range starts after its end: {s}"));
+    }
+    Ok(start..end.unwrap_or(usize::MAX))
+}
+
+/// Get the bytes embedded in a `data:` URI, like
+/// `data:image/png;base64,iVBORw0...`, or `None` if `uri` is not a data URI.
+///
+/// # Errors
+///
+/// If the data is not encoded in base64, or if the base64 is not valid.
+#[must_use]
+pub fn decode_data_uri(uri: &str) -> Option<Result<Vec<u8>, String>> {
+    let data = uri.strip_prefix("data:")?;
+    let Some((header, payload)) = data.split_once(',') else {
+        return Some(Err(
+            "A data URI must be like `data:<type>;base64,<data>`".into(),
+        ));
+    };
+    if !header.ends_with(";base64") {
+        return Some(Err(format!(
+            "Only base64 data is supported, found {header}"
+        )));
+    }
+    Some(decode_base64(payload))
+}
+
+/// Decode some base64 data, in the standard or in the URL safe alphabet.
+fn decode_base64(data: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    let (mut acc, mut bits) = (0_u32, 0);
+    for ch in data.trim_end_matches('=').bytes() {
+        let value = match ch {
+            b'A'..=b'Z' => ch - b'A',
+            b'a'..=b'z' => ch - b'a' + 26,
+            b'0'..=b'9' => ch - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => {
+                let ch = char::from(ch);
+                return Err(format!("Invalid character in base64 data: {ch}"));
+            }
+        };
+        acc = (acc << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            #[allow(clippy::cast_possible_truncation)]
+            bytes.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Ok(bytes)
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
 /// Define a section that contains a figure.
 pub struct SectionFigure {
-    /// Path to the actual figure's location on disk
+    /// Path to the actual figure's location on disk, or a `data:` URI with
+    /// the figure itself (see [`decode_data_uri`]).
     pub path: String,
     /// The rotation, in degrees
     pub rotation: f32,
+    /// How much the figure covers what is behind it, from 0 (invisible) to
+    /// 1 (the default).
+    #[serde(default = "SectionFigure::full_opacity")]
+    pub opacity: f32,
+    /// All the images of the figure, when it is a sequence shown one after
+    /// the other, like a flipbook. The first one is also the `path`. Empty
+    /// for a single image.
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// How long each image of a sequence is shown, in milliseconds.
+    #[serde(default = "SectionFigure::default_frame_ms")]
+    pub frame_ms: u32,
+    /// The point the figure rotates around, relative to the figure: (0, 0)
+    /// is its top-left corner, and (1, 1) the bottom-right one. Without it,
+    /// the figure rotates around its center.
+    #[serde(default)]
+    pub anchor: Option<Position>,
+    /// The caption, written below the image.
+    #[serde(default)]
+    pub caption: Option<String>,
+}
+
+/// How long each image of a sequence is shown, when the figure does not say
+/// otherwise.
+pub const DEFAULT_FRAME_MS: u32 = 500;
+
+impl SectionFigure {
+    /// Helper: the default opacity, when it is not given.
+    const fn full_opacity() -> f32 {
+        1.0
+    }
+
+    /// Helper: the default time each image is shown, when it is not given.
+    const fn default_frame_ms() -> u32 {
+        DEFAULT_FRAME_MS
+    }
+
+    /// If the figure is a sequence of images.
+    #[must_use]
+    pub const fn is_sequence(&self) -> bool {
+        self.paths.len() > 1
+    }
+
+    /// The image to show, `elapsed` after the figure was shown first: the
+    /// images of a sequence are shown in a loop.
+    #[must_use]
+    pub fn frame_path(&self, elapsed: Duration) -> &str {
+        if !self.is_sequence() || self.frame_ms == 0 {
+            return &self.path;
+        }
+        let frame = elapsed.as_millis() / u128::from(self.frame_ms);
+        let idx = frame % self.paths.len() as u128;
+        usize::try_from(idx).map_or(&self.path, |idx| &self.paths[idx])
+    }
+
+    /// The paths of all the images of the figure: the one image, or all the
+    /// images of a sequence.
+    #[must_use]
+    pub fn image_paths(&self) -> Vec<&str> {
+        if self.is_sequence() {
+            self.paths.iter().map(String::as_str).collect()
+        } else {
+            vec![self.path.as_str()]
+        }
+    }
 }
 
 impl Default for SectionFigure {
@@ -84,12 +624,17 @@ impl Default for SectionFigure {
         Self {
             path: String::new(),
             rotation: 0.0,
+            opacity: Self::full_opacity(),
+            paths: Vec::new(),
+            frame_ms: DEFAULT_FRAME_MS,
+            anchor: None,
+            caption: None,
         }
     }
 }
 
 /// The main entry in each section.
-#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
 pub enum SectionMain {
     /// The variant that represents a picture.
     Figure(SectionFigure),
@@ -97,28 +642,158 @@ pub enum SectionMain {
     Text(SectionText),
 }
 
+/// Who can see a section.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+)]
+pub enum Visibility {
+    /// Everybody: the section is in the main window and in the presenter's
+    /// one.
+    #[default]
+    Both,
+    /// Only the audience, in the main window.
+    Main,
+    /// Only the presenter, in the side window.
+    Presenter,
+}
+
+impl Visibility {
+    /// If the section is shown in the main window.
+    #[must_use]
+    pub const fn on_main(self) -> bool {
+        matches!(self, Self::Both | Self::Main)
+    }
+
+    /// If the section is shown in the presenter's window.
+    #[must_use]
+    pub const fn on_presenter(self) -> bool {
+        matches!(self, Self::Both | Self::Presenter)
+    }
+}
+
 /// The internal representation for a `section`.
 /// The section can contain text, has a size, a position,
 /// and so on and so forth.
-#[derive(serde::Serialize, serde::Deserialize, Debug, Default, PartialEq)]
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq,
+)]
 pub struct Section {
     /// The size of the section.
     pub size: Option<Size>,
     /// The position of the section in the slide.
     pub position: Option<Position>,
+    /// The coordinate of the position that was not given, if any, like with
+    /// `:ps x 0.3`: the backend picks it, as if there were no position. See
+    /// [`Section::position_or`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub free_axis: Option<Axis>,
     /// The specific section.
     pub sec_main: Option<SectionMain>,
+    /// Who can see the section.
+    #[serde(default)]
+    pub visibility: Visibility,
+    /// The color of the box behind the section, if any.
+    #[serde(default)]
+    pub bg_color: Option<Color>,
+    /// The profiles the section is shown in: all of them, when empty.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl Section {
+    /// Where the section starts, if it has a position: the coordinate that
+    /// was not given, if any, is taken from `default`.
+    #[must_use]
+    pub fn position_or(&self, default: &Position) -> Option<Position> {
+        let mut position = self.position.clone()?;
+        match self.free_axis {
+            Some(Axis::X) => position.x = default.x,
+            Some(Axis::Y) => position.y = default.y,
+            None => {}
+        }
+        Some(position)
+    }
+
+    /// The coordinates of the position that were given, if any.
+    #[must_use]
+    pub const fn coordinates(&self) -> (Option<f32>, Option<f32>) {
+        let Some(position) = &self.position else {
+            return (None, None);
+        };
+        match self.free_axis {
+            Some(Axis::X) => (None, Some(position.y)),
+            Some(Axis::Y) => (Some(position.x), None),
+            None => (Some(position.x), Some(position.y)),
+        }
+    }
+
+    /// Set the coordinates of the position: the one that is not given is
+    /// left to the backend, and without any, there is no position.
+    pub const fn set_coordinates(&mut self, x: Option<f32>, y: Option<f32>) {
+        (self.position, self.free_axis) = match (x, y) {
+            (Some(x), Some(y)) => (Some(Position { x, y }), None),
+            (Some(x), None) => (Some(Position { x, y: 0.0 }), Some(Axis::Y)),
+            (None, Some(y)) => (Some(Position { x: 0.0, y }), Some(Axis::X)),
+            (None, None) => (None, None),
+        };
+    }
+}
+
+/// How the sections of a slide are laid out by the parser, instead of by
+/// their positions.
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq,
+)]
+pub enum Layout {
+    /// A title slide, made with `:title`: its text is a big title in the
+    /// middle of the slide, with the `subtitle` and the `author` of the
+    /// slideshow's metadata below it, if any.
+    Title,
 }
 
 /// The representation of a single slide.
 /// It has a background color and one or more sections.
 /// Each section contains either text, or an image, or both.
-#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
 pub struct Slide {
     /// The default backgound color.
     pub bg_color: Option<Color>,
     /// The list of sections in the single slide.
     pub sections: Vec<Section>,
+    /// Where the slide goes in the slideshow: the slides are sorted by this,
+    /// and then by where they are in the input.
+    #[serde(default)]
+    pub order: i32,
+    /// The chapter the slide belongs to: the consecutive slides in the same
+    /// group make a chapter, that can be skipped as a whole.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// If the slide is counted by the slide counter. Cover slides and
+    /// section dividers may be left out, so that they don't inflate the
+    /// total.
+    #[serde(default = "Slide::counted_by_default")]
+    pub counted: bool,
+    /// The rows and the columns of the grid the figures with no position
+    /// are placed on, one per cell, row by row.
+    #[serde(default)]
+    pub grid: Option<(u32, u32)>,
+    /// The profiles the slide is shown in: all of them, when empty.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The gradient drawn as the background, instead of the `bg_color`.
+    #[serde(default)]
+    pub gradient: Option<Gradient>,
+    /// How the sections are laid out, if not by their positions. The parser
+    /// takes it away once it has [applied](Self::apply_layout) it.
+    #[serde(default)]
+    pub layout: Option<Layout>,
 }
 
 impl Slide {
@@ -127,27 +802,1069 @@ impl Slide {
     pub const fn default() -> Self {
         let sections = vec![];
         let bg_color = None;
-        Self { bg_color, sections }
+        Self {
+            bg_color,
+            sections,
+            order: 0,
+            group: None,
+            counted: true,
+            grid: None,
+            tags: vec![],
+            gradient: None,
+            layout: None,
+        }
+    }
+
+    /// Helper: the slides are counted, unless they say otherwise.
+    const fn counted_by_default() -> bool {
+        true
+    }
+
+    /// The slide the backends show when there are no slides at all: it
+    /// just says so, and it is not counted.
+    #[must_use]
+    pub fn placeholder() -> Self {
+        let text = Section {
+            position: Some(Position { x: 0.4, y: 0.45 }),
+            sec_main: Some(SectionMain::Text(SectionText::from_lines([
+                NO_SLIDES,
+            ]))),
+            ..Section::default()
+        };
+        Self {
+            sections: vec![text],
+            counted: false,
+            ..Self::default()
+        }
     }
 }
 
+/// How wide and how high a text of a title slide is, without its blank
+/// lines around it, that are removed.
+fn title_extent(section: &mut Section) -> (f32, f32) {
+    let (Some(size), Some(SectionMain::Text(text))) =
+        (&section.size, &mut section.sec_main)
+    else {
+        return (0.0, 0.0);
+    };
+    text.text = format!("{}\n", text.text.trim_matches('\n'));
+    let rows = text.text.lines().count();
+    let longest = text.text.lines().map(|l| l.chars().count()).max();
+    #[allow(clippy::cast_precision_loss)]
+    let (width, rows) = (longest.unwrap_or(0) as f32 * size.w, rows as f32);
+    let line_step = size.h * text.line_height.unwrap_or(1.0);
+    (width, line_step.mul_add(rows - 1.0, size.h))
+}
+
+/// The room left around a figure in its grid cell, as a fraction of the
+/// cell.
+const GRID_MARGIN: f32 = 0.05;
+
+/// Where the `ordinal`-th cell of a grid of `rows` by `cols` is, counting
+/// row by row, and how big a figure in it is.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn grid_cell((rows, cols): (u32, u32), ordinal: usize) -> (Position, Size) {
+    let (rows, cols) = (rows.max(1) as usize, cols.max(1) as usize);
+    let (row, col) = (ordinal / cols, ordinal % cols);
+    let (w, h) = (1.0 / cols as f32, 1.0 / rows as f32);
+    let position = Position {
+        x: (col as f32 + GRID_MARGIN) * w,
+        y: (row as f32 + GRID_MARGIN) * h,
+    };
+    let size = Size {
+        w: w * 2.0f32.mul_add(-GRID_MARGIN, 1.0),
+        h: h * 2.0f32.mul_add(-GRID_MARGIN, 1.0),
+    };
+    (position, size)
+}
+
+/// What the [placeholder](Slide::placeholder) slide says.
+pub const NO_SLIDES: &str = "No slides";
+
 /// The whole slideshow we have to render.
 ///
 /// Note that not all the information are used by all the backends. But since
 /// we have a single parser and multiple backends, it is what it is.
-#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq,
+)]
 pub struct Slideshow {
     /// The slides to be shown.
     pub slides: Vec<Slide>,
-    /// The hashmap containing the association between the
-    /// font names and their path.
+    /// The map containing the association between the
+    /// font names and their path, sorted by name so that the slideshow is
+    /// always serialized the same way.
     ///
-    /// Unused at the moment, as there is only a single font available for SDL.
-    pub fonts: HashMap<String, String>,
+    /// SDL loads them, and the text can be drawn with any of them instead of
+    /// the built-in font, by cycling through them at runtime. Like the
+    /// figures, the relative paths are relative to the `base_folder`.
+    pub fonts: BTreeMap<String, String>,
     /// The default background color.
     pub bg_col: Option<Color>,
+    /// The default background gradient, instead of the `bg_col`.
+    #[serde(default)]
+    pub gradient: Option<Gradient>,
     /// The default font color.
     pub font_col: Option<Color>,
     /// The default font size.
     pub font_size: Option<Size>,
+    /// What is known about the slideshow, like its `title`, its `author`
+    /// and its `date`, set with `:meta` in the `:ge` section. It is sorted
+    /// by key, so that the slideshow is always serialized the same way.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    /// The message of the slideshows that show an error, as built by
+    /// [`Slideshow::from_error`]. It is not saved: the slides read from a
+    /// file never show an error.
+    #[serde(skip)]
+    pub error: Option<String>,
+    /// The folder the slides were read from: the relative paths of the
+    /// figures are relative to it, see [`Slideshow::resolve_path`]. It is
+    /// not saved, so that the slides can be moved with their images.
+    #[serde(skip)]
+    pub base_folder: PathBuf,
+    /// The temporary folder the slides were read from, like the one a zip
+    /// bundle is extracted into, if any. It is not saved.
+    #[serde(skip)]
+    pub temp_folder: TempFolder,
+}
+
+/// A temporary folder the slides need while they are shown, like the one a
+/// zip bundle is extracted into. It is removed from the disk once the last
+/// copy of the slideshow that holds it is dropped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TempFolder(Option<Arc<RemovedOnDrop>>);
+
+/// A folder that is removed when dropped.
+#[derive(Debug, PartialEq, Eq)]
+struct RemovedOnDrop(PathBuf);
+
+impl Drop for RemovedOnDrop {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            tracing::warn!("Unable to remove {}: {e}", self.0.display());
+        }
+    }
+}
+
+impl TempFolder {
+    /// Take care of `path`, an existing folder: it is removed with the
+    /// last copy of the returned value.
+    #[must_use]
+    pub fn new(path: PathBuf) -> Self {
+        Self(Some(Arc::new(RemovedOnDrop(path))))
+    }
+
+    /// The folder, if any.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.0.as_deref().map(|folder| folder.0.as_path())
+    }
+}
+
+impl std::fmt::Display for Slideshow {
+    /// A short summary: the number of slides, and the title and the number
+    /// of sections of each one.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        let len = self.slides.len();
+        write!(f, "{len} slide{}", plural(len))?;
+        for (idx, (slide, title)) in
+            self.slides.iter().zip(self.titles()).enumerate()
+        {
+            let sections = slide.sections.len();
+            write!(
+                f,
+                "\n  {}. {title} ({sections} section{})",
+                idx + 1,
+                plural(sections)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The title given to the slides without any text.
+pub const UNTITLED: &str = "(untitled)";
+
+/// A slide, with the colors and sizes it is drawn with once the defaults are
+/// applied: the section's own ones win over the slide's, then over the
+/// slideshow's, and then over the built-in ones.
+#[derive(Debug, PartialEq)]
+pub struct ResolvedSlide<'a> {
+    /// The slide itself.
+    pub slide: &'a Slide,
+    /// The background color.
+    pub bg_col: Color,
+    /// The sections of the slide, in order.
+    pub sections: Vec<ResolvedSection<'a>>,
+}
+
+/// A section, with the colors and sizes it is drawn with, see
+/// [`ResolvedSlide`].
+#[derive(Debug, PartialEq)]
+pub struct ResolvedSection<'a> {
+    /// The section itself.
+    pub section: &'a Section,
+    /// The color of the text, for a text section.
+    pub font_col: Option<Color>,
+    /// The size: of each letter for a text, of the image for a figure.
+    pub size: (f32, f32),
+}
+
+/// The words per minute used to estimate how long a slideshow lasts, when
+/// nothing else is given.
+pub const DEFAULT_WPM: u32 = 130;
+
+/// Some statistics about a slideshow, useful to plan a rehearsal.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SlideshowStats {
+    /// The number of slides.
+    pub slides: usize,
+    /// The number of words in all the text sections.
+    pub words: usize,
+    /// The number of words in each slide.
+    pub words_per_slide: Vec<usize>,
+}
+
+/// What changed from a slideshow to another one, as given by
+/// [`Slideshow::diff`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SlideshowDiff {
+    /// The slides that are in both, but changed, by their index in the new
+    /// slideshow.
+    pub changed: Vec<usize>,
+    /// The new slides, by their index in the new slideshow.
+    pub added: Vec<usize>,
+    /// The slides that are gone, by their index in the old slideshow.
+    pub removed: Vec<usize>,
+    /// If the defaults of the slideshow (the colors, the size of the
+    /// letters, or the fonts) changed: then, every slide may look different.
+    pub defaults_changed: bool,
+}
+
+impl SlideshowDiff {
+    /// If nothing changed at all.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.changed.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && !self.defaults_changed
+    }
+}
+
+impl SlideshowStats {
+    /// The estimated time needed to say all the words, speaking at `wpm`
+    /// words per minute. A `wpm` of 0 is considered as 1.
+    #[must_use]
+    pub fn estimated_duration(&self, wpm: u32) -> Duration {
+        let millis = self.words as u64 * 60_000 / u64::from(wpm.max(1));
+        Duration::from_millis(millis)
+    }
+}
+
+impl Slide {
+    /// The figure sections of the slide, in order.
+    pub fn figures(&self) -> impl Iterator<Item = &SectionFigure> {
+        self.sections.iter().filter_map(|s| match &s.sec_main {
+            Some(SectionMain::Figure(fig)) => Some(fig),
+            _ => None,
+        })
+    }
+
+    /// The slide with the figures that have no position placed on its
+    /// [grid](Self::grid), or the slide itself if it has no grid.
+    ///
+    /// A figure with no size takes the one of its cell, and the figures
+    /// that do not fit in the grid are left where they are.
+    #[must_use]
+    pub fn place_on_grid(&self) -> Cow<'_, Self> {
+        let Some(grid) = self.grid else {
+            return Cow::Borrowed(self);
+        };
+        let mut slide = self.clone();
+        let loose = slide.sections.iter_mut().filter(|s| {
+            s.position.is_none()
+                && matches!(s.sec_main, Some(SectionMain::Figure(_)))
+        });
+        let cells = (grid.0 * grid.1) as usize;
+        for (ordinal, section) in loose.take(cells).enumerate() {
+            let (position, size) = grid_cell(grid, ordinal);
+            section.position = Some(position);
+            section.size.get_or_insert(size);
+        }
+        Cow::Owned(slide)
+    }
+
+    /// Lay the sections out as the [layout](Self::layout) says, with the
+    /// `metadata` of the slideshow. Nothing changes without a layout.
+    ///
+    /// On a title slide, the texts with no position are centered one below
+    /// the other: the first one is the title, and the metadata come last.
+    /// The layout is done then, and taken away: laying the slide out again,
+    /// like once more after an import, changes nothing.
+    pub fn apply_layout(&mut self, metadata: &BTreeMap<String, String>) {
+        let Some(Layout::Title) = self.layout.take() else {
+            return;
+        };
+        let details: Vec<&str> = TITLE_METADATA
+            .iter()
+            .filter_map(|key| metadata.get(*key))
+            .map(String::as_str)
+            .collect();
+        if !details.is_empty() {
+            self.sections.push(Section {
+                size: Some(TITLE_DETAILS_SIZE),
+                sec_main: Some(SectionMain::Text(SectionText::from_lines(
+                    details,
+                ))),
+                ..Section::default()
+            });
+        }
+        let mut texts: Vec<&mut Section> = self
+            .sections
+            .iter_mut()
+            .filter(|s| {
+                s.position.is_none()
+                    && matches!(
+                        &s.sec_main,
+                        Some(SectionMain::Text(t)) if !t.text.trim().is_empty()
+                    )
+            })
+            .collect();
+        let mut extents = vec![];
+        for (idx, section) in texts.iter_mut().enumerate() {
+            let size = if idx == 0 {
+                TITLE_SIZE
+            } else {
+                TITLE_DETAILS_SIZE
+            };
+            section.size.get_or_insert(size);
+            extents.push(title_extent(section));
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let gaps = extents.len().saturating_sub(1) as f32;
+        let height = extents.iter().map(|(_, h)| h).sum::<f32>();
+        let mut top = (TITLE_GAP.mul_add(-gaps, 1.0 - height) / 2.0).max(0.0);
+        for (section, (width, height)) in texts.into_iter().zip(extents) {
+            let x = ((1.0 - width) / 2.0).max(0.0);
+            section.position = Some(Position { x, y: top });
+            top += height + TITLE_GAP;
+        }
+    }
+
+    /// The text sections of the slide, code included, in order.
+    pub fn texts(&self) -> impl Iterator<Item = &SectionText> {
+        self.sections.iter().filter_map(|s| match &s.sec_main {
+            Some(SectionMain::Text(text)) => Some(text),
+            _ => None,
+        })
+    }
+
+    /// The lines of all the text sections of the slide, in order.
+    pub fn text_lines(&self) -> impl Iterator<Item = &str> {
+        self.texts().flat_map(|t| t.text.lines())
+    }
+
+    /// The title of the slide, that is its first line with some text.
+    #[must_use]
+    pub fn title(&self) -> Option<&str> {
+        self.text_lines().map(str::trim).find(|l| !l.is_empty())
+    }
+
+    /// Count the words in the text sections of the slide. Inline images are
+    /// not words, so they are not counted.
+    #[must_use]
+    pub fn word_count(&self) -> usize {
+        self.text_lines()
+            .flat_map(TextRun::split)
+            .map(|run| match run {
+                TextRun::Text(text) => text.split_whitespace().count(),
+                TextRun::Image { .. } => 0,
+            })
+            .sum()
+    }
+
+    /// The paths of the images used in the slide: the figures, and the
+    /// inline images in the text.
+    #[must_use]
+    pub fn image_paths(&self) -> Vec<&str> {
+        let figures = self.figures().flat_map(SectionFigure::image_paths);
+        let inline =
+            self.text_lines()
+                .flat_map(TextRun::split)
+                .filter_map(|run| match run {
+                    TextRun::Image { path, .. } => Some(path),
+                    TextRun::Text(_) => None,
+                });
+        figures.chain(inline).collect()
+    }
+}
+
+impl Slideshow {
+    /// Where the image at `path`, as written in the slides, is: the relative
+    /// paths are relative to the [`Slideshow::base_folder`]. The `data:`
+    /// URIs are given back as they are.
+    #[must_use]
+    pub fn resolve_path(&self, path: &str) -> String {
+        if decode_data_uri(path).is_some() {
+            return path.to_string();
+        }
+        let full = self.base_folder.join(path);
+        full.canonicalize()
+            .unwrap_or(full)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// A slideshow with a single slide, that shows `message` in red: it is
+    /// shown instead of the slides that can't be read, so that the mistake
+    /// is seen right away.
+    #[must_use]
+    pub fn from_error(message: &str) -> Self {
+        let mut text = SectionText::from_lines(message.lines());
+        text.color = Some(ERROR_COLOR);
+        // The messages can be long: they are wrapped in the slide.
+        text.box_width = Some(0.9);
+        let section = Section {
+            position: Some(Position { x: 0.05, y: 0.05 }),
+            size: Some(Size { w: 0.02, h: 0.05 }),
+            sec_main: Some(SectionMain::Text(text)),
+            ..Section::default()
+        };
+        Self {
+            slides: vec![Slide {
+                sections: vec![section],
+                ..Slide::default()
+            }],
+            error: Some(message.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// If the slideshow shows an error, as built by [`Self::from_error`].
+    #[must_use]
+    pub const fn is_error(&self) -> bool {
+        self.error.is_some()
+    }
+
+    /// The paths of the images of all the figures, slide after slide: the
+    /// images used more than once are there more than once. The images
+    /// inside the text are left out, see [`Slide::image_paths`] for them.
+    #[must_use]
+    pub fn all_figure_paths(&self) -> Vec<&str> {
+        self.slides
+            .iter()
+            .flat_map(Slide::figures)
+            .flat_map(SectionFigure::image_paths)
+            .collect()
+    }
+
+    /// The slides, with the colors and sizes they are drawn with once the
+    /// defaults are applied.
+    pub fn resolved_slides(&self) -> impl Iterator<Item = ResolvedSlide<'_>> {
+        let bg_col = self.bg_col.unwrap_or(DEFAULT_BG_COLOR);
+        let font_col = self.font_col.unwrap_or(DEFAULT_FONT_COLOR);
+        let font_size = self.font_size.as_ref().unwrap_or(&DEFAULT_FONT_SIZE);
+        self.slides.iter().map(move |slide| ResolvedSlide {
+            slide,
+            bg_col: slide.bg_color.unwrap_or(bg_col),
+            sections: slide
+                .sections
+                .iter()
+                .map(|section| {
+                    let (font_col, size) = match &section.sec_main {
+                        Some(SectionMain::Figure(_)) => {
+                            (None, &DEFAULT_FIGURE_SIZE)
+                        }
+                        Some(SectionMain::Text(text)) => {
+                            (Some(text.color.unwrap_or(font_col)), font_size)
+                        }
+                        None => (None, font_size),
+                    };
+                    ResolvedSection {
+                        section,
+                        font_col,
+                        size: section.size.as_ref().unwrap_or(size).into(),
+                    }
+                })
+                .collect(),
+        })
+    }
+
+    /// Keep only the slides in `range` (0 based), like one given by
+    /// [`parse_range`], and get the range that was actually kept.
+    ///
+    /// The ends past the last slide are moved back to it.
+    pub fn retain_range(&mut self, range: Range<usize>) -> Range<usize> {
+        let len = self.slides.len();
+        let start = range.start.min(len);
+        let kept = start..range.end.clamp(start, len);
+        self.slides.truncate(kept.end);
+        self.slides.drain(..kept.start);
+        kept
+    }
+
+    /// Keep only the slides, and the sections, shown in `profile`: the ones
+    /// with no tags, and the ones tagged with it.
+    pub fn retain_profile(&mut self, profile: &str) {
+        let shown = |tags: &[String]| {
+            tags.is_empty() || tags.iter().any(|t| t == profile)
+        };
+        self.slides.retain(|slide| shown(&slide.tags));
+        for slide in &mut self.slides {
+            slide.sections.retain(|section| shown(&section.tags));
+        }
+    }
+
+    /// The slide at `idx` (0 based), if there is one.
+    #[must_use]
+    pub fn slide_at(&self, idx: usize) -> Option<&Slide> {
+        self.slides.get(idx)
+    }
+
+    /// The title of each slide, in order (see [`Slide::title`]). The slides
+    /// without any text are [`UNTITLED`].
+    #[must_use]
+    pub fn titles(&self) -> Vec<String> {
+        self.slides
+            .iter()
+            .map(|s| s.title().unwrap_or(UNTITLED).to_string())
+            .collect()
+    }
+
+    /// Compute the statistics of the slideshow.
+    #[must_use]
+    pub fn stats(&self) -> SlideshowStats {
+        let words_per_slide: Vec<usize> =
+            self.slides.iter().map(Slide::word_count).collect();
+        SlideshowStats {
+            slides: self.slides.len(),
+            words: words_per_slide.iter().sum(),
+            words_per_slide,
+        }
+    }
+
+    /// What changed from this slideshow to `other`.
+    ///
+    /// The slides that are the same at the start and at the end of both are
+    /// left out: the ones in between are compared one by one, and the ones
+    /// left over are added (or removed). So a slide added, removed or
+    /// changed is pinpointed, while a slide moved far away shows up as many
+    /// changed ones.
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> SlideshowDiff {
+        let (old, new) = (&self.slides, &other.slides);
+        let prefix = old.iter().zip(new).take_while(|(o, n)| o == n).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(o, n)| o == n)
+            .count();
+        let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+        let common = (old_end - prefix).min(new_end - prefix);
+        SlideshowDiff {
+            changed: (prefix..prefix + common).collect(),
+            added: (prefix + common..new_end).collect(),
+            removed: (prefix + common..old_end).collect(),
+            defaults_changed: self.fonts != other.fonts
+                || self.bg_col != other.bg_col
+                || self.gradient != other.gradient
+                || self.font_col != other.font_col
+                || self.font_size != other.font_size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_fonts_serialized_in_order() {
+        let fonts = [("sans", "sans.ttf"), ("mono", "mono.ttf")];
+        let metadata = [("title", "My Talk"), ("author", "Jane Doe")];
+        let map = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                .collect()
+        };
+        let serialized = |fonts: &[(&str, &str)], metadata: &[(&str, &str)]| {
+            let slideshow = Slideshow {
+                fonts: map(fonts),
+                metadata: map(metadata),
+                ..Slideshow::default()
+            };
+            serde_json::to_string(&slideshow).unwrap()
+        };
+        let first = serialized(&fonts, &metadata);
+        let (mut fonts_back, mut metadata_back) = (fonts, metadata);
+        fonts_back.reverse();
+        metadata_back.reverse();
+        assert_eq!(first, serialized(&fonts_back, &metadata_back));
+        assert!(
+            first.contains(r#""fonts":{"mono":"mono.ttf","sans":"sans.ttf"}"#),
+            "{first}"
+        );
+        assert!(
+            first.contains(
+                r#""metadata":{"author":"Jane Doe","title":"My Talk"}"#
+            ),
+            "{first}"
+        );
+    }
+
+    /// The example deck in the README.
+    const README_DECK: &str = r"# Comments are ignored
+:ge :bc green :fc yellow :sz 16
+
+:sl
+:tb :sz 40 :fc red
+BIG TITLE
+:tb
+A line
+  Note that it starts just below the title!
+
+:sl
+:tb :sz 10 :fc blue
+Small title now
+:tb
+But again, the line is just below the title
+
+:sl
+:tb :ps 0.3 0.3 :fc fuchsia
+ We can also
+center the text
+ manually!
+";
+
+    #[test]
+    fn test_text_from_lines() {
+        let mut text = SectionText::from_lines(["A title", "", "a line"]);
+        text.push_line("another one");
+        assert_eq!(text.text, "A title\n\na line\nanother one\n");
+
+        // The same text the parser builds.
+        let slides = crate::parser::parse_text(
+            ":sl\n:tb\nA title\n\na line\nanother one\n",
+            Path::new(""),
+        )
+        .unwrap();
+        let Some(SectionMain::Text(parsed)) =
+            &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("Not a text section");
+        };
+        assert_eq!(parsed.text, text.text);
+
+        // And the lines come back when the backends split it.
+        let lines: Vec<&str> = text.text.split('\n').collect();
+        assert_eq!(lines, ["A title", "", "a line", "another one", ""]);
+    }
+
+    #[test]
+    fn test_from_error() {
+        let slides = Slideshow::from_error("Bad token\nat line 3");
+        assert_eq!(slides.slides.len(), 1);
+        let Some(SectionMain::Text(text)) =
+            &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("Not a text section");
+        };
+        assert_eq!(text.text, "Bad token\nat line 3\n");
+        assert_eq!(text.color, Some(ERROR_COLOR));
+        assert!(slides.is_error());
+        assert!(!Slideshow::default().is_error());
+        // The metadata written in the slides can't pass for an error.
+        let mut slides = Slideshow::default();
+        slides.metadata.insert("error".into(), "none".into());
+        assert!(!slides.is_error());
+    }
+
+    #[test]
+    fn test_position_or() {
+        let default = Position { x: 0.1, y: 0.2 };
+        let mut section = Section::default();
+        assert_eq!(section.position_or(&default), None);
+        section.set_coordinates(Some(0.5), None);
+        assert_eq!(section.coordinates(), (Some(0.5), None));
+        assert_eq!(
+            section.position_or(&default),
+            Some(Position { x: 0.5, y: 0.2 })
+        );
+        section.set_coordinates(None, Some(0.7));
+        assert_eq!(
+            section.position_or(&default),
+            Some(Position { x: 0.1, y: 0.7 })
+        );
+        section.set_coordinates(Some(0.3), Some(0.4));
+        assert_eq!(section.free_axis, None);
+        section.set_coordinates(None, None);
+        assert_eq!(section.position, None);
+    }
+
+    #[test]
+    fn test_frame_path() {
+        let mut fig = SectionFigure {
+            path: String::from("a.png"),
+            ..SectionFigure::default()
+        };
+        assert_eq!(fig.frame_path(Duration::from_secs(3)), "a.png");
+
+        fig.paths = vec!["a.png".into(), "b.png".into(), "c.png".into()];
+        fig.frame_ms = 100;
+        let at = |ms| fig.frame_path(Duration::from_millis(ms));
+        assert_eq!(at(0), "a.png");
+        assert_eq!(at(99), "a.png");
+        assert_eq!(at(100), "b.png");
+        assert_eq!(at(250), "c.png");
+        assert_eq!(at(300), "a.png");
+    }
+
+    #[test]
+    fn test_figures_and_texts() {
+        let text = ":sl\n:tb\nHello\n:fg star.jpg square.png\n:code\nf()\n\
+                    :sl\n:fg square.png\n:tb\nSee ![a](star.jpg)\n";
+        let base = Path::new("./resources");
+        let slideshow = crate::parser::parse_text(text, base).unwrap();
+        let first = &slideshow.slides[0];
+        assert_eq!(first.figures().count(), 1);
+        let texts: Vec<_> = first.texts().map(|t| t.is_code).collect();
+        assert_eq!(texts, [false, true]);
+
+        let names = |paths: Vec<&str>| -> Vec<String> {
+            let name = |p: &str| Path::new(p).file_name().map(|n| n.to_owned());
+            paths
+                .into_iter()
+                .filter_map(name)
+                .map(|n| n.into_string().unwrap())
+                .collect()
+        };
+        assert_eq!(
+            names(slideshow.all_figure_paths()),
+            ["star.jpg", "square.png", "square.png"]
+        );
+        let second = names(slideshow.slides[1].image_paths());
+        assert_eq!(second, ["square.png", "star.jpg"]);
+    }
+
+    #[test]
+    fn test_gradient_color_at() {
+        let gradient = Gradient {
+            from: Color::from((0, 0, 0, 255)),
+            to: Color::from((255, 100, 10, 255)),
+            direction: GradientDirection::Vertical,
+        };
+        assert_eq!(gradient.color_at(0.0), gradient.from);
+        assert_eq!(gradient.color_at(1.0), gradient.to);
+        assert_eq!(gradient.color_at(0.5), Color::from((128, 50, 5, 255)));
+        // Out of the range, the colors at the ends are kept.
+        assert_eq!(gradient.color_at(-1.0), gradient.from);
+        assert_eq!(gradient.color_at(2.0), gradient.to);
+    }
+
+    #[test]
+    fn test_place_on_grid() {
+        let text = ":sl :grid 2 2\n:fg star.jpg\n:fg star.jpg\n:tb\nGallery\n\
+                    :fg star.jpg\n:fg star.jpg :ps 0.9 0.9\n:fg star.jpg\n\
+                    :fg star.jpg\n";
+        let base = Path::new("./resources");
+        let slideshow = crate::parser::parse_text(text, base).unwrap();
+        let slide = slideshow.slides[0].place_on_grid();
+        let placed: Vec<_> = slide
+            .sections
+            .iter()
+            .filter(|s| matches!(s.sec_main, Some(SectionMain::Figure(_))))
+            .map(|s| s.position.as_ref().map(|p| (p.x > 0.5, p.y > 0.5)))
+            .collect();
+        // One figure per quadrant, row by row: the explicit position is
+        // kept, and the figure that does not fit is left alone.
+        let quadrants = [
+            Some((false, false)),
+            Some((true, false)),
+            Some((false, true)),
+            Some((true, true)),
+            Some((true, true)),
+            None,
+        ];
+        assert_eq!(placed, quadrants);
+        let size = slide.sections[0].size.as_ref().unwrap();
+        assert!((size.w - 0.45).abs() < 1e-6 && (size.h - 0.45).abs() < 1e-6);
+        // The text is not on the grid.
+        assert!(slide.sections[2].position.is_none());
+
+        // Without a grid, nothing moves.
+        let slide = Slide::default();
+        assert!(matches!(slide.place_on_grid(), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_retain_profile() {
+        let text = ":sl\n:tb\nFor all\n:tb :tag advanced\nThe details\n\
+                    :sl :tag beginner\n:tb\nThe basics\n\
+                    :sl :tag advanced expert\n:tb\nDeep dive\n\
+                    :tb :tag beginner\nSkip this\n";
+        let parse = || crate::parser::parse_text(text, Path::new("")).unwrap();
+        let sections = |slideshow: &Slideshow| -> Vec<usize> {
+            slideshow.slides.iter().map(|s| s.sections.len()).collect()
+        };
+        assert_eq!(sections(&parse()), [2, 1, 2]);
+
+        let mut advanced = parse();
+        advanced.retain_profile("advanced");
+        assert_eq!(sections(&advanced), [2, 1]);
+        assert_eq!(advanced.titles(), ["For all", "Deep dive"]);
+
+        let mut beginner = parse();
+        beginner.retain_profile("beginner");
+        assert_eq!(sections(&beginner), [1, 1]);
+        assert_eq!(beginner.titles(), ["For all", "The basics"]);
+
+        let mut other = parse();
+        other.retain_profile("other");
+        assert_eq!(sections(&other), [1]);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("10-20"), Ok(9..20));
+        assert_eq!(parse_range("10..=20"), Ok(9..20));
+        assert_eq!(parse_range("10..20"), Ok(9..19));
+        assert_eq!(parse_range(" 3 .. "), Ok(2..usize::MAX));
+        assert_eq!(parse_range("..2"), Ok(0..1));
+        assert!(parse_range("0-3").is_err());
+        assert!(parse_range("3").is_err());
+        assert!(parse_range("a..b").is_err());
+        assert!(parse_range("20-10").is_err());
+        assert!(parse_range("5..4").is_err());
+        assert_eq!(parse_range("5-5"), Ok(4..5));
+    }
+
+    #[test]
+    fn test_retain_range() {
+        let deck = |n: usize| Slideshow {
+            slides: (0..n)
+                .map(|i| Slide {
+                    order: i32::try_from(i).unwrap(),
+                    ..Slide::default()
+                })
+                .collect(),
+            ..Slideshow::default()
+        };
+        let mut slideshow = deck(5);
+        assert_eq!(slideshow.retain_range(1..3), 1..3);
+        let orders: Vec<_> = slideshow.slides.iter().map(|s| s.order).collect();
+        assert_eq!(orders, [1, 2]);
+
+        let mut slideshow = deck(5);
+        assert_eq!(slideshow.retain_range(3..usize::MAX), 3..5);
+        assert_eq!(slideshow.slides.len(), 2);
+        let mut slideshow = deck(5);
+        assert_eq!(slideshow.retain_range(7..9), 5..5);
+        assert!(slideshow.slides.is_empty());
+    }
+
+    #[test]
+    fn test_data_uri() {
+        assert_eq!(decode_data_uri("star.jpg"), None);
+        let hello = decode_data_uri("data:text/plain;base64,aGVsbG8=");
+        assert_eq!(hello, Some(Ok(b"hello".to_vec())));
+        let bytes =
+            decode_data_uri("data:application/octet-stream;base64,-_8=");
+        assert_eq!(bytes, Some(Ok(vec![0xfb, 0xff])));
+
+        assert!(matches!(
+            decode_data_uri("data:text/plain,hello"),
+            Some(Err(_))
+        ));
+        assert!(matches!(
+            decode_data_uri("data:image/png;base64,a*b"),
+            Some(Err(_))
+        ));
+        assert!(matches!(decode_data_uri("data:image/png"), Some(Err(_))));
+    }
+
+    #[test]
+    fn test_diff() {
+        let deck = |texts: &[&str]| {
+            let text: String =
+                texts.iter().map(|t| format!(":sl\n:tb\n{t}\n")).collect();
+            crate::parser::parse_text(&text, Path::new("")).unwrap()
+        };
+        let base = deck(&["a", "b", "c", "d"]);
+        assert!(base.diff(&base).is_empty());
+        let diff = base.diff(&deck(&["a", "x", "c", "d"]));
+        assert_eq!(diff.changed, [1]);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        let diff = base.diff(&deck(&["a", "b", "new", "c", "d"]));
+        assert_eq!(
+            (diff.changed.len(), diff.added, diff.removed.len()),
+            (0, vec![2], 0)
+        );
+        let diff = base.diff(&deck(&["a", "c", "d"]));
+        assert_eq!(
+            (diff.changed.len(), diff.added.len(), diff.removed),
+            (0, 0, vec![1])
+        );
+        let diff = base.diff(&deck(&["a", "x", "y"]));
+        assert_eq!((diff.changed, diff.removed), (vec![1, 2], vec![3]));
+        // The same slides, with other defaults.
+        let mut other = deck(&["a", "b", "c", "d"]);
+        other.bg_col = Some(DEFAULT_BG_COLOR);
+        let diff = base.diff(&other);
+        assert!(diff.defaults_changed && diff.changed.is_empty());
+    }
+
+    #[test]
+    fn test_stats() {
+        let slideshow =
+            crate::parser::parse_text(README_DECK, Path::new("")).unwrap();
+        let stats = slideshow.stats();
+        assert_eq!(
+            stats,
+            SlideshowStats {
+                slides: 3,
+                words: 31,
+                words_per_slide: vec![12, 12, 7],
+            }
+        );
+        assert_eq!(stats.estimated_duration(62), Duration::from_secs(30));
+        assert_eq!(stats.estimated_duration(0), Duration::from_secs(31 * 60));
+    }
+
+    #[test]
+    fn test_slide_at_and_titles() {
+        let mut slideshow =
+            crate::parser::parse_text(README_DECK, Path::new("")).unwrap();
+        slideshow.slides.push(Slide::default());
+        assert_eq!(
+            slideshow.titles(),
+            ["BIG TITLE", "Small title now", "We can also", UNTITLED]
+        );
+        let slide = slideshow.slide_at(1).unwrap();
+        assert_eq!(slide.title(), Some("Small title now"));
+        assert!(slideshow.slide_at(4).is_none());
+    }
+
+    #[test]
+    fn test_resolved_slides() {
+        let text = ":ge :fc red\n:sl\n:tb :fc blue\nA\n:tb :sz 0.1 0.2\nB\n\
+                    :fg star.jpg\n:sl :bc green";
+        let slideshow =
+            crate::parser::parse_text(text, Path::new("./resources")).unwrap();
+        let resolved: Vec<_> = slideshow.resolved_slides().collect();
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved[0].bg_col, DEFAULT_BG_COLOR);
+        assert_eq!(resolved[1].bg_col, Color::from((0x00, 0x80, 0x00, 0xff)));
+        assert!(resolved[1].sections.is_empty());
+
+        let sections = &resolved[0].sections;
+        let blue = Color::from((0x00, 0x00, 0xff, 0xff));
+        let red = Color::from((0xff, 0x00, 0x00, 0xff));
+        assert_eq!(sections[0].font_col, Some(blue));
+        assert_eq!(sections[0].size, (&DEFAULT_FONT_SIZE).into());
+        assert_eq!(sections[1].font_col, Some(red));
+        assert_eq!(sections[1].size, (0.1, 0.2));
+        assert_eq!(sections[2].font_col, None);
+        assert_eq!(sections[2].size, (&DEFAULT_FIGURE_SIZE).into());
+
+        assert_eq!(red.to_string(), "#ff0000ff");
+    }
+
+    #[test]
+    fn test_readable_on() {
+        let white = Color::from((0xff, 0xff, 0xff, 0xff));
+        assert!((white.luminance() - 1.0).abs() < 1e-6);
+        assert!(DEFAULT_FONT_COLOR.luminance().abs() < 1e-6);
+        assert_eq!(Color::readable_on(white), DEFAULT_FONT_COLOR);
+        assert_eq!(Color::readable_on(DEFAULT_FONT_COLOR), white);
+        // Dark blue, and yellow.
+        assert_eq!(Color::readable_on(Color::from((0, 0, 0x80, 0xff))), white);
+        let yellow = Color::from((0xff, 0xff, 0, 0xff));
+        assert_eq!(Color::readable_on(yellow), DEFAULT_FONT_COLOR);
+        // Mid gray is closer to white than it looks.
+        let gray = Color::from((0x80, 0x80, 0x80, 0xff));
+        assert_eq!(Color::readable_on(gray), DEFAULT_FONT_COLOR);
+    }
+
+    #[test]
+    fn test_display() {
+        let slideshow =
+            crate::parser::parse_text(README_DECK, Path::new("")).unwrap();
+        let expected = "3 slides
+  1. BIG TITLE (2 sections)
+  2. Small title now (2 sections)
+  3. We can also (1 section)";
+        assert_eq!(slideshow.to_string(), expected);
+
+        let mut slideshow = Slideshow::default();
+        slideshow.slides.push(Slide::default());
+        assert_eq!(
+            slideshow.to_string(),
+            "1 slide\n  1. (untitled) (0 sections)"
+        );
+    }
+
+    #[test]
+    fn test_text_run_split() {
+        use TextRun::{Image, Text};
+        assert_eq!(
+            TextRun::split("a ![b](c.png) d"),
+            [
+                Text("a "),
+                Image {
+                    alt: "b",
+                    path: "c.png"
+                },
+                Text(" d")
+            ]
+        );
+        // The brackets of something else are not an image.
+        assert_eq!(
+            TextRun::split("see ![a] and [b](c)"),
+            [Text("see ![a] and [b](c)")]
+        );
+        assert_eq!(TextRun::split("![a]()"), [Text("![a]()")]);
+        // An escaped image is plain text.
+        assert_eq!(
+            TextRun::split("A \\![x](y) B ![z](w)"),
+            [
+                Text("A "),
+                Text("![x](y) B "),
+                Image {
+                    alt: "z",
+                    path: "w"
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stats_skip_inline_images() {
+        let slide = Slide {
+            bg_color: None,
+            sections: vec![Section {
+                sec_main: Some(SectionMain::Text(SectionText {
+                    text: "a ![star](star.jpg) star\n".into(),
+                    ..SectionText::default()
+                })),
+                ..Section::default()
+            }],
+            order: 0,
+            group: None,
+            counted: true,
+            grid: None,
+            tags: vec![],
+            gradient: None,
+            layout: None,
+        };
+        assert_eq!(slide.word_count(), 2);
+    }
 }
@@ -0,0 +1,202 @@
+/*!
+Keep many slideshows on the same slide, over the network.
+
+One slideshow serves the index of the slide it shows with a [`Server`], and
+the others [`follow`] it: this way the projector and the speaker's laptop
+always show the same slide, even if they are two different processes.
+
+The protocol is as simple as it gets: each time the slide changes, the server
+writes its (0 based) index in a line of text, like `3\n`. A new follower
+receives the current index as soon as it connects.
+*/
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use tracing::{debug, info, warn};
+
+/// How long writing to a follower can take: a follower that does not read
+/// in time is dropped, so that it can't freeze the slideshow.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The followers, and the last index sent to them.
+#[derive(Default)]
+struct Followers {
+    streams: Vec<TcpStream>,
+    last: Option<usize>,
+}
+
+impl Followers {
+    /// Send `idx` to all the followers, forgetting the ones that went away.
+    fn send(&mut self, idx: usize) {
+        let line = format!("{idx}\n");
+        self.streams
+            .retain_mut(|s| match s.write_all(line.as_bytes()) {
+                Ok(()) => true,
+                Err(e) => {
+                    info!("A follower went away: {}", e);
+                    false
+                }
+            });
+        self.last = Some(idx);
+    }
+}
+
+/// Broadcast the index of the current slide to whoever follows it.
+pub struct Server {
+    followers: Arc<Mutex<Followers>>,
+    addr: SocketAddr,
+}
+
+impl Server {
+    /// Start listening on `addr`, like `0.0.0.0:7878`. The followers are
+    /// accepted in a thread of their own.
+    ///
+    /// # Errors
+    ///
+    /// If the address can't be used.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let followers = Arc::new(Mutex::new(Followers::default()));
+
+        let accepted = Arc::clone(&followers);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(s) => s,
+                    Err(e) => {
+                        warn!("Unable to accept a follower: {}", e);
+                        continue;
+                    }
+                };
+                debug!("New follower: {:?}", stream.peer_addr());
+                if let Err(e) = stream.set_write_timeout(Some(WRITE_TIMEOUT)) {
+                    warn!("Unable to set up a follower: {}", e);
+                    continue;
+                }
+                let mut followers =
+                    accepted.lock().expect("The server's lock is poisoned.");
+                // Bring the newcomer to the current slide.
+                let hello = followers
+                    .last
+                    .map_or(Ok(()), |idx| writeln!(stream, "{idx}"));
+                match hello {
+                    Ok(()) => followers.streams.push(stream),
+                    Err(e) => warn!("Unable to talk to a follower: {}", e),
+                }
+            }
+        });
+
+        Ok(Self { followers, addr })
+    }
+
+    /// The address the server listens on. Useful when binding the port 0.
+    #[must_use]
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Tell the followers that the slide at `idx` is shown. Nothing is sent
+    /// if the slide did not change. The followers that don't read what is
+    /// sent to them are dropped.
+    pub fn send(&self, idx: usize) {
+        let mut followers = self
+            .followers
+            .lock()
+            .expect("The server's lock is poisoned.");
+        if followers.last != Some(idx) {
+            followers.send(idx);
+        }
+    }
+}
+
+/// Read the indexes sent by the server, one per line, and forward them.
+fn read_indexes<R: BufRead>(reader: R, tx: &Sender<usize>) {
+    for line in reader.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(e) => {
+                warn!("Unable to read from the server: {}", e);
+                break;
+            }
+        };
+        let Ok(idx) = line.trim().parse() else {
+            warn!("Unexpected message from the server: {}", line);
+            continue;
+        };
+        if tx.send(idx).is_err() {
+            // Nobody is listening anymore.
+            break;
+        }
+    }
+    info!("Not following the server anymore.");
+}
+
+/// Connect to the [`Server`] at `addr`, and receive the index of the slide
+/// it shows each time it changes.
+///
+/// # Errors
+///
+/// If the server can't be reached.
+pub fn follow<A: ToSocketAddrs>(addr: A) -> std::io::Result<Receiver<usize>> {
+    let stream = TcpStream::connect(addr)?;
+    let (tx, rx) = channel();
+    thread::spawn(move || read_indexes(BufReader::new(stream), &tx));
+    Ok(rx)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_indexes() {
+        let (tx, rx) = channel();
+        read_indexes("3\nnope\n 5 \n".as_bytes(), &tx);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![3, 5]);
+    }
+
+    #[test]
+    fn test_stalled_follower() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        // A follower that never reads.
+        let _stalled = TcpStream::connect(server.local_addr()).unwrap();
+        while server.followers.lock().unwrap().streams.is_empty() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        // Once the buffers are full, writing times out, and the follower is
+        // dropped instead of blocking the slideshow.
+        let start = std::time::Instant::now();
+        let mut idx = 0;
+        while !server.followers.lock().unwrap().streams.is_empty() {
+            server.followers.lock().unwrap().send(idx);
+            idx += 1;
+            assert!(start.elapsed() < Duration::from_secs(30));
+        }
+    }
+
+    #[test]
+    fn test_follow() {
+        let timeout = Duration::from_secs(5);
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        server.send(2);
+        // A late follower gets the current slide first.
+        let rx = follow(server.local_addr()).unwrap();
+        assert_eq!(rx.recv_timeout(timeout), Ok(2));
+
+        // The follower is accepted in another thread: wait for it.
+        while server.followers.lock().unwrap().streams.is_empty() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        server.send(2);
+        server.send(4);
+        server.send(1);
+        assert_eq!(rx.recv_timeout(timeout), Ok(4));
+        assert_eq!(rx.recv_timeout(timeout), Ok(1));
+    }
+}
@@ -0,0 +1,127 @@
+/*!
+Named themes, to restyle a slideshow without editing it.
+
+A theme sets the default colors and size of the letters of a slideshow, the
+ones that `:ge` would set. The colors and sizes given to each section still
+win.
+*/
+
+use std::str::FromStr;
+
+use crate::parser::parse_color;
+use crate::slideshow::{Color, Size, Slideshow};
+
+/// The available themes: name, background color, font color and size of the
+/// letters, as `(w, h)`.
+const THEMES: [(&str, &str, &str, (f32, f32)); 3] = [
+    ("dark", "#1e1e1eff", "#e0e0e0ff", (0.02, 0.09)),
+    ("light", "white", "black", (0.018, 0.08)),
+    ("solarized", "#002b36ff", "#839496ff", (0.016, 0.075)),
+];
+
+/// The defaults set by a theme.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// The default background color.
+    pub bg_col: Color,
+    /// The default font color.
+    pub font_col: Color,
+    /// The default size of the letters, as `(w, h)`.
+    pub font_size: (f32, f32),
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    /// Get the theme by its name, like `dark`, `light` or `solarized`.
+    fn from_str(s: &str) -> Result<Self, String> {
+        let name = s.to_lowercase();
+        let (_, bg, font, font_size) =
+            THEMES.iter().find(|(n, ..)| *n == name).ok_or_else(|| {
+                let names: Vec<_> = THEMES.iter().map(|t| t.0).collect();
+                format!("Unknown theme {s}, use one of {}", names.join(", "))
+            })?;
+        let color = |c: &str| parse_color(c).map_err(|e| e.to_string());
+        Ok(Self {
+            bg_col: color(bg)?,
+            font_col: color(font)?,
+            font_size: *font_size,
+        })
+    }
+}
+
+impl Theme {
+    /// Set the defaults of the slideshow that it does not set already. With
+    /// `force`, the slideshow's colors are replaced as well: its size of the
+    /// letters is kept on purpose, since it is chosen for the content of the
+    /// slides, that a theme knows nothing about.
+    pub const fn apply(&self, slideshow: &mut Slideshow, force: bool) {
+        if force || (slideshow.bg_col.is_none() && slideshow.gradient.is_none())
+        {
+            slideshow.bg_col = Some(self.bg_col);
+            slideshow.gradient = None;
+        }
+        if force || slideshow.font_col.is_none() {
+            slideshow.font_col = Some(self.font_col);
+        }
+        if slideshow.font_size.is_none() {
+            let (w, h) = self.font_size;
+            slideshow.font_size = Some(Size { w, h });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_themes() {
+        for (name, ..) in THEMES {
+            assert!(name.parse::<Theme>().is_ok(), "{name}");
+        }
+        assert!("Dark".parse::<Theme>().is_ok());
+        assert!("neon".parse::<Theme>().is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let text = ":ge :bc green :sz 0.02 0.1\n:sl\n:tb :fc red\nHello";
+        let theme: Theme = "light".parse().unwrap();
+        let white = Color::from((0xff, 0xff, 0xff, 0xff));
+        let black = Color::from((0x00, 0x00, 0x00, 0xff));
+        let green = Color::from((0x00, 0x80, 0x00, 0xff));
+
+        let mut slides =
+            crate::parser::parse_text(text, Path::new("")).unwrap();
+        theme.apply(&mut slides, false);
+        assert_eq!(slides.bg_col, Some(green));
+        assert_eq!(slides.font_col, Some(black));
+        let font_size = Some(Size { w: 0.02, h: 0.1 });
+        assert_eq!(slides.font_size, font_size);
+
+        theme.apply(&mut slides, true);
+        assert_eq!(slides.bg_col, Some(white));
+        // Even forced, the slideshow's size of the letters is kept.
+        assert_eq!(slides.font_size, font_size);
+        // The section's own color still wins.
+        let Some(crate::slideshow::SectionMain::Text(text)) =
+            &slides.slides[0].sections[0].sec_main
+        else {
+            panic!("Expected a text section");
+        };
+        assert_eq!(text.color, Some(Color::from((0xff, 0x00, 0x00, 0xff))));
+    }
+
+    #[test]
+    fn test_apply_font_size() {
+        let text = ":ge :bc green\n:sl\n:tb\nHello";
+        let mut slides =
+            crate::parser::parse_text(text, Path::new("")).unwrap();
+        assert_eq!(slides.font_size, None);
+        let theme: Theme = "dark".parse().unwrap();
+        theme.apply(&mut slides, false);
+        assert_eq!(slides.font_size, Some(Size { w: 0.02, h: 0.09 }));
+    }
+}
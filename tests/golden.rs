@@ -0,0 +1,189 @@
+//! Golden image tests for the SDL renderer: a few slides are drawn offscreen,
+//! and compared to the images in `tests/golden/`.
+//!
+//! They need a working SDL, with SDL_ttf and SDL_image, so they only run with
+//! the `golden` feature: `cargo test --features golden --test golden`.
+//!
+//! When the drawing changes on purpose, write the images again with
+//! `SLIDY_UPDATE_GOLDEN=1 cargo test --features golden --test golden`, and
+//! check them before committing them. When a test fails, the image that was
+//! drawn is saved in the target folder, next to the message that says where.
+//! A test with no image to compare to is skipped, with a message that says
+//! how to write it.
+
+use std::path::{Path, PathBuf};
+
+use sdl2::image::{LoadSurface, SaveSurface};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::surface::Surface;
+
+use slidy::backends::render_slide_to_rgba;
+use slidy::parser::parse_text;
+
+/// The size of the images.
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 240;
+
+/// How much a channel of a pixel can change before the pixel is different:
+/// the fonts are not drawn exactly the same by all the versions of SDL_ttf.
+const TOLERANCE: u8 = 8;
+
+/// How many pixels can be different before the test fails.
+const MAX_DIFFERENT_PIXELS: usize = 16;
+
+/// The variable to set to write the images again.
+const UPDATE_VAR: &str = "SLIDY_UPDATE_GOLDEN";
+
+/// Where the image called `name` is.
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{name}.png"))
+}
+
+/// Save the RGBA pixels as a PNG image.
+fn save_png(pixels: &[u8], path: &Path) -> Result<(), String> {
+    let mut pixels = pixels.to_vec();
+    let surface = Surface::from_data(
+        &mut pixels,
+        WIDTH,
+        HEIGHT,
+        WIDTH * 4,
+        PixelFormatEnum::RGBA32,
+    )?;
+    surface.save(path)
+}
+
+/// Load a PNG image, as RGBA pixels, with its size.
+fn load_png(path: &Path) -> Result<(Vec<u8>, (u32, u32)), String> {
+    let surface =
+        Surface::from_file(path)?.convert_format(PixelFormatEnum::RGBA32)?;
+    let (width, height) = surface.size();
+    let row = width as usize * 4;
+    let pitch = surface.pitch() as usize;
+    let pixels = surface.with_lock(|pixels| {
+        pixels
+            .chunks(pitch)
+            .take(height as usize)
+            .flat_map(|line| &line[..row])
+            .copied()
+            .collect()
+    });
+    Ok((pixels, (width, height)))
+}
+
+/// Compare the pixels, and say how they differ, if they do too much.
+fn compare(actual: &[u8], expected: &[u8]) -> Result<(), String> {
+    if actual.len() != expected.len() {
+        return Err(format!(
+            "The images have {} and {} bytes.",
+            actual.len(),
+            expected.len()
+        ));
+    }
+    let mut different = 0;
+    let mut worst = 0;
+    for (a, e) in actual.chunks(4).zip(expected.chunks(4)) {
+        let diff = a.iter().zip(e).map(|(a, e)| a.abs_diff(*e)).max();
+        let diff = diff.unwrap_or_default();
+        worst = worst.max(diff);
+        if diff > TOLERANCE {
+            different += 1;
+        }
+    }
+    if different > MAX_DIFFERENT_PIXELS {
+        return Err(format!(
+            "{different} pixels are different (at most \
+             {MAX_DIFFERENT_PIXELS}), by up to {worst}."
+        ));
+    }
+    Ok(())
+}
+
+/// Draw the first slide of `slides`, and compare it to the image `name`.
+fn check(name: &str, slides: &str) {
+    let base = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources");
+    let slideshow = parse_text(slides, &base).unwrap();
+    let _image_context = sdl2::image::init(sdl2::image::InitFlag::PNG).unwrap();
+    let actual = render_slide_to_rgba(&slideshow, 0, WIDTH, HEIGHT).unwrap();
+
+    let path = golden_path(name);
+    if std::env::var_os(UPDATE_VAR).is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        save_png(&actual, &path).unwrap();
+        return;
+    }
+    if !path.exists() {
+        eprintln!(
+            "Skipping {name}: there is no {}. Run the test with \
+             {UPDATE_VAR}=1 to write it.",
+            path.display()
+        );
+        return;
+    }
+    let (expected, size) = load_png(&path)
+        .unwrap_or_else(|e| panic!("Unable to load {}: {e}.", path.display()));
+    assert_eq!(size, (WIDTH, HEIGHT), "{} has another size", path.display());
+    if let Err(e) = compare(&actual, &expected) {
+        let drawn = Path::new(env!("CARGO_TARGET_TMPDIR"))
+            .join(format!("{name}.actual.png"));
+        let saved = save_png(&actual, &drawn).map_or_else(
+            |e| format!("not saved: {e}"),
+            |()| format!("saved in {}", drawn.display()),
+        );
+        panic!(
+            "{name} drifted from {}: {e} The drawn image is {saved}.",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn golden_text() {
+    check(
+        "text",
+        ":ge :bc navy :fc white\n:sl\n:tb :ps 0.1 0.1 :sz 0.08\nHello\n\
+         :tb :ps 0.1 0.5 :fc yellow\nA second line\n",
+    );
+}
+
+#[test]
+fn golden_figure() {
+    check(
+        "figure",
+        ":ge :bc white\n:sl\n:fg star.jpg :ps 0.25 0.25 :sz 0.5 0.5 :rt 30\n",
+    );
+}
+
+#[test]
+fn golden_code() {
+    check(
+        "code",
+        ":ge :bc silver\n:sl\n:code rust\n\
+         fn main() {\n    println!(\"Hi\");\n}\n",
+    );
+}
+
+#[test]
+fn golden_section_background() {
+    check(
+        "section_background",
+        ":sl :bc teal\n:tb :ps 0.2 0.4 :sbc yellow :fc black\nA callout\n",
+    );
+}
+
+#[test]
+fn golden_compare() {
+    let black = [0, 0, 0, 255].repeat(32);
+    let mut close = black.clone();
+    close[0] = TOLERANCE;
+    assert!(compare(&close, &black).is_ok());
+
+    let mut far = black.clone();
+    for pixel in far.chunks_mut(4).take(MAX_DIFFERENT_PIXELS + 1) {
+        pixel[1] = 200;
+    }
+    assert!(compare(&far, &black).is_err());
+    assert!(compare(&black[4..], &black).is_err());
+}